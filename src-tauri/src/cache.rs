@@ -0,0 +1,234 @@
+//! Results of earlier runs, so resubmitting the same code for the same
+//! exercise answers straight away. Entries are keyed on everything that
+//! can change the result and kept between app runs in the app data dir.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::runner::{LanguageRunner, ResourceLimits};
+use crate::{CodeValidationResponse, Grading, Outcome};
+
+/// Most entries kept; the least recently used go first.
+const CAPACITY: usize = 500;
+/// Runs that printed more than this are not worth keeping.
+const MAX_ENTRY_OUTPUT: usize = 64 * 1024;
+/// Saving waits this long so a burst of runs is written once.
+const SAVE_DELAY: Duration = Duration::from_secs(5);
+const FILE_NAME: &str = "run-cache.json";
+
+static CACHE: OnceLock<ResultCache> = OnceLock::new();
+
+pub struct ResultCache {
+    file: PathBuf,
+    entries: Mutex<Entries>,
+    save_scheduled: AtomicBool,
+    /// Held while the file is written or removed, so a save that is
+    /// under way can't put back entries `clear` just removed.
+    file_lock: Mutex<()>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Entries {
+    /// Bumped on every hit and insert; the entry with the lowest `used`
+    /// is the least recently used.
+    clock: u64,
+    map: HashMap<String, Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    used: u64,
+    response: CodeValidationResponse,
+}
+
+/// Loads the cache saved in `dir`. Until this runs nothing is cached.
+pub fn init(dir: PathBuf) {
+    let file = dir.join(FILE_NAME);
+    let entries = fs::read(&file)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Entries>(&bytes).ok())
+        .unwrap_or_default();
+    println!(
+        "Run cache: {} entries in {}",
+        entries.map.len(),
+        file.display()
+    );
+
+    let _ = CACHE.set(ResultCache {
+        file,
+        entries: Mutex::new(entries),
+        save_scheduled: AtomicBool::new(false),
+        file_lock: Mutex::new(()),
+    });
+}
+
+pub fn get() -> Option<&'static ResultCache> {
+    CACHE.get()
+}
+
+/// Identifies a run: the runner and its toolchain version, the code, its
+/// input, its limits and how the result is graded. The nonce of unit-test
+/// grading is left out, it never shows in the response.
+pub fn key(
+    runner: &dyn LanguageRunner,
+    version: Option<&str>,
+    code: &str,
+    stdin: Option<&str>,
+    limits: &ResourceLimits,
+    grading: &Grading,
+) -> String {
+    let grading = match grading {
+        Grading::Output {
+            expected,
+            comparison,
+        } => serde_json::json!({ "expected": expected, "comparison": comparison }),
+        Grading::UnitTests { tests, .. } => serde_json::json!({ "tests": tests }),
+        Grading::RunOnly => serde_json::Value::Null,
+    };
+    let identity = serde_json::json!({
+        "runner": runner.display_name(),
+        "version": version,
+        "code": code,
+        "stdin": stdin,
+        "limits": limits,
+        "grading": grading,
+    });
+
+    Sha256::digest(identity.to_string().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+impl ResultCache {
+    pub fn lookup(&self, key: &str) -> Option<CodeValidationResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+        let entry = entries.map.get_mut(key)?;
+        entry.used = clock;
+        Some(entry.response.clone())
+    }
+
+    /// Keeps the response if it says something about the code itself.
+    /// Timeouts, missing toolchains, sandbox failures and cancelled runs
+    /// depend on the machine and the moment, so they are run again.
+    pub fn store(&'static self, key: String, response: &CodeValidationResponse) {
+        let lasting = matches!(
+            response.outcome,
+            Outcome::Passed | Outcome::WrongAnswer | Outcome::RuntimeError | Outcome::CompileError
+        );
+        if !lasting || response.stdout.len() + response.stderr.len() > MAX_ENTRY_OUTPUT {
+            return;
+        }
+
+        {
+            let mut entries = self.entries.lock().unwrap();
+            entries.clock += 1;
+            let used = entries.clock;
+            entries.map.insert(
+                key,
+                Entry {
+                    used,
+                    response: response.clone(),
+                },
+            );
+            while entries.map.len() > CAPACITY {
+                let oldest = entries
+                    .map
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.map.remove(&oldest);
+                }
+            }
+        }
+        self.schedule_save();
+    }
+
+    /// Forgets every entry, the saved ones included.
+    pub fn clear(&self) {
+        let _file = self.file_lock.lock().unwrap();
+        self.entries.lock().unwrap().map.clear();
+        if let Err(e) = fs::remove_file(&self.file) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                println!("Run cache not removed: {}", e);
+            }
+        }
+    }
+
+    fn schedule_save(&'static self) {
+        if self.save_scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        tokio::spawn(async move {
+            tokio::time::sleep(SAVE_DELAY).await;
+            self.save_scheduled.store(false, Ordering::SeqCst);
+            let _ = tokio::task::spawn_blocking(move || self.save()).await;
+        });
+    }
+
+    /// Writes to a temporary file first, so a crash mid-write leaves the
+    /// previous cache intact.
+    fn save(&self) {
+        let _file = self.file_lock.lock().unwrap();
+        let json = match serde_json::to_vec(&*self.entries.lock().unwrap()) {
+            Ok(json) => json,
+            Err(e) => return println!("Run cache not saved: {}", e),
+        };
+        let partial = self.file.with_extension("json.tmp");
+        let saved = self
+            .file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&partial, json))
+            .and_then(|_| fs::rename(&partial, &self.file));
+        if let Err(e) = saved {
+            println!("Run cache not saved: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::languages;
+    use crate::runner::Comparison;
+
+    #[test]
+    fn key_ignores_the_nonce_but_not_the_grading() {
+        let python = languages::find("python").unwrap();
+        let limits = ResourceLimits::default();
+        let comparison = Comparison::default();
+        let key = |grading: &Grading| {
+            key(
+                python,
+                Some("Python 3.12.1"),
+                "print(1)",
+                None,
+                &limits,
+                grading,
+            )
+        };
+        let tests = |nonce: &str| Grading::UnitTests {
+            tests: "def test_one(): pass",
+            nonce: nonce.to_string(),
+        };
+        let output = |expected| Grading::Output {
+            expected,
+            comparison: &comparison,
+        };
+
+        assert_eq!(key(&tests("a")), key(&tests("b")));
+        assert_ne!(key(&output("1")), key(&output("2")));
+        assert_ne!(key(&output("1")), key(&Grading::RunOnly));
+    }
+}
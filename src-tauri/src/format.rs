@@ -0,0 +1,199 @@
+//! Formatting code in the editor with the language's usual formatter.
+//! The formatter gets the code on stdin inside the same sandbox as
+//! `validate_code`: it parses code nobody has checked yet.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::pool;
+use crate::runner::languages::{self, Formatter, LanguageRunner};
+use crate::runner::process::{self, Observer};
+use crate::runner::toolchain::{self, Toolchain};
+use crate::runner::{Diagnostic, ResourceLimits, Sandbox};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormatResponse {
+    /// `None` when the formatter rejected the code; `diagnostics` then say
+    /// why.
+    formatted: Option<String>,
+    changed: bool,
+    /// Name and version of the formatter that ran.
+    formatter: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Formats `code` with the first of the language's formatters that is
+/// installed: black or ruff for Python, prettier for JavaScript and
+/// TypeScript, rustfmt for Rust.
+#[tauri::command]
+pub async fn format_code(language: String, code: String) -> Result<FormatResponse, String> {
+    let runner =
+        languages::find(&language).ok_or_else(|| format!("Unsupported language: {}", language))?;
+    let (formatter, toolchain) = locate(runner).await?;
+
+    // Formatting is as heavy as compiling, and just as little up to the
+    // lesson.
+    let limits = ResourceLimits::compiler();
+    let _permit = pool::acquire(None).await;
+    let sandbox = Sandbox::new(limits.clone())?;
+    let mut cmd = sandbox.command(&toolchain.path);
+    cmd.args(formatter.args);
+
+    let output = process::run(cmd, &limits, Some(&code), Observer::default())
+        .await
+        .map_err(|e| format!("Nie można uruchomić {}: {}", formatter.name, e))?;
+    if output.timed_out {
+        return Err(format!(
+            "{} nie zakończył formatowania w ciągu {} s",
+            formatter.name,
+            limits.timeout().as_secs()
+        ));
+    }
+
+    // rustfmt and black name themselves in `--version`, prettier doesn't.
+    let name = match toolchain.version {
+        Some(version) if version.starts_with(formatter.name) => version,
+        Some(version) => format!("{} {}", formatter.name, version),
+        None => formatter.name.to_string(),
+    };
+    // Cut-off code must never replace what the learner wrote.
+    if output.output_truncated {
+        return Ok(FormatResponse {
+            formatted: None,
+            changed: false,
+            formatter: name,
+            diagnostics: vec![Diagnostic {
+                line: None,
+                column: None,
+                message: format!(
+                    "Sformatowany kod przekracza limit {} KB",
+                    limits.max_output_bytes / 1024
+                ),
+            }],
+        });
+    }
+    if !output.status.is_some_and(|status| status.success()) {
+        return Ok(FormatResponse {
+            formatted: None,
+            changed: false,
+            formatter: name,
+            diagnostics: parse_errors(&String::from_utf8_lossy(&output.stderr)),
+        });
+    }
+
+    let formatted = String::from_utf8_lossy(&output.stdout).to_string();
+    Ok(FormatResponse {
+        changed: formatted != code,
+        formatted: Some(formatted),
+        formatter: name,
+        diagnostics: Vec::new(),
+    })
+}
+
+/// The first of the runner's formatters that is installed. Lookups are
+/// cached like the toolchains themselves.
+async fn locate(
+    runner: &'static dyn LanguageRunner,
+) -> Result<(&'static Formatter, Toolchain), String> {
+    let formatters = runner.formatters();
+    if formatters.is_empty() {
+        return Err(format!(
+            "Formatowanie nie jest obsługiwane dla {}",
+            runner.display_name()
+        ));
+    }
+
+    tokio::task::spawn_blocking(move || {
+        formatters
+            .iter()
+            .find_map(|formatter| {
+                toolchain::cached(
+                    formatter.name,
+                    || runner.locate_formatter(formatter),
+                    &["--version"],
+                )
+                .ok()
+                .map(|toolchain| (formatter, toolchain))
+            })
+            .ok_or_else(|| {
+                let names: Vec<_> = formatters.iter().map(|formatter| formatter.name).collect();
+                format!(
+                    "Nie znaleziono formatera. Zainstaluj jeden z programów: {}",
+                    names.join(", ")
+                )
+            })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Every formatter reports a syntax error on a line of its own, starting
+/// with `error` (black, ruff, rustfmt) or `[error]` (prettier), with the
+/// location in that line or in a ` --> <stdin>:L:C` line right after it.
+fn parse_errors(stderr: &str) -> Vec<Diagnostic> {
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    let location = LOCATION.get_or_init(|| Regex::new(r"(\d+):(\d+)").unwrap());
+
+    let lines: Vec<&str> = stderr.lines().collect();
+    let mut diagnostics = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let message = match line.strip_prefix("[error] ") {
+            Some(message) if message.contains("Error") => message,
+            Some(_) => continue,
+            None if line.starts_with("error") => line,
+            None => continue,
+        };
+        let message = message.strip_prefix("error: ").unwrap_or(message);
+        let pointer = lines
+            .get(index + 1)
+            .filter(|next| next.trim_start().starts_with("-->"));
+        let found = pointer
+            .and_then(|pointer| location.captures(pointer))
+            .or_else(|| location.captures(message));
+
+        diagnostics.push(Diagnostic {
+            line: found.as_ref().and_then(|found| found[1].parse().ok()),
+            column: found.as_ref().and_then(|found| found[2].parse().ok()),
+            message: message.trim().to_string(),
+        });
+    }
+
+    if diagnostics.is_empty() && !stderr.trim().is_empty() {
+        diagnostics.push(Diagnostic {
+            line: None,
+            column: None,
+            message: stderr.trim().to_string(),
+        });
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_formatter_errors() {
+        let outputs = [
+            "error: cannot format -: Cannot parse for target version Python 3.12: 2:6: print(\n",
+            "error: Failed to parse at 2:6: Expected ')', found newline\n",
+            "[error] main.js: SyntaxError: Unexpected token (2:6)\n[error] > 2 | print(\n[error]     |      ^\n",
+            "error: this file contains an unclosed delimiter\n --> <stdin>:2:6\n  |\n",
+        ];
+
+        for stderr in outputs {
+            let diagnostics = parse_errors(stderr);
+            assert_eq!(diagnostics.len(), 1, "{}", stderr);
+            assert_eq!(
+                (diagnostics[0].line, diagnostics[0].column),
+                (Some(2), Some(6))
+            );
+        }
+        assert_eq!(
+            parse_errors("[error] main.js: SyntaxError: Unexpected token (2:6)\n")[0].message,
+            "main.js: SyntaxError: Unexpected token (2:6)"
+        );
+    }
+}
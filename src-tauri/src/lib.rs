@@ -1,38 +1,58 @@
 use serde::{Deserialize, Serialize};
-
+use tauri::{Manager, State, Window};
+
+mod cache;
+mod format;
+mod markup;
+mod pool;
+mod projects;
+mod repl;
+mod runner;
+mod runs;
+mod solutions;
 mod supabase;
-use supabase::{get_supabase_config, SupabaseClient};
+use runner::hints::{self, Hint};
+use runner::languages::{self, Engine, Execution, ToolchainStatus};
+use runner::process::Observer;
+use runner::{
+    process, unit_tests, Comparison, Confinement, Diagnostic, DiffLine, LanguageRunner, LimitKind,
+    ResourceLimits, Sandbox, UnitTestResult,
+};
+use supabase::session::{self, Session};
+use supabase::store::{self, SessionStore};
+use supabase::{SignOutScope, SupabaseClient, SupabaseError};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AuthResponse {
     success: bool,
     message: String,
     user_id: Option<String>,
-    access_token: Option<String>,
-    refresh_token: Option<String>,
-}
-
-fn create_supabase_client() -> Result<SupabaseClient, String> {
-    let (url, anon_key) = get_supabase_config()?;
-    Ok(SupabaseClient::new(url, anon_key))
+    /// Whether the backend now holds a session for the user; the tokens
+    /// themselves stay here.
+    signed_in: bool,
 }
 
 #[tauri::command]
-async fn login_user(email: String, password: String) -> Result<AuthResponse, String> {
+async fn login_user(
+    client: State<'_, SupabaseClient>,
+    email: String,
+    password: String,
+) -> Result<AuthResponse, String> {
     println!("Login attempt: email={}", email);
 
-    let client = create_supabase_client()?;
-
     match client.sign_in(&email, &password).await {
         Ok(auth_response) => {
             println!("Login successful for user: {}", auth_response.user.id);
+            client
+                .session()
+                .set(Session::from_auth(&auth_response))
+                .await;
 
             Ok(AuthResponse {
                 success: true,
                 message: "Zalogowano pomyślnie".to_string(),
                 user_id: Some(auth_response.user.id),
-                access_token: auth_response.access_token,
-                refresh_token: auth_response.refresh_token,
+                signed_in: true,
             })
         }
         Err(e) => {
@@ -42,23 +62,46 @@ async fn login_user(email: String, password: String) -> Result<AuthResponse, Str
                 success: false,
                 message: format!("Błąd logowania: {}", e),
                 user_id: None,
-                access_token: None,
-                refresh_token: None,
+                signed_in: false,
             })
         }
     }
 }
 
+/// Signs the user out of Supabase, on every device when `everywhere` is
+/// set, and drops what this machine kept for them: the saved session and
+/// the results of their earlier runs.
+#[tauri::command]
+async fn logout_user(
+    client: State<'_, SupabaseClient>,
+    everywhere: Option<bool>,
+) -> Result<(), SupabaseError> {
+    let scope = if everywhere.unwrap_or(false) {
+        SignOutScope::Global
+    } else {
+        SignOutScope::Local
+    };
+    println!("Logout requested: scope={:?}", scope);
+
+    let revoked = client.session().sign_out(&client, scope).await;
+    if let Some(cache) = cache::get() {
+        cache.clear();
+    }
+    if let Err(e) = &revoked {
+        println!("Session not revoked: {}", e);
+    }
+    revoked
+}
+
 #[tauri::command]
 async fn register_user(
+    client: State<'_, SupabaseClient>,
     email: String,
     password: String,
     username: String,
 ) -> Result<AuthResponse, String> {
     println!("Register attempt: email={}, username={}", email, username);
 
-    let client = create_supabase_client()?;
-
     match client.sign_up(&email, &password, &username).await {
         Ok(auth_response) => {
             println!(
@@ -66,12 +109,19 @@ async fn register_user(
                 auth_response.user.id
             );
 
+            // Without email confirmation Supabase signs the user in right away.
+            let session = Session::from_auth(&auth_response);
+            let signed_in = session.is_some();
+            if signed_in {
+                client.session().set(session).await;
+            }
+
             let message = if auth_response.user.confirmation_sent_at.is_some() {
                 format!(
                     "Konto {} zostało utworzone! Sprawdź swojego maila i potwierdź adres email.",
                     username
                 )
-            } else if auth_response.access_token.is_some() {
+            } else if signed_in {
                 format!("Konto {} zostało utworzone pomyślnie!", username)
             } else {
                 format!(
@@ -84,8 +134,7 @@ async fn register_user(
                 success: true,
                 message,
                 user_id: Some(auth_response.user.id),
-                access_token: auth_response.access_token,
-                refresh_token: auth_response.refresh_token,
+                signed_in,
             })
         }
         Err(e) => {
@@ -95,24 +144,20 @@ async fn register_user(
                 success: false,
                 message: format!("Błąd rejestracji: {}", e),
                 user_id: None,
-                access_token: None,
-                refresh_token: None,
+                signed_in: false,
             })
         }
     }
 }
 
 #[tauri::command]
-async fn google_sign_in() -> Result<String, String> {
-    let client = create_supabase_client()?;
+async fn google_sign_in(client: State<'_, SupabaseClient>) -> Result<String, SupabaseError> {
     client.sign_in_with_google().await
 }
 
 #[tauri::command]
-async fn check_is_admin(access_token: String) -> Result<bool, String> {
-    let client = create_supabase_client()?;
-
-    let response = client.get_user_profile_with_role(&access_token).await?;
+async fn check_is_admin(client: State<'_, SupabaseClient>) -> Result<bool, SupabaseError> {
+    let response = client.get_user_profile_with_role().await?;
 
     if let Some(role) = response.get("role").and_then(|r| r.as_str()) {
         Ok(role == "admin" || role == "super_admin")
@@ -128,219 +173,595 @@ pub struct CodeValidationRequest {
     expected_output: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// How a validation run ended, from the learner's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Passed,
+    WrongAnswer,
+    RuntimeError,
+    CompileError,
+    Timeout,
+    LimitExceeded,
+    ToolchainMissing,
+    /// The sandbox could not be set up or the program could not be started.
+    SandboxError,
+    /// Stopped by the learner through `cancel_run`.
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeValidationResponse {
+    /// The program ran and exited successfully, whatever it printed.
     success: bool,
+    outcome: Outcome,
     output: String,
+    stdout: String,
+    /// Kept separately: warnings on stderr don't fail an otherwise
+    /// successful run.
+    stderr: String,
+    /// `None` when the program was killed by a signal or timed out.
+    exit_code: Option<i32>,
+    duration_ms: u64,
     error: Option<String>,
     is_correct: bool,
+    timed_out: bool,
+    limit_exceeded: Option<LimitKind>,
+    /// Compiler output when the code did not build; `error` then only
+    /// says that compilation failed.
+    compile_error: Option<String>,
+    diagnostics: Vec<Diagnostic>,
+    /// Explanations of what went wrong, for runs that failed.
+    hints: Vec<Hint>,
+    /// Expected vs actual output, line by line; empty when the output is
+    /// correct or the program never got to print it.
+    diff: Vec<DiffLine>,
+    /// One entry per hidden test when the exercise is graded by unit tests.
+    tests: Vec<UnitTestResult>,
+    confinement: Confinement,
+    /// Answered from the cache of earlier runs; nothing ran this time.
+    #[serde(default)]
+    cached: bool,
 }
 
+impl CodeValidationResponse {
+    /// A run that never produced any output.
+    fn failure(outcome: Outcome, error: String, confinement: &Confinement) -> Self {
+        Self {
+            success: false,
+            outcome,
+            output: String::new(),
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: None,
+            duration_ms: 0,
+            error: Some(error),
+            is_correct: false,
+            timed_out: false,
+            limit_exceeded: None,
+            compile_error: None,
+            diagnostics: Vec::new(),
+            hints: Vec::new(),
+            diff: Vec::new(),
+            tests: Vec::new(),
+            confinement: confinement.clone(),
+            cached: false,
+        }
+    }
+
+    /// Copies what the process printed and how it ended; the caller
+    /// decides the outcome.
+    fn from_run(outcome: Outcome, output: &process::RunOutput, confinement: &Confinement) -> Self {
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        Self {
+            success: false,
+            outcome,
+            output: stdout.trim().to_string(),
+            stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.and_then(|status| status.code()),
+            duration_ms: output.duration.as_millis() as u64,
+            error: None,
+            is_correct: false,
+            timed_out: output.timed_out,
+            limit_exceeded: None,
+            compile_error: None,
+            diagnostics: Vec::new(),
+            hints: Vec::new(),
+            diff: Vec::new(),
+            tests: Vec::new(),
+            confinement: confinement.clone(),
+            cached: false,
+        }
+    }
+}
+
+/// What decides whether a program that exited cleanly solved the exercise.
+enum Grading<'a> {
+    /// Its output, compared with the expected one.
+    Output {
+        expected: &'a str,
+        comparison: &'a Comparison,
+    },
+    /// The exercise's hidden tests, run after the learner's code.
+    UnitTests { tests: &'a str, nonce: String },
+    /// Nothing to check, as in the playground: a clean exit is a pass.
+    RunOnly,
+}
+
+/// Checks the code against `expected_output`, or against the exercise's
+/// hidden `tests` when there are any. While the run waits for a free place
+/// its position is emitted as `run-queued` events tagged with `request_id`;
+/// a second identical request from the same window shares the first one's
+/// result.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn validate_code(
+    window: Window,
     code: String,
     language: String,
     expected_output: String,
+    limits: Option<ResourceLimits>,
+    comparison: Option<Comparison>,
+    tests: Option<String>,
+    engine: Option<Engine>,
+    request_id: Option<String>,
 ) -> Result<CodeValidationResponse, String> {
-    match language.as_str() {
-        "python" => validate_python_code(code, expected_output).await,
-        "javascript" => validate_javascript_code(code, expected_output).await,
-        "typescript" => validate_typescript_code(code, expected_output).await,
-        _ => Err(format!("Unsupported language: {}", language)),
-    }
+    let runner = languages::select(&language, engine.unwrap_or_default())?;
+    let limits = limits.unwrap_or_default().clamped();
+    let comparison = comparison.unwrap_or_default();
+    let request = serde_json::json!({
+        "code": code,
+        "runner": runner.display_name(),
+        "expected": expected_output,
+        "limits": limits,
+        "comparison": comparison,
+        "tests": tests,
+    })
+    .to_string();
+    let grading = match tests.as_deref().filter(|tests| !tests.trim().is_empty()) {
+        Some(tests) => Grading::UnitTests {
+            tests,
+            nonce: unit_tests::nonce(),
+        },
+        None => Grading::Output {
+            expected: &expected_output,
+            comparison: &comparison,
+        },
+    };
+    let observer = Observer {
+        queued: Some(pool::report_position(window.clone(), request_id)),
+        ..Observer::default()
+    };
+    pool::deduplicate(
+        window.label(),
+        request,
+        run_validation(code, runner, None, limits, &grading, observer),
+    )
+    .await
 }
-//RCE, bezpieczeństwo tego jest żadne, ale wywalone XD
-async fn validate_python_code(
+
+async fn run_validation(
     code: String,
-    expected_output: String,
+    runner: &'static dyn LanguageRunner,
+    stdin: Option<String>,
+    limits: ResourceLimits,
+    grading: &Grading<'_>,
+    observer: Observer,
 ) -> Result<CodeValidationResponse, String> {
-    use std::process::Command;
-
-    let output = Command::new("python").arg("-c").arg(&code).output();
-
-    match output {
-        Ok(result) => {
-            let stdout = String::from_utf8_lossy(&result.stdout).trim().to_string();
-            let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
-
-            if !stderr.is_empty() {
-                return Ok(CodeValidationResponse {
-                    success: false,
-                    output: stderr.clone(),
-                    error: Some(stderr),
-                    is_correct: false,
-                });
-            }
-
-            let is_correct = stdout == expected_output;
-
-            Ok(CodeValidationResponse {
-                success: true,
-                output: stdout,
-                error: None,
-                is_correct,
-            })
+    let submitted = code;
+    let code = match grading {
+        Grading::UnitTests { tests, nonce } => {
+            runner.with_tests(&submitted, tests, nonce).ok_or_else(|| {
+                format!(
+                    "Testy jednostkowe nie są obsługiwane dla {}",
+                    runner.display_name()
+                )
+            })?
         }
-        Err(e) => Ok(CodeValidationResponse {
-            success: false,
-            output: String::new(),
-            error: Some(format!(
-                "Nie można uruchomić Pythona: {}. Upewnij się, że Python jest zainstalowany.",
-                e
-            )),
-            is_correct: false,
+        Grading::Output { .. } | Grading::RunOnly => submitted.clone(),
+    };
+
+    // Streamed runs always run: the learner watches the output arrive.
+    let cache_key = match (cache::get(), &observer.output) {
+        (Some(_), None) => languages::locate(runner).await.ok().map(|toolchain| {
+            cache::key(
+                runner,
+                toolchain.version.as_deref(),
+                &submitted,
+                stdin.as_deref(),
+                &limits,
+                grading,
+            )
         }),
-    }
-}
-async fn validate_typescript_code(
-    code: String,
-    expected_output: String,
-) -> Result<CodeValidationResponse, String> {
-    use std::fs;
-    use std::path::PathBuf;
-    use std::process::Command;
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis();
-    let pid = std::process::id();
-    let filename = format!("validate_ts_{}_{}.ts", pid, now);
-
-    let mut tmp_path: PathBuf = std::env::temp_dir();
-    tmp_path.push(filename);
-
-    if let Err(e) = fs::write(&tmp_path, code.as_bytes()) {
+        _ => None,
+    };
+    if let Some(cached) = cache_key
+        .as_deref()
+        .and_then(|key| cache::get()?.lookup(key))
+    {
         return Ok(CodeValidationResponse {
-            success: false,
-            output: String::new(),
-            error: Some(format!("Nie można zapisać pliku tymczasowego: {}", e)),
-            is_correct: false,
+            cached: true,
+            ..cached
         });
     }
 
-    fn run_with_file(cmd: &str, file: &PathBuf) -> std::io::Result<std::process::Output> {
-        Command::new(cmd)
-            .env("TS_NODE_TRANSPILE_ONLY", "true")
-            .env(
-                "TS_NODE_COMPILER_OPTIONS",
-                r#"{"module":"commonjs","target":"es2019"}"#,
-            )
-            .arg(file)
-            .output()
+    let _permit = pool::acquire(observer.queued.clone()).await;
+    let sandbox = match Sandbox::new(limits.clone()) {
+        Ok(sandbox) => sandbox,
+        Err(e) => return Ok(sandbox_error(e)),
+    };
+
+    let execution =
+        languages::execute(runner, &sandbox, &code, stdin.as_deref(), &limits, observer).await;
+    let mut response =
+        response_from_execution(execution, runner, grading, &limits, sandbox.confinement());
+    if !response.is_correct {
+        response.hints = hints::explain(runner.language(), &submitted, &response.diagnostics);
     }
+    if let (Some(cache), Some(key)) = (cache::get(), cache_key) {
+        cache.store(key, &response);
+    }
+    Ok(response)
+}
 
-    let mut candidates: Vec<&str> = vec!["ts-node"];
-    if cfg!(target_os = "windows") {
-        candidates.extend_from_slice(&[
-            "ts-node.cmd",
-            ".\\node_modules\\.bin\\ts-node.cmd",
-            "..\\node_modules\\.bin\\ts-node.cmd",
-            "..\\..\\node_modules\\.bin\\ts-node.cmd",
-        ]);
-    } else {
-        candidates.extend_from_slice(&[
-            "./node_modules/.bin/ts-node",
-            "../node_modules/.bin/ts-node",
-            "../../node_modules/.bin/ts-node",
-        ]);
+fn response_from_execution(
+    execution: Result<Execution, String>,
+    runner: &dyn LanguageRunner,
+    grading: &Grading,
+    limits: &ResourceLimits,
+    confinement: &Confinement,
+) -> CodeValidationResponse {
+    match execution {
+        Ok(Execution::Ran(output)) => {
+            response_from_output(output, runner, grading, limits, confinement)
+        }
+        Ok(Execution::CompileFailed(output)) => compile_error_response(output, runner, confinement),
+        Ok(Execution::ToolchainMissing(e)) => {
+            CodeValidationResponse::failure(Outcome::ToolchainMissing, e, confinement)
+        }
+        Err(e) => CodeValidationResponse::failure(Outcome::SandboxError, e, confinement),
     }
+}
 
-    let mut last_err: Option<String> = None;
-    let mut chosen_output: Option<std::process::Output> = None;
+fn compile_error_response(
+    output: process::RunOutput,
+    runner: &dyn LanguageRunner,
+    confinement: &Confinement,
+) -> CodeValidationResponse {
+    let mut response =
+        CodeValidationResponse::from_run(Outcome::CompileError, &output, confinement);
+    let compiler_output = response.stderr.trim().to_string();
+
+    response.compile_error = Some(if output.timed_out {
+        format!(
+            "Kompilacja przekroczyła limit czasu ({} s).",
+            ResourceLimits::compiler().timeout().as_secs()
+        )
+    } else {
+        compiler_output.clone()
+    });
+    response.diagnostics = runner.parse_diagnostics(&compiler_output);
+    response.output = compiler_output;
+    response.error = Some("Błąd kompilacji".to_string());
+    // The learner's program never started, so this was not a run timeout.
+    response.timed_out = false;
+    response
+}
 
-    for cmd in candidates.iter() {
-        match run_with_file(cmd, &tmp_path) {
-            Ok(out) => {
-                chosen_output = Some(out);
-                break;
-            }
-            Err(e) => {
-                last_err = Some(format!("{}", e));
-            }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCase {
+    #[serde(default)]
+    input: Option<String>,
+    #[serde(alias = "expectedOutput")]
+    expected_output: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestCaseResult {
+    description: Option<String>,
+    input: Option<String>,
+    expected_output: String,
+    actual_output: String,
+    passed: bool,
+    outcome: Outcome,
+    stderr: String,
+    exit_code: Option<i32>,
+    duration_ms: u64,
+    error: Option<String>,
+    timed_out: bool,
+    limit_exceeded: Option<LimitKind>,
+    diff: Vec<DiffLine>,
+    hints: Vec<Hint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestCasesResponse {
+    all_passed: bool,
+    passed_count: usize,
+    total: usize,
+    cases: Vec<TestCaseResult>,
+    confinement: Confinement,
+}
+
+/// Runs the code once per test case, piping each case's `input` to stdin.
+/// The cases come either straight from the editor or from the lesson's
+/// `content.testCases`, in which case the lesson's `limits`, `comparison`
+/// and `engine` apply too.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn validate_test_cases(
+    client: State<'_, SupabaseClient>,
+    code: String,
+    language: String,
+    test_cases: Option<Vec<TestCase>>,
+    lesson_id: Option<String>,
+    limits: Option<ResourceLimits>,
+    comparison: Option<Comparison>,
+    engine: Option<Engine>,
+) -> Result<TestCasesResponse, String> {
+    let (test_cases, limits, comparison, engine) = match (test_cases, lesson_id) {
+        (Some(test_cases), _) => (test_cases, limits, comparison, engine),
+        (None, Some(lesson_id)) => {
+            let lesson = supabase::fetch_lesson(&client, lesson_id).await?;
+            let checks = ExerciseChecks::from_content(&lesson.content)?;
+            (
+                checks.test_cases,
+                limits.or(checks.limits),
+                comparison.or(checks.comparison),
+                engine.or(checks.engine),
+            )
         }
+        (None, None) => return Err("Podaj testCases albo lessonId".to_string()),
+    };
+
+    if test_cases.is_empty() {
+        return Err("Ćwiczenie nie ma żadnych przypadków testowych".to_string());
     }
 
-    let _ = fs::remove_file(&tmp_path);
+    let runner = languages::select(&language, engine.unwrap_or_default())?;
+    let report = run_test_cases(
+        &code,
+        runner,
+        test_cases,
+        limits.unwrap_or_default().clamped(),
+        &comparison.unwrap_or_default(),
+    )
+    .await?;
+    println!(
+        "Test cases: {}/{} passed",
+        report.passed_count, report.total
+    );
+    Ok(report)
+}
 
-    let output = match chosen_output {
-        Some(out) => out,
-        None => {
-            return Ok(CodeValidationResponse {
-                success: false,
-                output: String::new(),
-                error: Some(format!(
-                    "Nie można znaleźć/uruchomić ts-node. Ostatni błąd: {}",
-                    last_err.unwrap_or_else(|| "unknown error".to_string())
-                )),
-                is_correct: false,
-            });
+/// How an exercise lesson's `content` says solutions are checked.
+struct ExerciseChecks {
+    test_cases: Vec<TestCase>,
+    /// Hidden unit tests, run after the code.
+    tests: Option<String>,
+    limits: Option<ResourceLimits>,
+    comparison: Option<Comparison>,
+    engine: Option<Engine>,
+}
+
+impl ExerciseChecks {
+    /// Broken `testCases` are an error; the other settings fall back to
+    /// their defaults when they don't parse. A lesson with only a
+    /// top-level `expectedOutput` has that as its one test case.
+    fn from_content(content: &serde_json::Value) -> Result<Self, String> {
+        let setting = |key: &str| content.get(key).cloned().filter(|value| !value.is_null());
+        let mut test_cases = setting("testCases")
+            .map(serde_json::from_value::<Vec<TestCase>>)
+            .transpose()
+            .map_err(|e| format!("Nieprawidłowe testCases w lekcji: {}", e))?
+            .unwrap_or_default();
+        if test_cases.is_empty() {
+            let text = |key: &str| {
+                setting(key)
+                    .and_then(|value| value.as_str().map(str::to_string))
+                    .filter(|text| !text.trim().is_empty())
+            };
+            if let Some(expected_output) = text("expectedOutput") {
+                test_cases.push(TestCase {
+                    input: text("input"),
+                    expected_output,
+                    description: None,
+                });
+            }
         }
-    };
 
-    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Ok(Self {
+            test_cases,
+            tests: setting("tests")
+                .and_then(|value| value.as_str().map(str::to_string))
+                .filter(|tests| !tests.trim().is_empty()),
+            limits: setting("limits").and_then(|value| serde_json::from_value(value).ok()),
+            comparison: setting("comparison").and_then(|value| serde_json::from_value(value).ok()),
+            engine: setting("engine").and_then(|value| serde_json::from_value(value).ok()),
+        })
+    }
+}
 
-    if !stderr.is_empty() {
-        return Ok(CodeValidationResponse {
-            success: false,
-            output: stderr.clone(),
-            error: Some(stderr),
-            is_correct: false,
+/// Runs `code` once per test case, each in a fresh sandbox.
+async fn run_test_cases(
+    code: &str,
+    runner: &'static dyn LanguageRunner,
+    test_cases: Vec<TestCase>,
+    limits: ResourceLimits,
+    comparison: &Comparison,
+) -> Result<TestCasesResponse, String> {
+    let total = test_cases.len();
+    let mut cases = Vec::with_capacity(total);
+    let mut confinement = Confinement::default();
+
+    for case in test_cases {
+        let grading = Grading::Output {
+            expected: &case.expected_output,
+            comparison,
+        };
+        let response = run_validation(
+            code.to_string(),
+            runner,
+            case.input.clone(),
+            limits.clone(),
+            &grading,
+            Observer::default(),
+        )
+        .await?;
+
+        confinement = response.confinement;
+        cases.push(TestCaseResult {
+            description: case.description,
+            input: case.input,
+            expected_output: case.expected_output,
+            actual_output: response.output,
+            passed: response.is_correct,
+            outcome: response.outcome,
+            stderr: response.stderr,
+            exit_code: response.exit_code,
+            duration_ms: response.duration_ms,
+            error: response.error,
+            timed_out: response.timed_out,
+            limit_exceeded: response.limit_exceeded,
+            diff: response.diff,
+            hints: response.hints,
         });
     }
 
-    let is_correct = stdout == expected_output;
-    Ok(CodeValidationResponse {
-        success: true,
-        output: stdout,
-        error: None,
-        is_correct,
+    let passed_count = cases.iter().filter(|case| case.passed).count();
+    Ok(TestCasesResponse {
+        all_passed: passed_count == total,
+        passed_count,
+        total,
+        cases,
+        confinement,
     })
 }
 
-async fn validate_javascript_code(
-    code: String,
-    expected_output: String,
-) -> Result<CodeValidationResponse, String> {
-    use std::process::Command;
+/// Which languages can run on this machine. Results are cached; pass
+/// `refresh` after installing a toolchain.
+#[tauri::command]
+async fn check_toolchains(refresh: Option<bool>) -> Result<Vec<ToolchainStatus>, String> {
+    Ok(languages::check_all(refresh.unwrap_or(false)).await)
+}
 
-    let output = Command::new("node").arg("-e").arg(&code).output();
+fn sandbox_error(e: String) -> CodeValidationResponse {
+    CodeValidationResponse::failure(Outcome::SandboxError, e, &Confinement::default())
+}
 
-    match output {
-        Ok(result) => {
-            let stdout = String::from_utf8_lossy(&result.stdout).trim().to_string();
-            let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
+/// The exit status decides whether the program worked; stderr is only
+/// used to explain a failure, so warnings don't fail a correct solution.
+fn response_from_output(
+    output: process::RunOutput,
+    runner: &dyn LanguageRunner,
+    grading: &Grading,
+    limits: &ResourceLimits,
+    confinement: &Confinement,
+) -> CodeValidationResponse {
+    let mut response =
+        CodeValidationResponse::from_run(Outcome::RuntimeError, &output, confinement);
+    let stderr = response.stderr.trim().to_string();
+
+    if output.cancelled {
+        response.outcome = Outcome::Cancelled;
+        response.error = Some("Program został zatrzymany.".to_string());
+        return response;
+    }
 
-            if !stderr.is_empty() {
-                return Ok(CodeValidationResponse {
-                    success: false,
-                    output: stderr.clone(),
-                    error: Some(stderr),
-                    is_correct: false,
-                });
-            }
+    // The harness's result lines are not part of what the learner printed,
+    // even when the program crashed halfway through the tests.
+    if let Grading::UnitTests { nonce, .. } = grading {
+        let (tests, printed) = unit_tests::parse(&response.stdout, nonce);
+        response.output = printed.trim().to_string();
+        response.stdout = printed;
+        response.tests = tests;
+    }
+
+    let status = match output.status {
+        Some(status) if !output.timed_out => status,
+        _ => {
+            response.outcome = Outcome::Timeout;
+            response.error = Some(format!(
+                "Przekroczono limit czasu wykonania ({} s). Sprawdź, czy program nie zawiera nieskończonej pętli.",
+                limits.timeout().as_secs_f32()
+            ));
+            return response;
+        }
+    };
 
-            let is_correct = stdout == expected_output;
+    if let Some(limit) = LimitKind::detect(&output, limits) {
+        response.outcome = Outcome::LimitExceeded;
+        response.error = Some(limit.message(limits));
+        response.limit_exceeded = Some(limit);
+        return response;
+    }
 
-            Ok(CodeValidationResponse {
-                success: true,
-                output: stdout,
-                error: None,
-                is_correct,
-            })
+    if !status.success() {
+        response.diagnostics = runner.parse_diagnostics(&stderr);
+        response.error = Some(if stderr.is_empty() {
+            process::describe_failure(&status)
+        } else {
+            stderr
+        });
+        return response;
+    }
+
+    response.success = true;
+    let (expected, comparison) = match grading {
+        Grading::Output {
+            expected,
+            comparison,
+        } => (*expected, *comparison),
+        Grading::UnitTests { .. } => {
+            let failed = response.tests.iter().filter(|test| !test.passed).count();
+            response.is_correct = !response.tests.is_empty() && failed == 0;
+            response.outcome = if response.is_correct {
+                Outcome::Passed
+            } else {
+                Outcome::WrongAnswer
+            };
+            if response.tests.is_empty() {
+                response.error = Some("Nie uruchomiono żadnego testu".to_string());
+            } else if failed > 0 {
+                response.error = Some(format!(
+                    "Nie przeszło {} z {} testów",
+                    failed,
+                    response.tests.len()
+                ));
+            }
+            return response;
+        }
+        Grading::RunOnly => {
+            response.outcome = Outcome::Passed;
+            response.is_correct = true;
+            return response;
+        }
+    };
+
+    match comparison.matches(&response.stdout, expected) {
+        Ok(true) => {
+            response.outcome = Outcome::Passed;
+            response.is_correct = true;
+        }
+        Ok(false) => {
+            response.outcome = Outcome::WrongAnswer;
+            response.diff = comparison.diff(&response.stdout, expected);
+        }
+        Err(e) => {
+            response.outcome = Outcome::WrongAnswer;
+            response.error = Some(e);
         }
-        Err(e) => Ok(CodeValidationResponse {
-            success: false,
-            output: String::new(),
-            error: Some(format!(
-                "Nie można uruchomić Node.js: {}. Upewnij się, że Node.js jest zainstalowany.",
-                e
-            )),
-            is_correct: false,
-        }),
     }
+    response
+}
+
+/// Runs the embedded JavaScript engine instead of the app when the process
+/// was started for that, and returns its exit code.
+pub fn run_embedded_engine() -> Option<i32> {
+    runner::embedded::main(std::env::args_os())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -353,14 +774,76 @@ pub fn run() {
         }
     }
 
+    // Every screen needs Supabase, so a missing URL or key stops the app
+    // here rather than failing each command.
+    let supabase = match SupabaseClient::from_env() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Supabase is not configured: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(supabase)
+        .manage(runs::Runs::default())
+        .manage(repl::Repls::default())
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                window.state::<repl::Repls>().close_window(window.label());
+            }
+        })
+        .setup(|app| {
+            session::watch(app.handle().clone());
+            // The key is kept apart from the session it encrypts.
+            match (app.path().app_data_dir(), app.path().app_config_dir()) {
+                (Ok(dir), Ok(config_dir)) => {
+                    app.state::<SupabaseClient>()
+                        .session()
+                        .attach_store(SessionStore::new(
+                            dir.join("session.bin"),
+                            store::platform_keys(
+                                &app.config().identifier,
+                                config_dir.join("session.key"),
+                            ),
+                        ));
+                    cache::init(dir);
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    println!("Run cache and saved sessions disabled: {}", e)
+                }
+            }
+            // Warm the toolchain cache so the first exercise doesn't pay for it.
+            tauri::async_runtime::spawn(async {
+                for status in languages::check_all(false).await {
+                    if !status.available {
+                        println!("Toolchain unavailable: {}", status.language);
+                    }
+                }
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             login_user,
+            logout_user,
+            session::restore_session,
             register_user,
             google_sign_in,
             check_is_admin,
             validate_code,
+            validate_test_cases,
+            check_toolchains,
+            markup::validate_markup,
+            format::format_code,
+            pool::configure_execution_pool,
+            solutions::verify_course_solutions,
+            projects::validate_project,
+            runs::run_code_streaming,
+            runs::cancel_run,
+            repl::start_repl,
+            repl::send_repl_input,
+            repl::stop_repl,
             supabase::get_all_courses,
             supabase::get_lesson_by_id,
             supabase::create_course,
@@ -372,6 +855,7 @@ pub fn run() {
             supabase::search_lessons,
             supabase::get_user_progress,
             supabase::update_lesson_progress,
+            supabase::record_attempt,
             supabase::get_user_profile,
             supabase::get_user_statistics,
             supabase::get_user_achievements,
@@ -382,3 +866,115 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+    use std::time::Duration;
+
+    fn run_output(status: ExitStatus, stdout: &str, stderr: &str) -> process::RunOutput {
+        process::RunOutput {
+            status: Some(status),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+            timed_out: false,
+            cancelled: false,
+            output_truncated: false,
+            cpu_time: Some(Duration::ZERO),
+            duration: Duration::from_millis(12),
+        }
+    }
+
+    fn respond(output: process::RunOutput, expected: &str) -> CodeValidationResponse {
+        grade(
+            output,
+            &Grading::Output {
+                expected,
+                comparison: &Comparison::default(),
+            },
+        )
+    }
+
+    fn grade(output: process::RunOutput, grading: &Grading) -> CodeValidationResponse {
+        response_from_output(
+            output,
+            languages::find("python").unwrap(),
+            grading,
+            &ResourceLimits::default(),
+            &Confinement::default(),
+        )
+    }
+
+    #[test]
+    fn warnings_on_stderr_do_not_fail_a_correct_run() {
+        let output = run_output(
+            ExitStatus::from_raw(0),
+            "42\n",
+            "DeprecationWarning: something is deprecated",
+        );
+        let response = respond(output, "42");
+        assert!(response.success && response.is_correct);
+        assert_eq!(response.outcome, Outcome::Passed);
+        assert_eq!(response.exit_code, Some(0));
+        assert!(response.stderr.contains("DeprecationWarning"));
+    }
+
+    #[test]
+    fn killed_by_signal_with_empty_stderr_is_a_runtime_error() {
+        let response = respond(
+            run_output(ExitStatus::from_raw(libc::SIGSEGV), "42\n", ""),
+            "42",
+        );
+        assert!(!response.success && !response.is_correct);
+        assert_eq!(response.outcome, Outcome::RuntimeError);
+        assert_eq!(response.exit_code, None);
+        assert!(response.error.unwrap().contains("sygnałem"));
+    }
+
+    #[test]
+    fn wrong_output_comes_with_a_diff() {
+        let response = respond(run_output(ExitStatus::from_raw(0), "41\n", ""), "42");
+        assert_eq!(response.outcome, Outcome::WrongAnswer);
+        assert_eq!(response.diff.len(), 2);
+    }
+
+    #[test]
+    fn unit_test_results_are_not_part_of_the_output() {
+        let nonce = unit_tests::nonce();
+        let stdout = format!(
+            "hello\n{nonce}{{\"name\": \"test_ok\", \"passed\": true, \"message\": null}}\n{nonce}{{\"name\": \"test_bad\", \"passed\": false, \"message\": \"1 != 2\"}}\n"
+        );
+        let response = grade(
+            run_output(ExitStatus::from_raw(0), &stdout, ""),
+            &Grading::UnitTests { tests: "", nonce },
+        );
+        assert_eq!(response.output, "hello");
+        assert_eq!(response.tests.len(), 2);
+        assert_eq!(response.outcome, Outcome::WrongAnswer);
+        assert!(!response.is_correct);
+    }
+
+    #[test]
+    fn reads_exercise_checks_from_lesson_content() {
+        let checks = ExerciseChecks::from_content(&serde_json::json!({
+            "testCases": [{ "input": "2", "expectedOutput": "4" }],
+            "tests": "  ",
+            "limits": null,
+            "comparison": { "mode": "bogus" },
+        }))
+        .unwrap();
+        assert_eq!(checks.test_cases[0].expected_output, "4");
+        assert!(checks.tests.is_none() && checks.limits.is_none() && checks.comparison.is_none());
+
+        let broken = serde_json::json!({ "testCases": [{ "input": "2" }] });
+        assert!(ExerciseChecks::from_content(&broken).is_err());
+
+        let output_only = serde_json::json!({ "expectedOutput": "Hello\n", "testCases": [] });
+        let checks = ExerciseChecks::from_content(&output_only).unwrap();
+        assert_eq!(checks.test_cases.len(), 1);
+        assert_eq!(checks.test_cases[0].expected_output, "Hello\n");
+        assert_eq!(checks.test_cases[0].input, None);
+    }
+}
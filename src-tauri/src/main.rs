@@ -2,5 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // Sandboxed runs of the embedded JavaScript engine start this binary
+    // again; they must not open a window.
+    if let Some(code) = desktop_app_lib::run_embedded_engine() {
+        std::process::exit(code);
+    }
     desktop_app_lib::run()
 }
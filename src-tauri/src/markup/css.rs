@@ -0,0 +1,400 @@
+use cssparser::{
+    AtRuleParser, BasicParseErrorKind, CowRcStr, DeclarationParser, ParseError, ParseErrorKind,
+    Parser, ParserInput, ParserState, QualifiedRuleParser, RuleBodyItemParser, RuleBodyParser,
+    StyleSheetParser, ToCss,
+};
+use scraper::selector::Simple;
+use scraper::ElementRef;
+use selectors::matching::{
+    self, MatchingContext, MatchingForInvalidation, MatchingMode, NeedsSelectorFlags, QuirksMode,
+    SelectorCaches,
+};
+use selectors::parser::{ParseRelative, SelectorList};
+
+use crate::runner::Diagnostic;
+
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    pub property: String,
+    pub value: String,
+    pub important: bool,
+}
+
+struct Rule {
+    selectors: SelectorList<Simple>,
+    declarations: Vec<Declaration>,
+}
+
+/// Every style rule of a page, in source order.
+#[derive(Default)]
+pub struct Stylesheet {
+    rules: Vec<Rule>,
+}
+
+impl Stylesheet {
+    /// Adds the rules of `css`. Rules inside `@media` and `@supports`
+    /// count as if their condition held; other at-rules are skipped.
+    /// `embedded_at` is the line of the HTML file a `<style>` block starts
+    /// on, so problems point at the right line of what the learner wrote.
+    pub fn add(&mut self, css: &str, embedded_at: Option<u32>, issues: &mut Vec<Diagnostic>) {
+        let mut input = ParserInput::new(css);
+        let mut input = Parser::new(&mut input);
+        let mut parser = RuleParser {
+            issues,
+            embedded_at,
+        };
+        let results: Vec<_> = StyleSheetParser::new(&mut input, &mut parser).collect();
+        for result in results {
+            match result {
+                Ok(parsed) => self.rules.extend(parsed),
+                Err((error, text)) => parser.report(&error, text),
+            }
+        }
+    }
+
+    /// The value `property` ends up with on `element` after the cascade:
+    /// `!important` first, then the inline style, then specificity, then
+    /// source order. Shorthands are not expanded and nothing is inherited.
+    pub fn value_for(&self, element: &ElementRef, property: &str) -> Option<String> {
+        let mut caches = SelectorCaches::default();
+        let mut context = MatchingContext::new(
+            MatchingMode::Normal,
+            None,
+            &mut caches,
+            QuirksMode::NoQuirks,
+            NeedsSelectorFlags::No,
+            MatchingForInvalidation::No,
+        );
+
+        let mut winner: Option<((bool, u32, usize), &str)> = None;
+        let mut consider = |key: (bool, u32, usize), value| {
+            if winner.is_none_or(|(best, _)| key >= best) {
+                winner = Some((key, value));
+            }
+        };
+
+        for (order, rule) in self.rules.iter().enumerate() {
+            let specificity = rule
+                .selectors
+                .slice()
+                .iter()
+                .filter(|selector| {
+                    matching::matches_selector(selector, 0, None, element, &mut context)
+                })
+                .map(|selector| selector.specificity())
+                .max();
+            let Some(specificity) = specificity else {
+                continue;
+            };
+            for declaration in &rule.declarations {
+                if declaration.property == property {
+                    consider(
+                        (declaration.important, specificity, order),
+                        &declaration.value,
+                    );
+                }
+            }
+        }
+
+        let inline = element
+            .value()
+            .attr("style")
+            .map(inline_declarations)
+            .unwrap_or_default();
+        for declaration in &inline {
+            if declaration.property == property {
+                consider(
+                    (declaration.important, u32::MAX, self.rules.len()),
+                    &declaration.value,
+                );
+            }
+        }
+
+        winner.map(|(_, value)| value.to_string())
+    }
+
+    /// For a stylesheet without a page to apply it to: the value the last
+    /// rule written for exactly `selector` gives `property`.
+    pub fn declared_for(&self, selector: &str, property: &str) -> Option<String> {
+        let wanted = canonical_selectors(selector)?;
+        self.rules
+            .iter()
+            .filter(|rule| {
+                rule.selectors
+                    .slice()
+                    .iter()
+                    .any(|selector| wanted.contains(&selector.to_css_string()))
+            })
+            .flat_map(|rule| &rule.declarations)
+            .filter(|declaration| declaration.property == property)
+            .max_by_key(|declaration| declaration.important)
+            .map(|declaration| declaration.value.clone())
+    }
+}
+
+/// The declarations of a `style` attribute; broken ones are left out.
+pub fn inline_declarations(style: &str) -> Vec<Declaration> {
+    let mut input = ParserInput::new(style);
+    let mut input = Parser::new(&mut input);
+    RuleBodyParser::new(&mut input, &mut Declarations)
+        .filter_map(Result::ok)
+        .collect()
+}
+
+/// `p,  .a>b` → `["p", ".a > b"]`, so equal selectors compare equal
+/// however they were spaced.
+fn canonical_selectors(selector: &str) -> Option<Vec<String>> {
+    let mut input = ParserInput::new(selector);
+    let list = SelectorList::parse(
+        &scraper::selector::Parser,
+        &mut Parser::new(&mut input),
+        ParseRelative::No,
+    )
+    .ok()?;
+    Some(list.slice().iter().map(|s| s.to_css_string()).collect())
+}
+
+/// Lowercase, single spaces, none after commas, so `rgb(0, 0, 0)` and
+/// `RGB(0,0,0)` compare equal.
+pub fn normalize_value(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(", ", ",")
+        .to_lowercase()
+}
+
+enum CssError<'i> {
+    Selector,
+    EmptyValue(CowRcStr<'i>),
+}
+
+enum AtRule {
+    /// Its rules apply, whatever the condition.
+    Conditional,
+    Ignored,
+}
+
+struct RuleParser<'a> {
+    issues: &'a mut Vec<Diagnostic>,
+    embedded_at: Option<u32>,
+}
+
+impl RuleParser<'_> {
+    fn report(&mut self, error: &ParseError<CssError>, text: &str) {
+        let message = match &error.kind {
+            ParseErrorKind::Custom(CssError::Selector) => {
+                format!(
+                    "Nieprawidłowy selektor: {}",
+                    first_line(text).trim_end_matches('{').trim_end()
+                )
+            }
+            ParseErrorKind::Custom(CssError::EmptyValue(name)) => {
+                format!("Brak wartości właściwości {}", name)
+            }
+            ParseErrorKind::Basic(BasicParseErrorKind::AtRuleInvalid(name)) => {
+                format!("Nieprawidłowa reguła @{}", name)
+            }
+            ParseErrorKind::Basic(_) => format!("Nieprawidłowa deklaracja: {}", first_line(text)),
+        };
+        let (line, column) = match self.embedded_at {
+            Some(start) => (start + error.location.line, None),
+            None => (error.location.line + 1, Some(error.location.column)),
+        };
+        self.issues.push(Diagnostic {
+            line: Some(line),
+            column,
+            message: format!("CSS: {}", message),
+        });
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.trim().lines().next().unwrap_or_default().trim()
+}
+
+impl<'i> QualifiedRuleParser<'i> for RuleParser<'_> {
+    type Prelude = SelectorList<Simple>;
+    type QualifiedRule = Vec<Rule>;
+    type Error = CssError<'i>;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        SelectorList::parse(&scraper::selector::Parser, input, ParseRelative::No).map_err(|e| {
+            ParseError {
+                kind: match e.kind {
+                    ParseErrorKind::Basic(kind) => ParseErrorKind::Basic(kind),
+                    ParseErrorKind::Custom(_) => ParseErrorKind::Custom(CssError::Selector),
+                },
+                location: e.location,
+            }
+        })
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        selectors: Self::Prelude,
+        _: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::QualifiedRule, ParseError<'i, Self::Error>> {
+        let mut declarations = Vec::new();
+        let mut errors = Vec::new();
+        for result in RuleBodyParser::new(input, &mut Declarations) {
+            match result {
+                Ok(declaration) => declarations.push(declaration),
+                Err(error) => errors.push(error),
+            }
+        }
+        for (error, text) in errors {
+            self.report(&error, text);
+        }
+        Ok(vec![Rule {
+            selectors,
+            declarations,
+        }])
+    }
+}
+
+impl<'i> AtRuleParser<'i> for RuleParser<'_> {
+    type Prelude = AtRule;
+    type AtRule = Vec<Rule>;
+    type Error = CssError<'i>;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        while input.next().is_ok() {}
+        let conditional =
+            name.eq_ignore_ascii_case("media") || name.eq_ignore_ascii_case("supports");
+        Ok(if conditional {
+            AtRule::Conditional
+        } else {
+            AtRule::Ignored
+        })
+    }
+
+    fn rule_without_block(&mut self, _: Self::Prelude, _: &ParserState) -> Result<Vec<Rule>, ()> {
+        Ok(Vec::new())
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::Prelude,
+        _: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::AtRule, ParseError<'i, Self::Error>> {
+        let mut rules = Vec::new();
+        if let AtRule::Conditional = prelude {
+            let nested: Vec<_> = StyleSheetParser::new(input, self).collect();
+            for result in nested {
+                match result {
+                    Ok(parsed) => rules.extend(parsed),
+                    Err((error, text)) => self.report(&error, text),
+                }
+            }
+        } else {
+            while input.next().is_ok() {}
+        }
+        Ok(rules)
+    }
+}
+
+/// Parses the declarations inside a rule or a `style` attribute.
+struct Declarations;
+
+impl<'i> DeclarationParser<'i> for Declarations {
+    type Declaration = Declaration;
+    type Error = CssError<'i>;
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Declaration, ParseError<'i, Self::Error>> {
+        let start = input.position();
+        while input.next().is_ok() {}
+        let text = input.slice_from(start).trim();
+
+        let (value, important) = match text.rsplit_once('!') {
+            Some((value, flag)) if flag.trim().eq_ignore_ascii_case("important") => {
+                (value.trim(), true)
+            }
+            _ => (text, false),
+        };
+        if value.is_empty() {
+            return Err(input.new_custom_error(CssError::EmptyValue(name)));
+        }
+        Ok(Declaration {
+            property: name.to_ascii_lowercase(),
+            value: value.to_string(),
+            important,
+        })
+    }
+}
+
+impl<'i> AtRuleParser<'i> for Declarations {
+    type Prelude = ();
+    type AtRule = Declaration;
+    type Error = CssError<'i>;
+}
+
+impl<'i> QualifiedRuleParser<'i> for Declarations {
+    type Prelude = ();
+    type QualifiedRule = Declaration;
+    type Error = CssError<'i>;
+}
+
+impl<'i> RuleBodyItemParser<'i, Declaration, CssError<'i>> for Declarations {
+    fn parse_declarations(&self) -> bool {
+        true
+    }
+
+    fn parse_qualified(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::{Html, Selector};
+
+    #[test]
+    fn applies_the_cascade() {
+        let mut stylesheet = Stylesheet::default();
+        let mut issues = Vec::new();
+        stylesheet.add(
+            "#main p { color: blue }\np.note { color: green; margin: 0 }\np { color: red !important; }\np { color }",
+            None,
+            &mut issues,
+        );
+        let page = Html::parse_document(
+            r#"<div id="main"><p class="note" style="margin: 4px">a</p></div><span style="color: RGB(0, 0, 0)">b</span>"#,
+        );
+        let p = page.select(&Selector::parse("p").unwrap()).next().unwrap();
+        let span = page
+            .select(&Selector::parse("span").unwrap())
+            .next()
+            .unwrap();
+
+        assert_eq!(stylesheet.value_for(&p, "color").as_deref(), Some("red"));
+        assert_eq!(stylesheet.value_for(&p, "margin").as_deref(), Some("4px"));
+        assert_eq!(
+            stylesheet
+                .value_for(&span, "color")
+                .map(|v| normalize_value(&v)),
+            Some("rgb(0,0,0)".to_string())
+        );
+        assert_eq!(
+            stylesheet.declared_for("p.note", "margin").as_deref(),
+            Some("0")
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(4));
+    }
+}
@@ -0,0 +1,221 @@
+use std::cell::RefCell;
+
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+
+use crate::runner::Diagnostic;
+
+/// Elements that never have content or an end tag.
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose end tag may be left out; browsers close them on their
+/// own, so a missing one is not a mistake.
+const OPTIONAL_END: &[&str] = &[
+    "html", "head", "body", "p", "li", "dt", "dd", "option", "optgroup", "tr", "td", "th", "thead",
+    "tbody", "tfoot", "colgroup", "rt", "rp",
+];
+
+/// What a pass over the learner's HTML found.
+#[derive(Default)]
+pub struct Scan {
+    pub issues: Vec<Diagnostic>,
+    /// Contents of each `<style>` element with the line it starts on.
+    pub styles: Vec<(String, u32)>,
+}
+
+/// Finds syntax errors and elements that are closed in the wrong place or
+/// never. Browsers repair both silently, which is exactly why learners
+/// don't notice them, so they are reported with the line they are on.
+pub fn scan(html: &str) -> Scan {
+    let tokenizer = Tokenizer::new(Collector::default(), TokenizerOpts::default());
+    let input = BufferQueue::default();
+    input.push_back(html.into());
+    let _ = tokenizer.feed(&input);
+    tokenizer.end();
+
+    let collector = tokenizer.sink;
+    let mut scan = collector.scan.into_inner();
+    for open in collector.open.into_inner().into_iter().rev() {
+        if !OPTIONAL_END.contains(&open.name.as_str()) {
+            scan.issues.push(issue(
+                open.line,
+                format!("Element <{}> nie został zamknięty", open.name),
+            ));
+        }
+    }
+    scan.issues.sort_by_key(|issue| issue.line);
+    scan
+}
+
+fn issue(line: u32, message: String) -> Diagnostic {
+    Diagnostic {
+        line: Some(line),
+        column: None,
+        message,
+    }
+}
+
+struct Open {
+    name: String,
+    line: u32,
+}
+
+/// Roots of SVG and MathML, where `<path/>` closes an element as in XML.
+const FOREIGN: &[&str] = &["svg", "math"];
+
+#[derive(Default)]
+struct Collector {
+    scan: RefCell<Scan>,
+    open: RefCell<Vec<Open>>,
+    /// The `<style>` being read, if any.
+    style: RefCell<Option<(String, u32)>>,
+}
+
+impl Collector {
+    /// Inside `<svg>` or `<math>`, unless back in HTML through
+    /// `<foreignObject>`.
+    fn in_foreign(&self) -> bool {
+        self.open
+            .borrow()
+            .iter()
+            .rev()
+            .find(|element| {
+                FOREIGN.contains(&element.name.as_str()) || element.name == "foreignobject"
+            })
+            .is_some_and(|element| element.name != "foreignobject")
+    }
+
+    fn start_tag(&self, name: &str, self_closing: bool, line: u32) -> TokenSinkResult<()> {
+        let foreign = FOREIGN.contains(&name) || self.in_foreign();
+        if self_closing && !foreign && !VOID.contains(&name) {
+            // Browsers ignore the slash and leave the element open.
+            self.scan.borrow_mut().issues.push(issue(
+                line,
+                format!(
+                    "Znacznik <{0}/> nie może być samozamykający, zamknij go przez </{0}>",
+                    name
+                ),
+            ));
+        } else if !self_closing && !VOID.contains(&name) {
+            self.open.borrow_mut().push(Open {
+                name: name.to_string(),
+                line,
+            });
+        }
+        match name {
+            "style" => {
+                *self.style.borrow_mut() = Some((String::new(), line));
+                TokenSinkResult::RawData(RawKind::Rawtext)
+            }
+            "script" => TokenSinkResult::RawData(RawKind::ScriptData),
+            "textarea" | "title" => TokenSinkResult::RawData(RawKind::Rcdata),
+            _ => TokenSinkResult::Continue,
+        }
+    }
+
+    fn end_tag(&self, name: &str, line: u32) {
+        if name == "style" {
+            if let Some(style) = self.style.borrow_mut().take() {
+                self.scan.borrow_mut().styles.push(style);
+            }
+        }
+        if VOID.contains(&name) {
+            return;
+        }
+
+        let mut open = self.open.borrow_mut();
+        let mut scan = self.scan.borrow_mut();
+        let Some(position) = open.iter().rposition(|element| element.name == name) else {
+            scan.issues.push(issue(
+                line,
+                format!("Zamknięcie </{}> bez wcześniejszego otwarcia", name),
+            ));
+            return;
+        };
+        for unclosed in open.drain(position + 1..) {
+            if !OPTIONAL_END.contains(&unclosed.name.as_str()) {
+                scan.issues.push(issue(
+                    unclosed.line,
+                    format!(
+                        "Element <{}> nie został zamknięty przed </{}> w linii {}",
+                        unclosed.name, name, line
+                    ),
+                ));
+            }
+        }
+        open.pop();
+    }
+}
+
+impl TokenSink for Collector {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, line: u64) -> TokenSinkResult<()> {
+        let line = line as u32;
+        match token {
+            Token::TagToken(tag) => match tag.kind {
+                TagKind::StartTag => return self.start_tag(&tag.name, tag.self_closing, line),
+                TagKind::EndTag => self.end_tag(&tag.name, line),
+            },
+            Token::CharacterTokens(text) => {
+                if let Some((style, _)) = self.style.borrow_mut().as_mut() {
+                    style.push_str(&text);
+                }
+            }
+            Token::ParseError(error) => {
+                self.scan
+                    .borrow_mut()
+                    .issues
+                    .push(issue(line, describe_parse_error(&error)));
+            }
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+fn describe_parse_error(error: &str) -> String {
+    match error {
+        "Duplicate attribute" => "Powtórzony atrybut w znaczniku".to_string(),
+        "Unexpected EOF" => {
+            "Nieoczekiwany koniec pliku: niezamknięty znacznik, komentarz lub cudzysłów".to_string()
+        }
+        "Bad character" => "Niedozwolony znak w znaczniku".to_string(),
+        _ => format!("Błąd składni HTML ({})", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_misnested_and_unclosed_elements() {
+        let scan = scan(
+            "<!DOCTYPE html>\n<ul>\n<li><span>a</li>\n</ul>\n<style>\np { color: red }\n</style>\n<div>\n<p>b\n",
+        );
+        let lines: Vec<_> = scan.issues.iter().map(|issue| issue.line).collect();
+
+        assert_eq!(lines, vec![Some(3), Some(8)]);
+        assert!(scan.issues[0].message.contains("<span>"));
+        assert_eq!(scan.styles, vec![("\np { color: red }\n".to_string(), 5)]);
+    }
+
+    #[test]
+    fn self_closing_tags_close_only_foreign_elements() {
+        let svg = scan(
+            "<svg viewBox=\"0 0 10 10\">\n<path d=\"M0 0\"/>\n<circle r=\"1\"/>\n</svg>\n<br/>\n",
+        );
+        assert!(svg.issues.is_empty(), "{:?}", svg.issues);
+
+        let div = scan("<section>\n<div/>\n</section>\n");
+        assert_eq!(div.issues.len(), 1, "{:?}", div.issues);
+        assert_eq!(div.issues[0].line, Some(2));
+        assert!(div.issues[0].message.contains("<div/>"));
+    }
+}
@@ -0,0 +1,487 @@
+//! HTML and CSS exercises. Nothing is run: the submitted page is parsed
+//! and checked against the assertions the lesson defines.
+
+mod css;
+mod html;
+
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+use crate::runner::Diagnostic;
+use crate::supabase::{self, SupabaseClient};
+use css::Stylesheet;
+
+/// One check of the submitted page, with an optional description shown
+/// instead of the generated one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkupAssertion {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(flatten)]
+    assertion: Assertion,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Assertion {
+    /// An element matches `selector`; exactly `count` of them when set,
+    /// and one containing `text` when that is set.
+    ElementExists {
+        selector: String,
+        #[serde(default)]
+        count: Option<usize>,
+        #[serde(default)]
+        text: Option<String>,
+    },
+    /// An element matching `selector` has the attribute, with `value`
+    /// when set.
+    Attribute {
+        selector: String,
+        name: String,
+        #[serde(default)]
+        value: Option<String>,
+    },
+    /// Every element matching `selector` gets `property` from the page's
+    /// styles, with `value` when set.
+    CssProperty {
+        selector: String,
+        property: String,
+        #[serde(default)]
+        value: Option<String>,
+    },
+    /// No syntax errors and no unclosed or misnested elements.
+    ValidMarkup,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssertionResult {
+    description: String,
+    passed: bool,
+    /// Why the assertion failed.
+    message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarkupValidationResponse {
+    all_passed: bool,
+    passed_count: usize,
+    total: usize,
+    assertions: Vec<AssertionResult>,
+    /// Syntax problems in the HTML and CSS, reported even when the
+    /// exercise doesn't assert valid markup.
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Checks an HTML or CSS exercise. For `css` the stylesheet is applied to
+/// the lesson's `html` page, if it has one. The assertions come either
+/// straight from the editor or from the lesson's `content.assertions`.
+#[tauri::command]
+pub async fn validate_markup(
+    client: State<'_, SupabaseClient>,
+    code: String,
+    language: String,
+    assertions: Option<Vec<MarkupAssertion>>,
+    html: Option<String>,
+    lesson_id: Option<String>,
+) -> Result<MarkupValidationResponse, String> {
+    let (assertions, html) = match (assertions, lesson_id) {
+        (Some(assertions), _) => (assertions, html),
+        (None, Some(lesson_id)) => {
+            let lesson = supabase::fetch_lesson(&client, lesson_id).await?;
+            let (assertions, lesson_html) = lesson_assertions(&lesson.content)?;
+            (assertions, html.or(lesson_html))
+        }
+        (None, None) => return Err("Podaj assertions albo lessonId".to_string()),
+    };
+
+    if assertions.is_empty() {
+        return Err("Ćwiczenie nie ma żadnych asercji".to_string());
+    }
+
+    let page = Page::parse(&code, &language, html.as_deref())?;
+    let results = page.check_all(&assertions);
+    let passed_count = results.iter().filter(|result| result.passed).count();
+    Ok(MarkupValidationResponse {
+        all_passed: passed_count == results.len(),
+        passed_count,
+        total: results.len(),
+        assertions: results,
+        diagnostics: page.issues,
+    })
+}
+
+/// Checks a lesson's own solution against its `assertions`. Returns the
+/// assertions it fails, each with why; `None` when the lesson asserts
+/// nothing.
+pub fn check_solution(
+    solution: &str,
+    language: &str,
+    content: &Value,
+) -> Result<Option<Vec<String>>, String> {
+    let (assertions, html) = lesson_assertions(content)?;
+    if assertions.is_empty() {
+        return Ok(None);
+    }
+    let page = Page::parse(solution, language, html.as_deref())?;
+    let failed = page
+        .check_all(&assertions)
+        .into_iter()
+        .filter(|result| !result.passed)
+        .map(|result| match result.message {
+            Some(message) => format!("{}: {}", result.description, message),
+            None => result.description,
+        })
+        .collect();
+    Ok(Some(failed))
+}
+
+/// The `assertions` of a lesson's `content` and the `html` page its
+/// styles apply to.
+fn lesson_assertions(content: &Value) -> Result<(Vec<MarkupAssertion>, Option<String>), String> {
+    let assertions = content
+        .get("assertions")
+        .cloned()
+        .map(serde_json::from_value::<Vec<MarkupAssertion>>)
+        .transpose()
+        .map_err(|e| format!("Nieprawidłowe assertions w lekcji: {}", e))?
+        .unwrap_or_default();
+    let html = content
+        .get("html")
+        .and_then(|value| value.as_str())
+        .map(str::to_string);
+    Ok((assertions, html))
+}
+
+impl Assertion {
+    fn describe(&self) -> String {
+        match self {
+            Assertion::ElementExists {
+                selector,
+                count: Some(count),
+                ..
+            } => format!("Strona ma {} elementów {}", count, selector),
+            Assertion::ElementExists {
+                selector,
+                text: Some(text),
+                ..
+            } => format!("Element {} zawiera tekst „{}”", selector, text),
+            Assertion::ElementExists { selector, .. } => format!("Istnieje element {}", selector),
+            Assertion::Attribute {
+                selector,
+                name,
+                value: Some(value),
+            } => format!("Element {} ma atrybut {}=\"{}\"", selector, name, value),
+            Assertion::Attribute { selector, name, .. } => {
+                format!("Element {} ma atrybut {}", selector, name)
+            }
+            Assertion::CssProperty {
+                selector,
+                property,
+                value: Some(value),
+            } => format!("{} ma {}: {}", selector, property, value),
+            Assertion::CssProperty {
+                selector, property, ..
+            } => format!("{} ma ustawioną właściwość {}", selector, property),
+            Assertion::ValidMarkup => "Kod nie zawiera błędów składni".to_string(),
+        }
+    }
+}
+
+/// The submitted page: its document, if there is one, and every style
+/// that applies to it.
+struct Page {
+    document: Option<Html>,
+    stylesheet: Stylesheet,
+    issues: Vec<Diagnostic>,
+}
+
+impl Page {
+    fn parse(code: &str, language: &str, fixture: Option<&str>) -> Result<Self, String> {
+        let mut stylesheet = Stylesheet::default();
+        let mut issues = Vec::new();
+        let document = match language {
+            "html" => {
+                let scan = html::scan(code);
+                issues = scan.issues;
+                for (css, line) in &scan.styles {
+                    stylesheet.add(css, Some(*line), &mut issues);
+                }
+                Some(Html::parse_document(code))
+            }
+            "css" => {
+                stylesheet.add(code, None, &mut issues);
+                fixture.map(Html::parse_document)
+            }
+            _ => {
+                return Err(format!(
+                    "Walidacja HTML/CSS nie obsługuje języka {}",
+                    language
+                ))
+            }
+        };
+        issues.sort_by_key(|issue| issue.line);
+
+        Ok(Self {
+            document,
+            stylesheet,
+            issues,
+        })
+    }
+
+    fn check_all(&self, assertions: &[MarkupAssertion]) -> Vec<AssertionResult> {
+        assertions
+            .iter()
+            .map(|assertion| {
+                let (passed, message) = self.check(&assertion.assertion);
+                AssertionResult {
+                    description: assertion
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| assertion.assertion.describe()),
+                    passed,
+                    message,
+                }
+            })
+            .collect()
+    }
+
+    fn check(&self, assertion: &Assertion) -> (bool, Option<String>) {
+        match assertion {
+            Assertion::ValidMarkup if self.issues.is_empty() => (true, None),
+            Assertion::ValidMarkup => (
+                false,
+                Some(format!("Liczba błędów składni: {}", self.issues.len())),
+            ),
+            Assertion::CssProperty {
+                selector,
+                property,
+                value,
+            } if self.document.is_none() => {
+                let declared = self.stylesheet.declared_for(selector, property);
+                judge_value(selector, property, value.as_deref(), declared)
+            }
+            Assertion::ElementExists {
+                selector,
+                count,
+                text,
+            } => {
+                let elements = match self.select(selector) {
+                    Ok(elements) => elements,
+                    Err(e) => return (false, Some(e)),
+                };
+                let found = elements
+                    .iter()
+                    .filter(|element| {
+                        text.as_deref().is_none_or(|text| {
+                            collapse(&element.text().collect::<String>()).contains(&collapse(text))
+                        })
+                    })
+                    .count();
+                match (count, text) {
+                    (Some(count), _) if found != *count => (
+                        false,
+                        Some(format!(
+                            "Oczekiwano {} elementów {}, znaleziono {}",
+                            count, selector, found
+                        )),
+                    ),
+                    (_, Some(text)) if found == 0 => (
+                        false,
+                        Some(format!(
+                            "Nie znaleziono elementu {} z tekstem „{}”",
+                            selector, text
+                        )),
+                    ),
+                    (None, None) if found == 0 => (false, Some(not_found(selector))),
+                    _ => (true, None),
+                }
+            }
+            Assertion::Attribute {
+                selector,
+                name,
+                value,
+            } => {
+                let elements = match self.select(selector) {
+                    Ok(elements) if elements.is_empty() => {
+                        return (false, Some(not_found(selector)))
+                    }
+                    Ok(elements) => elements,
+                    Err(e) => return (false, Some(e)),
+                };
+                let actual: Vec<&str> = elements
+                    .iter()
+                    .filter_map(|element| element.value().attr(name))
+                    .collect();
+                match (value, actual.first()) {
+                    (_, None) => (
+                        false,
+                        Some(format!("Element {} nie ma atrybutu {}", selector, name)),
+                    ),
+                    (Some(value), Some(first))
+                        if !actual.iter().any(|actual| actual.trim() == value.trim()) =>
+                    {
+                        (
+                            false,
+                            Some(format!(
+                                "Atrybut {} elementu {} ma wartość „{}”, oczekiwano „{}”",
+                                name, selector, first, value
+                            )),
+                        )
+                    }
+                    _ => (true, None),
+                }
+            }
+            Assertion::CssProperty {
+                selector,
+                property,
+                value,
+            } => {
+                let elements = match self.select(selector) {
+                    Ok(elements) if elements.is_empty() => {
+                        return (false, Some(not_found(selector)))
+                    }
+                    Ok(elements) => elements,
+                    Err(e) => return (false, Some(e)),
+                };
+                let property = property.to_ascii_lowercase();
+                elements
+                    .iter()
+                    .map(|element| {
+                        let applied = self.stylesheet.value_for(element, &property);
+                        judge_value(selector, &property, value.as_deref(), applied)
+                    })
+                    .find(|(passed, _)| !passed)
+                    .unwrap_or((true, None))
+            }
+        }
+    }
+
+    fn select(&self, selector: &str) -> Result<Vec<ElementRef<'_>>, String> {
+        let document = self
+            .document
+            .as_ref()
+            .ok_or("Ćwiczenie nie ma strony HTML, do której można zastosować style")?;
+        let selector = Selector::parse(selector)
+            .map_err(|_| format!("Nieprawidłowy selektor w ćwiczeniu: {}", selector))?;
+        Ok(document.select(&selector).collect())
+    }
+}
+
+fn judge_value(
+    selector: &str,
+    property: &str,
+    expected: Option<&str>,
+    actual: Option<String>,
+) -> (bool, Option<String>) {
+    match (expected, actual) {
+        (_, None) => (
+            false,
+            Some(format!(
+                "Element {} nie ma ustawionej właściwości {}",
+                selector, property
+            )),
+        ),
+        (Some(expected), Some(actual))
+            if css::normalize_value(&actual) != css::normalize_value(expected) =>
+        {
+            (
+                false,
+                Some(format!(
+                    "Właściwość {} elementu {} ma wartość „{}”, oczekiwano „{}”",
+                    property, selector, actual, expected
+                )),
+            )
+        }
+        _ => (true, None),
+    }
+}
+
+fn not_found(selector: &str) -> String {
+    format!("Nie znaleziono elementu {}", selector)
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(code: &str, language: &str, assertion: serde_json::Value) -> (bool, Option<String>) {
+        let assertion: MarkupAssertion = serde_json::from_value(assertion).unwrap();
+        Page::parse(code, language, None)
+            .unwrap()
+            .check(&assertion.assertion)
+    }
+
+    #[test]
+    fn checks_elements_attributes_and_styles() {
+        let page = r#"<!DOCTYPE html>
+<html><head><style>h1 { color: navy; }</style></head>
+<body><h1>Witaj</h1><a href="https://example.com">link</a></body></html>"#;
+
+        assert!(
+            !check(
+                page,
+                "html",
+                serde_json::json!({ "type": "element_exists", "selector": "h1", "text": "witaj" })
+            )
+            .0
+        );
+        assert!(
+            check(
+                page,
+                "html",
+                serde_json::json!({ "type": "element_exists", "selector": "h1", "text": "Witaj" })
+            )
+            .0
+        );
+        assert!(check(page, "html", serde_json::json!({ "type": "attribute", "selector": "a", "name": "href", "value": "https://example.com" })).0);
+        assert!(check(page, "html", serde_json::json!({ "type": "css_property", "selector": "h1", "property": "color", "value": "navy" })).0);
+        assert_eq!(
+            check(
+                page,
+                "html",
+                serde_json::json!({ "type": "css_property", "selector": "a", "property": "color" })
+            ),
+            (
+                false,
+                Some("Element a nie ma ustawionej właściwości color".to_string())
+            )
+        );
+        assert!(check(page, "html", serde_json::json!({ "type": "valid_markup" })).0);
+        assert!(check(".box { padding: 8px }", "css", serde_json::json!({ "type": "css_property", "selector": ".box", "property": "padding", "value": "8px" })).0);
+    }
+
+    #[test]
+    fn checks_a_lesson_solution_against_its_assertions() {
+        let content = serde_json::json!({
+            "html": "<p class=\"note\">Uwaga</p>",
+            "assertions": [
+                { "type": "css_property", "selector": ".note", "property": "color", "value": "red" },
+                { "type": "css_property", "selector": ".note", "property": "font-weight", "description": "Pogrubiona notatka" },
+            ],
+        });
+
+        assert_eq!(
+            check_solution(".note { color: red; font-weight: bold }", "css", &content),
+            Ok(Some(Vec::new()))
+        );
+        let failed = check_solution(".note { color: red }", "css", &content)
+            .unwrap()
+            .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert!(
+            failed[0].starts_with("Pogrubiona notatka: "),
+            "{}",
+            failed[0]
+        );
+        assert_eq!(
+            check_solution("<p></p>", "html", &serde_json::json!({})),
+            Ok(None)
+        );
+    }
+}
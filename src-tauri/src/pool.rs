@@ -0,0 +1,230 @@
+//! Limits how many sandboxed programs execute at once: runs, project
+//! checks, formatters and interactive sessions. Those over the limit wait
+//! in a first-come, first-served queue and can follow their place in it;
+//! identical requests from one window share a single run.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Window};
+use tokio::sync::{mpsc, oneshot, watch};
+
+use crate::CodeValidationResponse;
+
+static POOL: OnceLock<Pool> = OnceLock::new();
+
+fn pool() -> &'static Pool {
+    POOL.get_or_init(|| Pool {
+        state: Mutex::new(State {
+            max_parallel: default_parallelism(),
+            running: 0,
+            queue: VecDeque::new(),
+        }),
+        in_flight: Mutex::new(HashMap::new()),
+    })
+}
+
+/// Half the cores, so the UI stays responsive, but never more than four:
+/// compilers already use several cores each.
+fn default_parallelism() -> usize {
+    std::thread::available_parallelism()
+        .map(|cores| (cores.get() / 2).clamp(1, 4))
+        .unwrap_or(1)
+}
+
+struct Pool {
+    state: Mutex<State>,
+    /// Requests being answered, by window label and request; the receiver
+    /// gets the result once the first of them finishes.
+    in_flight: Mutex<HashMap<RequestKey, watch::Receiver<Option<Shared>>>>,
+}
+
+type Shared = Result<CodeValidationResponse, String>;
+/// Window label and the request itself.
+type RequestKey = (String, String);
+
+struct State {
+    max_parallel: usize,
+    running: usize,
+    queue: VecDeque<Waiter>,
+}
+
+struct Waiter {
+    start: oneshot::Sender<Permit>,
+    position: Option<mpsc::UnboundedSender<usize>>,
+}
+
+/// A place among the running programs; dropping it lets the next queued
+/// run start.
+pub struct Permit {
+    _private: (),
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = pool().state.lock().unwrap();
+        state.running -= 1;
+        start_waiting(&mut state);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PoolStatus {
+    max_parallel: usize,
+    running: usize,
+    queued: usize,
+}
+
+/// Waits for a free place. While queued, `position` receives the run's
+/// place in the queue, counted from 1, whenever it changes, and 0 once
+/// the run starts.
+pub async fn acquire(position: Option<mpsc::UnboundedSender<usize>>) -> Permit {
+    let started = {
+        let mut state = pool().state.lock().unwrap();
+        if state.running < state.max_parallel && state.queue.is_empty() {
+            state.running += 1;
+            return Permit { _private: () };
+        }
+
+        let (start, started) = oneshot::channel();
+        if let Some(position) = &position {
+            let _ = position.send(state.queue.len() + 1);
+        }
+        state.queue.push_back(Waiter { start, position });
+        started
+    };
+
+    match started.await {
+        Ok(permit) => permit,
+        // The pool never drops a waiter without starting it.
+        Err(_) => unreachable!("queued run dropped without a permit"),
+    }
+}
+
+/// Starts queued runs while there is room, then tells the rest where they
+/// are now. A waiter that gave up is skipped without taking a place.
+fn start_waiting(state: &mut State) {
+    while state.running < state.max_parallel {
+        let Some(waiter) = state.queue.pop_front() else {
+            break;
+        };
+        // Counted before the permit leaves: it may be dropped right away,
+        // and that drop waits for this lock.
+        state.running += 1;
+        match waiter.start.send(Permit { _private: () }) {
+            Ok(()) => {
+                if let Some(position) = waiter.position {
+                    let _ = position.send(0);
+                }
+            }
+            Err(permit) => {
+                std::mem::forget(permit);
+                state.running -= 1;
+            }
+        }
+    }
+
+    for (index, waiter) in state.queue.iter().enumerate() {
+        if let Some(position) = &waiter.position {
+            let _ = position.send(index + 1);
+        }
+    }
+}
+
+/// Payload of the `run-queued` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunQueuedEvent {
+    /// The id the frontend gave the run, if it gave one.
+    run_id: Option<String>,
+    /// Place in the queue counted from 1; 0 once the run has started.
+    position: usize,
+}
+
+/// A sender for [`acquire`] that reports the run's place in the queue to
+/// `window` as `run-queued` events.
+pub fn report_position(window: Window, run_id: Option<String>) -> mpsc::UnboundedSender<usize> {
+    let (sender, mut positions) = mpsc::unbounded_channel();
+    tauri::async_runtime::spawn(async move {
+        while let Some(position) = positions.recv().await {
+            let event = RunQueuedEvent {
+                run_id: run_id.clone(),
+                position,
+            };
+            if let Err(e) = window.emit_to(window.label(), "run-queued", event) {
+                println!("Failed to emit queue position: {}", e);
+            }
+        }
+    });
+    sender
+}
+
+/// Changes how many runs may execute at once; `None` only reports the
+/// current state.
+#[tauri::command]
+pub fn configure_execution_pool(max_parallel: Option<usize>) -> Result<PoolStatus, String> {
+    let mut state = pool().state.lock().unwrap();
+    if let Some(max_parallel) = max_parallel {
+        if !(1..=32).contains(&max_parallel) {
+            return Err("Liczba równoległych uruchomień musi wynosić od 1 do 32".to_string());
+        }
+        println!("Execution pool: up to {} parallel runs", max_parallel);
+        state.max_parallel = max_parallel;
+        start_waiting(&mut state);
+    }
+
+    Ok(PoolStatus {
+        max_parallel: state.max_parallel,
+        running: state.running,
+        queued: state.queue.len(),
+    })
+}
+
+/// Runs `run` unless the same window already asked for the same `request`
+/// and is still waiting for it; then its result is shared instead.
+pub async fn deduplicate(
+    window: &str,
+    request: String,
+    run: impl Future<Output = Shared>,
+) -> Shared {
+    let key = (window.to_string(), request);
+    let leader = {
+        let mut in_flight = pool().in_flight.lock().unwrap();
+        match in_flight.get(&key) {
+            Some(result) => Err(result.clone()),
+            None => {
+                let (sender, receiver) = watch::channel(None);
+                in_flight.insert(key.clone(), receiver);
+                Ok(sender)
+            }
+        }
+    };
+    let sender = match leader {
+        Ok(sender) => sender,
+        Err(mut result) => {
+            // Fails only when the first request was dropped with its
+            // window; this one then runs on its own.
+            if let Ok(result) = result.wait_for(Option::is_some).await {
+                println!("Sharing the result of an identical request");
+                return result.clone().unwrap();
+            }
+            return run.await;
+        }
+    };
+
+    let guard = InFlight(key);
+    let result = run.await;
+    let _ = sender.send(Some(result.clone()));
+    drop(guard);
+    result
+}
+
+/// Removes the request from the in-flight map however its run ends.
+struct InFlight(RequestKey);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        pool().in_flight.lock().unwrap().remove(&self.0);
+    }
+}
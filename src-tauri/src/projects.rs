@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::pool;
+use crate::runner::languages::{self, Engine, Execution};
+use crate::runner::process::Observer;
+use crate::runner::{
+    unit_tests, Comparison, Confinement, Diagnostic, LanguageRunner, ResourceLimits, Sandbox,
+    UnitTestResult,
+};
+use crate::supabase::{self, SupabaseClient};
+use crate::{response_from_execution, CodeValidationResponse, Grading};
+
+/// One file of the learner's project, with a path relative to its root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectFile {
+    path: String,
+    content: String,
+}
+
+/// How a project is graded; the lesson's `content` for `project` lessons.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSpec {
+    #[serde(default)]
+    requirements: Vec<String>,
+    /// File the program starts from; the first file of the project when
+    /// not set.
+    #[serde(default)]
+    entrypoint: Option<String>,
+    #[serde(default)]
+    checks: Vec<RequirementCheck>,
+    #[serde(default)]
+    limits: Option<ResourceLimits>,
+    #[serde(default)]
+    engine: Option<Engine>,
+}
+
+/// An automatic check of one of the project's requirements. A requirement
+/// is met when all of its checks pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequirementCheck {
+    /// Index into `requirements`.
+    requirement: usize,
+    /// Shown in the checklist instead of the generated description.
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(flatten)]
+    check: Check,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Check {
+    /// The entrypoint exits cleanly.
+    Runs {
+        #[serde(default)]
+        stdin: Option<String>,
+    },
+    /// The entrypoint prints the expected output.
+    Output {
+        #[serde(default)]
+        stdin: Option<String>,
+        #[serde(alias = "expectedOutput")]
+        expected_output: String,
+        #[serde(default)]
+        comparison: Option<Comparison>,
+    },
+    /// Something in the entrypoint's output matches `pattern`.
+    OutputPattern {
+        #[serde(default)]
+        stdin: Option<String>,
+        pattern: String,
+    },
+    /// Hidden unit tests, run as a separate file next to the project's
+    /// files so they can import them.
+    Tests { code: String },
+    /// The file exists once the program has run; it may be one the
+    /// learner wrote or one the program created.
+    FileExists { path: String },
+    /// The file exists and something in it matches `pattern`.
+    FileContains { path: String, pattern: String },
+}
+
+impl Check {
+    fn describe(&self) -> String {
+        match self {
+            Check::Runs { .. } => "Program uruchamia się bez błędów".to_string(),
+            Check::Output { .. } => "Program wypisuje oczekiwany wynik".to_string(),
+            Check::OutputPattern { pattern, .. } => {
+                format!("Wynik programu pasuje do wzorca /{}/", pattern)
+            }
+            Check::Tests { .. } => "Testy jednostkowe przechodzą".to_string(),
+            Check::FileExists { path } => format!("Plik {} istnieje", path),
+            Check::FileContains { path, pattern } => {
+                format!("Plik {} zawiera /{}/", path, pattern)
+            }
+        }
+    }
+
+    /// File checks look at what the runs left behind, so they go last.
+    fn inspects_files(&self) -> bool {
+        matches!(self, Check::FileExists { .. } | Check::FileContains { .. })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckResult {
+    description: String,
+    passed: bool,
+    /// Why the check failed.
+    message: Option<String>,
+    tests: Vec<UnitTestResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequirementResult {
+    requirement: String,
+    met: bool,
+    /// `false` when the lesson has no automatic check for the requirement;
+    /// it is then left to the learner.
+    checked: bool,
+    checks: Vec<CheckResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectValidationResponse {
+    /// Every requirement that has checks is met.
+    all_met: bool,
+    met_count: usize,
+    total: usize,
+    requirements: Vec<RequirementResult>,
+    /// What the entrypoint printed when run without input.
+    output: String,
+    stderr: String,
+    compile_error: Option<String>,
+    diagnostics: Vec<Diagnostic>,
+    confinement: Confinement,
+}
+
+/// Grades a multi-file project: writes `files` to a sandbox, runs the
+/// entrypoint and the requirement checks, and returns a checklist. The
+/// checks come either straight from the editor as `project` or from the
+/// lesson's `content`.
+#[tauri::command]
+pub async fn validate_project(
+    client: State<'_, SupabaseClient>,
+    files: Vec<ProjectFile>,
+    language: String,
+    project: Option<ProjectSpec>,
+    lesson_id: Option<String>,
+    limits: Option<ResourceLimits>,
+) -> Result<ProjectValidationResponse, String> {
+    let spec = match (project, lesson_id) {
+        (Some(project), _) => project,
+        (None, Some(lesson_id)) => {
+            let lesson = supabase::fetch_lesson(&client, lesson_id).await?;
+            serde_json::from_value(lesson.content)
+                .map_err(|e| format!("Nieprawidłowy opis projektu w lekcji: {}", e))?
+        }
+        (None, None) => return Err("Podaj project albo lessonId".to_string()),
+    };
+
+    if files.is_empty() {
+        return Err("Projekt nie zawiera żadnych plików".to_string());
+    }
+    if spec.checks.is_empty() {
+        return Err("Projekt nie ma żadnych automatycznych sprawdzeń".to_string());
+    }
+    if let Some(check) = spec
+        .checks
+        .iter()
+        .find(|check| check.requirement >= spec.requirements.len())
+    {
+        return Err(format!(
+            "Sprawdzenie odwołuje się do nieistniejącego wymagania {}",
+            check.requirement
+        ));
+    }
+
+    let entrypoint = spec
+        .entrypoint
+        .clone()
+        .unwrap_or_else(|| files[0].path.clone());
+    if !files.iter().any(|file| file.path == entrypoint) {
+        return Err(format!("Brak pliku startowego {}", entrypoint));
+    }
+
+    let runner = languages::select(&language, spec.engine.unwrap_or_default())?;
+    let limits = limits.or(spec.limits.clone()).unwrap_or_default().clamped();
+    let _permit = pool::acquire(None).await;
+    let sandbox = Sandbox::new(limits.clone())?;
+    let written = files
+        .iter()
+        .map(|file| sandbox.write_nested(&file.path, &file.content))
+        .collect::<Result<Vec<_>, _>>()?;
+    let entrypoint = sandbox.project_path(&entrypoint)?;
+
+    println!(
+        "Validating project: {} files, {} checks",
+        files.len(),
+        spec.checks.len()
+    );
+    let mut project = Project {
+        runner,
+        sandbox,
+        entrypoint,
+        files: written,
+        limits,
+        runs: HashMap::new(),
+    };
+
+    let main_run = project.judge(&Check::Runs { stdin: None }).await;
+    let mut results: Vec<Option<CheckResult>> = spec.checks.iter().map(|_| None).collect();
+    for file_checks in [false, true] {
+        for (index, check) in spec.checks.iter().enumerate() {
+            if check.check.inspects_files() == file_checks {
+                let mut result = project.check(&check.check).await;
+                if let Some(description) = &check.description {
+                    result.description = description.clone();
+                }
+                results[index] = Some(result);
+            }
+        }
+    }
+
+    let mut requirements: Vec<RequirementResult> = spec
+        .requirements
+        .iter()
+        .map(|requirement| RequirementResult {
+            requirement: requirement.clone(),
+            met: false,
+            checked: false,
+            checks: Vec::new(),
+        })
+        .collect();
+    for (check, result) in spec.checks.iter().zip(results) {
+        let requirement = &mut requirements[check.requirement];
+        requirement.checked = true;
+        requirement.checks.extend(result);
+    }
+    for requirement in &mut requirements {
+        requirement.met =
+            requirement.checked && requirement.checks.iter().all(|check| check.passed);
+    }
+
+    let met_count = requirements.iter().filter(|r| r.met).count();
+    Ok(ProjectValidationResponse {
+        all_met: requirements.iter().all(|r| r.met || !r.checked),
+        met_count,
+        total: requirements.len(),
+        requirements,
+        output: main_run.output,
+        stderr: main_run.stderr,
+        compile_error: main_run.compile_error,
+        diagnostics: main_run.diagnostics,
+        confinement: project.sandbox.confinement().clone(),
+    })
+}
+
+/// A project written to its sandbox, with the runs made so far.
+struct Project {
+    runner: &'static dyn LanguageRunner,
+    sandbox: Sandbox,
+    entrypoint: PathBuf,
+    files: Vec<PathBuf>,
+    limits: ResourceLimits,
+    /// Entrypoint runs by stdin; checks with the same input share one.
+    runs: HashMap<Option<String>, Result<Execution, String>>,
+}
+
+impl Project {
+    async fn check(&mut self, check: &Check) -> CheckResult {
+        let description = check.describe();
+        let (passed, message) = match check {
+            Check::FileExists { path } => match self.sandbox.project_path(path) {
+                Ok(file) if file.is_file() => (true, None),
+                Ok(_) => (false, Some(format!("Nie znaleziono pliku {}", path))),
+                Err(e) => (false, Some(e)),
+            },
+            Check::FileContains { path, pattern } => self.file_contains(path, pattern),
+            _ => {
+                let response = self.judge(check).await;
+                return CheckResult {
+                    description,
+                    passed: response.is_correct,
+                    message: response.compile_error.or(response.error),
+                    tests: response.tests,
+                };
+            }
+        };
+        CheckResult {
+            description,
+            passed,
+            message,
+            tests: Vec::new(),
+        }
+    }
+
+    /// Runs the program for a check and grades the run.
+    async fn judge(&mut self, check: &Check) -> CodeValidationResponse {
+        let stdin = match check {
+            Check::Runs { stdin }
+            | Check::Output { stdin, .. }
+            | Check::OutputPattern { stdin, .. } => stdin.clone(),
+            Check::Tests { code } => return self.run_tests(code).await,
+            Check::FileExists { .. } | Check::FileContains { .. } => None,
+        };
+        if !self.runs.contains_key(&stdin) {
+            let execution = self.execute(&self.entrypoint, stdin.as_deref()).await;
+            self.runs.insert(stdin.clone(), execution);
+        }
+        let execution = self.runs[&stdin].clone();
+
+        let default_comparison = Comparison::default();
+        let grading = match check {
+            Check::Output {
+                expected_output,
+                comparison,
+                ..
+            } => Grading::Output {
+                expected: expected_output,
+                comparison: comparison.as_ref().unwrap_or(&default_comparison),
+            },
+            _ => Grading::RunOnly,
+        };
+        let mut response = self.respond(execution, &grading);
+
+        if let (Check::OutputPattern { pattern, .. }, true) = (check, response.is_correct) {
+            match Regex::new(pattern) {
+                Ok(regex) if regex.is_match(&response.stdout) => {}
+                Ok(_) => {
+                    response.is_correct = false;
+                    response.error =
+                        Some(format!("Wynik programu nie pasuje do wzorca /{}/", pattern));
+                }
+                Err(e) => {
+                    response.is_correct = false;
+                    response.error = Some(format!("Nieprawidłowy wzorzec: {}", e));
+                }
+            }
+        }
+        response
+    }
+
+    /// The tests go into their own file, named after the nonce so it
+    /// can't clash with the learner's, and import the project like any
+    /// other module would.
+    async fn run_tests(&mut self, tests: &str) -> CodeValidationResponse {
+        let nonce = unit_tests::nonce();
+        let grading = Grading::UnitTests {
+            tests,
+            nonce: nonce.clone(),
+        };
+        let Some(harness) = self.runner.with_tests("", tests, &nonce) else {
+            return self.respond(
+                Err(format!(
+                    "Testy jednostkowe nie są obsługiwane dla {}",
+                    self.runner.display_name()
+                )),
+                &grading,
+            );
+        };
+
+        let extension = self
+            .entrypoint
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let execution = match self
+            .sandbox
+            .write_file(&format!("{}.{}", nonce, extension), &harness)
+        {
+            Ok(file) => self.execute(&file, None).await,
+            Err(e) => Err(e),
+        };
+        self.respond(execution, &grading)
+    }
+
+    async fn execute(&self, entrypoint: &Path, stdin: Option<&str>) -> Result<Execution, String> {
+        languages::execute_project(
+            self.runner,
+            &self.sandbox,
+            entrypoint,
+            &self.files,
+            stdin,
+            &self.limits,
+            Observer::default(),
+        )
+        .await
+    }
+
+    fn respond(
+        &self,
+        execution: Result<Execution, String>,
+        grading: &Grading,
+    ) -> CodeValidationResponse {
+        response_from_execution(
+            execution,
+            self.runner,
+            grading,
+            &self.limits,
+            self.sandbox.confinement(),
+        )
+    }
+
+    fn file_contains(&self, path: &str, pattern: &str) -> (bool, Option<String>) {
+        let regex = match Regex::new(pattern) {
+            Ok(regex) => regex,
+            Err(e) => return (false, Some(format!("Nieprawidłowy wzorzec: {}", e))),
+        };
+        let contents = match self
+            .sandbox
+            .project_path(path)
+            .and_then(|file| std::fs::read_to_string(file).map_err(|e| e.to_string()))
+        {
+            Ok(contents) => contents,
+            Err(_) => return (false, Some(format!("Nie znaleziono pliku {}", path))),
+        };
+        if regex.is_match(&contents) {
+            (true, None)
+        } else {
+            (
+                false,
+                Some(format!("Plik {} nie zawiera /{}/", path, pattern)),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_checks_from_lesson_content() {
+        let content = serde_json::json!({
+            "type": "project",
+            "title": "Kalkulator",
+            "requirements": ["Moduł calc.py", "Dodawanie działa"],
+            "entrypoint": "main.py",
+            "checks": [
+                { "requirement": 0, "type": "file_exists", "path": "calc.py" },
+                { "requirement": 1, "type": "output", "stdin": "2 3", "expectedOutput": "5" },
+                { "requirement": 1, "type": "tests", "code": "def test_add():\n    pass" }
+            ]
+        });
+        let spec: ProjectSpec = serde_json::from_value(content).unwrap();
+
+        assert_eq!(spec.entrypoint.as_deref(), Some("main.py"));
+        assert_eq!(spec.checks.len(), 3);
+        assert!(spec.checks[0].check.inspects_files());
+        assert!(matches!(
+            &spec.checks[1].check,
+            Check::Output { stdin: Some(stdin), expected_output, .. }
+                if stdin == "2 3" && expected_output == "5"
+        ));
+    }
+}
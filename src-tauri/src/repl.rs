@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, State, Window};
+use tokio::sync::mpsc;
+
+use crate::pool::{self, Permit};
+use crate::runner::languages;
+use crate::runner::process::{self, Interactive, Stream};
+use crate::runner::{Confinement, LimitKind, ResourceLimits, Sandbox};
+use crate::runs::forward;
+
+/// A session with no input for this long is closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+static SESSION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Interactive Python and Node sessions, by session id. Each one runs in
+/// its own sandbox with the same confinement as `validate_code`.
+#[derive(Default)]
+pub struct Repls {
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+}
+
+struct Session {
+    /// Label of the window the session belongs to; closing the window
+    /// ends it.
+    window: String,
+    input: mpsc::UnboundedSender<String>,
+    /// Tells this session apart from a later one with the same id.
+    serial: u64,
+}
+
+impl Repls {
+    /// Ends every session started from the window. Dropping a session's
+    /// input channel stops its process.
+    pub fn close_window(&self, label: &str) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .retain(|_, session| session.window != label);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplEnd {
+    /// The interpreter quit, e.g. after `exit()` or a crash.
+    Exited,
+    /// Nothing was typed for [`IDLE_TIMEOUT`].
+    Idle,
+    /// Stopped with `stop_repl` or by closing the window.
+    Closed,
+}
+
+/// Payload of the `repl-output` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplOutputEvent {
+    session_id: String,
+    stream: Stream,
+    data: String,
+}
+
+/// Payload of the `repl-exited` event, sent once per session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplExitedEvent {
+    session_id: String,
+    reason: ReplEnd,
+    message: String,
+}
+
+/// Starts an interactive interpreter. Its output, prompts included, comes
+/// as `repl-output` events; `repl-exited` says when and why it ended.
+#[tauri::command]
+pub async fn start_repl(
+    window: Window,
+    repls: State<'_, Repls>,
+    session_id: String,
+    language: String,
+    limits: Option<ResourceLimits>,
+) -> Result<Confinement, String> {
+    let runner =
+        languages::find(&language).ok_or_else(|| format!("Unsupported language: {}", language))?;
+    let args = runner.repl_args().ok_or_else(|| {
+        format!(
+            "Tryb interaktywny nie jest dostępny dla {}",
+            runner.display_name()
+        )
+    })?;
+    if repls.sessions.lock().unwrap().contains_key(&session_id) {
+        return Err(format!("Sesja {} już działa", session_id));
+    }
+
+    let program = languages::locate(runner)
+        .await
+        .map_err(|e| format!("Nie można uruchomić {}: {}", runner.display_name(), e))?
+        .path;
+    let limits = limits.unwrap_or_default().clamped();
+    // Held for the whole session: the interpreter counts as a run.
+    let permit = pool::acquire(None).await;
+    let sandbox = Sandbox::new(limits.clone())?;
+    let mut cmd = sandbox.command(&program);
+    cmd.args(args);
+
+    let (output_tx, output_rx) = mpsc::unbounded_channel();
+    let process = Interactive::spawn(cmd, &limits, output_tx)
+        .map_err(|e| format!("Nie można uruchomić {}: {}", runner.display_name(), e))?;
+
+    let (input_tx, input_rx) = mpsc::unbounded_channel();
+    let serial = SESSION_COUNTER.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut sessions = repls.sessions.lock().unwrap();
+        if sessions.contains_key(&session_id) {
+            return Err(format!("Sesja {} już działa", session_id));
+        }
+        sessions.insert(
+            session_id.clone(),
+            Session {
+                window: window.label().to_string(),
+                input: input_tx,
+                serial,
+            },
+        );
+    }
+
+    println!("REPL session {} started ({})", session_id, language);
+    let confinement = sandbox.confinement().clone();
+    let session = Running {
+        id: session_id,
+        serial,
+        sessions: repls.sessions.clone(),
+        sandbox,
+        limits,
+        permit,
+    };
+    tauri::async_runtime::spawn(session.drive(window, process, input_rx, output_rx));
+    Ok(confinement)
+}
+
+/// Sends one line to the interpreter, as if typed and followed by Enter.
+#[tauri::command]
+pub fn send_repl_input(
+    repls: State<'_, Repls>,
+    session_id: String,
+    input: String,
+) -> Result<(), String> {
+    let sessions = repls.sessions.lock().unwrap();
+    let session = sessions
+        .get(&session_id)
+        .ok_or_else(|| format!("Sesja {} nie istnieje", session_id))?;
+
+    let line = if input.ends_with('\n') {
+        input
+    } else {
+        input + "\n"
+    };
+    session
+        .input
+        .send(line)
+        .map_err(|_| format!("Sesja {} została zakończona", session_id))
+}
+
+/// Ends a session. Returns `false` when it had already ended.
+#[tauri::command]
+pub fn stop_repl(repls: State<'_, Repls>, session_id: String) -> Result<bool, String> {
+    Ok(repls.sessions.lock().unwrap().remove(&session_id).is_some())
+}
+
+/// What a session's task owns besides the process itself.
+struct Running {
+    id: String,
+    serial: u64,
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    /// Kept until the process is gone; dropping it removes the directory.
+    sandbox: Sandbox,
+    limits: ResourceLimits,
+    /// The session's place in the execution pool, given up when it ends.
+    permit: Permit,
+}
+
+impl Running {
+    /// Which limit ended the interpreter, judged the way a one-off run
+    /// would be. Its stderr went to the learner, so only the exit status
+    /// and the output allowance count.
+    fn limit_hit(&self, process: &Interactive, status: Option<ExitStatus>) -> Option<LimitKind> {
+        let output = process::RunOutput {
+            status,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            timed_out: false,
+            cancelled: false,
+            output_truncated: process.output_exceeded(),
+            cpu_time: None,
+            duration: Duration::ZERO,
+        };
+        LimitKind::detect(&output, &self.limits)
+    }
+
+    /// Tells the learner something about the session in its own output.
+    fn notice(&self, window: &Window, message: &str) {
+        let event = ReplOutputEvent {
+            session_id: self.id.clone(),
+            stream: Stream::Stderr,
+            data: message.to_string(),
+        };
+        if let Err(e) = window.emit_to(window.label(), "repl-output", event) {
+            println!("Failed to emit REPL output: {}", e);
+        }
+    }
+
+    /// Feeds input to the interpreter until it quits, goes idle or the
+    /// session is closed, then reports how it ended.
+    async fn drive(
+        self,
+        window: Window,
+        mut process: Interactive,
+        mut input: mpsc::UnboundedReceiver<String>,
+        output: mpsc::UnboundedReceiver<process::Chunk>,
+    ) {
+        let forwarder = tauri::async_runtime::spawn(forward(output, {
+            let (window, session_id) = (window.clone(), self.id.clone());
+            move |stream, data| {
+                let event = ReplOutputEvent {
+                    session_id: session_id.clone(),
+                    stream,
+                    data,
+                };
+                if let Err(e) = window.emit_to(window.label(), "repl-output", event) {
+                    println!("Failed to emit REPL output: {}", e);
+                }
+            }
+        }));
+
+        let ended = loop {
+            tokio::select! {
+                status = process.exited() => break Ok(status),
+                line = input.recv() => match line {
+                    // A closed pipe means the interpreter is gone, which
+                    // `exited` reports on the next turn.
+                    Some(line) => {
+                        if let Err(e) = process.send(line) {
+                            if e.kind() == std::io::ErrorKind::WouldBlock {
+                                self.notice(&window, "Interpreter nie odczytuje wejścia, linia pominięta.\n");
+                            }
+                        }
+                    }
+                    None => break Err(ReplEnd::Closed),
+                },
+                _ = tokio::time::sleep(IDLE_TIMEOUT) => break Err(ReplEnd::Idle),
+            }
+        };
+
+        let (reason, message) = match ended {
+            Ok(status) => {
+                let message = match (self.limit_hit(&process, status), status) {
+                    (Some(limit), _) => limit.message(&self.limits),
+                    (None, Some(status)) if !status.success() => process::describe_failure(&status),
+                    _ => "Sesja zakończona.".to_string(),
+                };
+                (ReplEnd::Exited, message)
+            }
+            Err(reason) => {
+                process.stop().await;
+                let message = match reason {
+                    ReplEnd::Idle => format!(
+                        "Sesja zamknięta po {} minutach bezczynności.",
+                        IDLE_TIMEOUT.as_secs() / 60
+                    ),
+                    _ => "Sesja zamknięta.".to_string(),
+                };
+                (reason, message)
+            }
+        };
+
+        let _ = forwarder.await;
+        {
+            let mut sessions = self.sessions.lock().unwrap();
+            if sessions
+                .get(&self.id)
+                .is_some_and(|session| session.serial == self.serial)
+            {
+                sessions.remove(&self.id);
+            }
+        }
+        drop(self.sandbox);
+        drop(self.permit);
+
+        println!("REPL session {} ended: {:?}", self.id, reason);
+        let event = ReplExitedEvent {
+            session_id: self.id,
+            reason,
+            message,
+        };
+        if let Err(e) = window.emit_to(window.label(), "repl-exited", event) {
+            println!("Failed to emit REPL exit: {}", e);
+        }
+    }
+}
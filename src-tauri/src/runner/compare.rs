@@ -0,0 +1,233 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+/// How an exercise decides that the program's output is right. Set per
+/// exercise through a `comparison` object in the lesson content, e.g.
+/// `{"mode": "numeric", "tolerance": 0.01}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Comparison {
+    pub mode: ComparisonMode,
+    /// Largest accepted absolute difference in `numeric` mode.
+    pub tolerance: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonMode {
+    /// Identical text; only the final newline is ignored.
+    Exact,
+    /// Any run of whitespace, including newlines, counts as one space.
+    #[serde(alias = "whitespaceNormalized")]
+    Whitespace,
+    /// Line by line, ignoring trailing whitespace, `\r\n` and trailing
+    /// blank lines.
+    #[default]
+    #[serde(alias = "trimLines")]
+    TrimLines,
+    #[serde(alias = "caseInsensitive")]
+    CaseInsensitive,
+    /// Numbers may differ by `tolerance`; other tokens must match.
+    Numeric,
+    /// The same lines in any order.
+    #[serde(alias = "unorderedLines")]
+    UnorderedLines,
+    /// The expected output is a regular expression the whole output must
+    /// match.
+    Regex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Same,
+    /// Expected but not printed.
+    Missing,
+    /// Printed but not expected.
+    Extra,
+}
+
+/// One line of the diff between expected and actual output, with 1-based
+/// line numbers on each side.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub expected_line: Option<usize>,
+    pub actual_line: Option<usize>,
+    pub text: String,
+}
+
+impl Default for Comparison {
+    fn default() -> Self {
+        Self {
+            mode: ComparisonMode::default(),
+            tolerance: 1e-6,
+        }
+    }
+}
+
+impl Comparison {
+    pub fn matches(&self, actual: &str, expected: &str) -> Result<bool, String> {
+        let actual = actual.replace("\r\n", "\n");
+        let expected = expected.replace("\r\n", "\n");
+
+        Ok(match self.mode {
+            ComparisonMode::Exact => {
+                actual.strip_suffix('\n').unwrap_or(&actual)
+                    == expected.strip_suffix('\n').unwrap_or(&expected)
+            }
+            ComparisonMode::Whitespace => words(&actual).eq(words(&expected)),
+            ComparisonMode::TrimLines => lines(&actual) == lines(&expected),
+            ComparisonMode::CaseInsensitive => {
+                lines(&actual.to_lowercase()) == lines(&expected.to_lowercase())
+            }
+            ComparisonMode::Numeric => {
+                let (actual, expected): (Vec<_>, Vec<_>) =
+                    (words(&actual).collect(), words(&expected).collect());
+                actual.len() == expected.len()
+                    && actual
+                        .iter()
+                        .zip(&expected)
+                        .all(|(a, e)| numbers_match(a, e, self.tolerance))
+            }
+            ComparisonMode::UnorderedLines => {
+                let (mut actual, mut expected) = (lines(&actual), lines(&expected));
+                actual.sort_unstable();
+                expected.sort_unstable();
+                actual == expected
+            }
+            ComparisonMode::Regex => {
+                let pattern = format!("^(?:{})$", expected.trim());
+                let regex = Regex::new(&pattern)
+                    .map_err(|e| format!("Nieprawidłowe wyrażenie regularne w ćwiczeniu: {}", e))?;
+                regex.is_match(actual.trim())
+            }
+        })
+    }
+
+    /// Line diff of the two outputs, normalised the way this mode compares
+    /// them. Empty for regex mode, where a diff against the pattern would
+    /// only confuse.
+    pub fn diff(&self, actual: &str, expected: &str) -> Vec<DiffLine> {
+        if self.mode == ComparisonMode::Regex {
+            return Vec::new();
+        }
+
+        let (actual, expected) = (actual.replace("\r\n", "\n"), expected.replace("\r\n", "\n"));
+        let (actual, expected) = (lines(&actual), lines(&expected));
+
+        TextDiff::from_slices(&expected, &actual)
+            .iter_all_changes()
+            .map(|change| DiffLine {
+                kind: match change.tag() {
+                    ChangeTag::Equal => DiffKind::Same,
+                    ChangeTag::Delete => DiffKind::Missing,
+                    ChangeTag::Insert => DiffKind::Extra,
+                },
+                expected_line: change.old_index().map(|index| index + 1),
+                actual_line: change.new_index().map(|index| index + 1),
+                text: change.value().to_string(),
+            })
+            .collect()
+    }
+}
+
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace()
+}
+
+fn lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+fn numbers_match(actual: &str, expected: &str, tolerance: f64) -> bool {
+    match (actual.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(a), Ok(e)) => (a - e).abs() <= tolerance,
+        _ => actual == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comparison(mode: ComparisonMode) -> Comparison {
+        Comparison {
+            mode,
+            tolerance: 0.0,
+        }
+    }
+
+    #[test]
+    fn default_ignores_trailing_whitespace_and_crlf() {
+        let default = Comparison::default();
+        assert!(default.matches("a \r\nb\r\n\r\n", "a\nb").unwrap());
+        assert!(!default.matches(" a\nb", "a\nb").unwrap());
+    }
+
+    #[test]
+    fn exact_only_ignores_final_newline() {
+        let exact = comparison(ComparisonMode::Exact);
+        assert!(exact.matches("a b\n", "a b").unwrap());
+        assert!(!exact.matches("a b \n", "a b").unwrap());
+    }
+
+    #[test]
+    fn whitespace_and_case_modes() {
+        assert!(comparison(ComparisonMode::Whitespace)
+            .matches("1   2\n3", "1 2 3")
+            .unwrap());
+        assert!(comparison(ComparisonMode::CaseInsensitive)
+            .matches("Hello World", "hello world")
+            .unwrap());
+    }
+
+    #[test]
+    fn numeric_uses_tolerance() {
+        let numeric = Comparison {
+            mode: ComparisonMode::Numeric,
+            tolerance: 0.01,
+        };
+        assert!(numeric.matches("wynik: 3.1416", "wynik: 3.14").unwrap());
+        assert!(!numeric.matches("wynik: 3.2", "wynik: 3.14").unwrap());
+        assert!(!numeric.matches("3.14 1", "3.14").unwrap());
+    }
+
+    #[test]
+    fn unordered_lines_and_regex() {
+        assert!(comparison(ComparisonMode::UnorderedLines)
+            .matches("b\na\n", "a\nb")
+            .unwrap());
+        let regex = comparison(ComparisonMode::Regex);
+        assert!(regex
+            .matches("Wylosowano: 42\n", r"Wylosowano: \d+")
+            .unwrap());
+        assert!(!regex
+            .matches("Wylosowano: 42 razy", r"Wylosowano: \d+")
+            .unwrap());
+        assert!(regex.matches("x", "(").is_err());
+    }
+
+    #[test]
+    fn diff_marks_missing_and_extra_lines() {
+        let diff = Comparison::default().diff("a\nX\nc\n", "a\nb\nc");
+        assert_eq!(diff.len(), 4);
+        let kinds: Vec<_> = diff.iter().map(|line| line.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DiffKind::Same,
+                DiffKind::Missing,
+                DiffKind::Extra,
+                DiffKind::Same
+            ]
+        );
+        assert_eq!(diff[1].expected_line, Some(2));
+        assert_eq!(diff[2].actual_line, Some(2));
+    }
+}
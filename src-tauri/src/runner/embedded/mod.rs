@@ -0,0 +1,250 @@
+//! JavaScript engine built into the app, for learners without Node.js.
+//!
+//! The engine does not run inside the app's own process: a run starts a
+//! copy of the app binary with [`ARG`] inside the sandbox, so timeouts,
+//! memory limits and killing work exactly as they do for Node. Only the
+//! part of Node that beginner exercises use is provided (see
+//! `prelude.js`).
+
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+
+use boa_engine::builtins::promise::{OperationType, PromiseState};
+use boa_engine::context::HostHooks;
+use boa_engine::error::JsNativeErrorKind;
+use boa_engine::object::builtins::JsPromise;
+use boa_engine::{
+    js_string, Context, JsArgs, JsError, JsNativeError, JsObject, JsResult, JsValue,
+    NativeFunction, Source,
+};
+
+/// First argument that turns the app binary into the engine, followed by
+/// the script path, or `--version`.
+pub const ARG: &str = "--run-embedded-js";
+
+pub const VERSION: &str = "Boa 0.18 (wbudowany)";
+
+const PRELUDE: &str = include_str!("prelude.js");
+
+// Node allows roughly this many nested calls with its default stack.
+const RECURSION_LIMIT: usize = 10_000;
+const STACK_SIZE_LIMIT: usize = 1024 * 1024;
+// JS calls nest native frames in places (callbacks, getters); give them room.
+const THREAD_STACK_BYTES: usize = 64 * 1024 * 1024;
+
+thread_local! {
+    static UNHANDLED: RefCell<Vec<JsObject>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Keeps track of rejected promises without a handler; like in Node, one
+/// that is still unhandled once the microtasks have run ends the program.
+struct Hooks;
+
+impl HostHooks for Hooks {
+    fn promise_rejection_tracker(
+        &self,
+        promise: &JsObject,
+        operation: OperationType,
+        _: &mut Context,
+    ) {
+        UNHANDLED.with(|unhandled| {
+            let mut unhandled = unhandled.borrow_mut();
+            match operation {
+                OperationType::Reject => unhandled.push(promise.clone()),
+                OperationType::Handle => {
+                    unhandled.retain(|other| !JsObject::equals(other, promise))
+                }
+            }
+        });
+    }
+}
+
+/// Runs the engine when the process was started with [`ARG`] and returns
+/// its exit code; `None` for a normal app start.
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Option<i32> {
+    let mut args = args.into_iter().skip(1);
+    if args.next()? != ARG {
+        return None;
+    }
+
+    let Some(script) = args.next() else {
+        eprintln!("Brak ścieżki do skryptu");
+        return Some(2);
+    };
+    if script == "--version" {
+        println!("{}", VERSION);
+        return Some(0);
+    }
+
+    let code = match std::fs::read_to_string(&script) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Nie można odczytać {}: {}", script.to_string_lossy(), e);
+            return Some(2);
+        }
+    };
+
+    let engine = std::thread::Builder::new()
+        .stack_size(THREAD_STACK_BYTES)
+        .spawn(move || run(&code))
+        .ok()?;
+    let code = engine.join().unwrap_or(70);
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+    Some(code)
+}
+
+/// Evaluates the script, then drains promise jobs and timers the way
+/// Node's event loop would.
+fn run(code: &str) -> i32 {
+    let mut context = match Context::builder().host_hooks(&Hooks).build() {
+        Ok(context) => context,
+        Err(e) => {
+            eprintln!("Nie można uruchomić silnika JavaScript: {}", e);
+            return 70;
+        }
+    };
+    context
+        .runtime_limits_mut()
+        .set_recursion_limit(RECURSION_LIMIT);
+    context
+        .runtime_limits_mut()
+        .set_stack_size_limit(STACK_SIZE_LIMIT);
+
+    if let Err(e) = install(&mut context) {
+        eprintln!("Nie można uruchomić silnika JavaScript: {}", e);
+        return 70;
+    }
+
+    let result = context
+        .eval(Source::from_bytes(code))
+        .and_then(|_| event_loop(&mut context));
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            report(&e, &mut context);
+            1
+        }
+    }
+}
+
+fn event_loop(context: &mut Context) -> JsResult<()> {
+    loop {
+        context.run_jobs();
+        if let Some(reason) = unhandled_rejection() {
+            return Err(JsError::from_opaque(reason));
+        }
+        let next_timer = context
+            .global_object()
+            .get(js_string!("__runNextTimer"), context)?;
+        let ran = next_timer
+            .as_callable()
+            .map(|run| run.call(&JsValue::undefined(), &[], context))
+            .transpose()?;
+        if !ran.is_some_and(|ran| ran.to_boolean()) {
+            return Ok(());
+        }
+    }
+}
+
+fn unhandled_rejection() -> Option<JsValue> {
+    let promise = UNHANDLED.with(|unhandled| unhandled.borrow_mut().drain(..).next())?;
+    match JsPromise::from_object(promise).ok()?.state() {
+        PromiseState::Rejected(reason) => Some(reason),
+        _ => None,
+    }
+}
+
+fn install(context: &mut Context) -> JsResult<()> {
+    context.register_global_callable(
+        js_string!("__write"),
+        2,
+        NativeFunction::from_fn_ptr(write),
+    )?;
+    context.register_global_callable(
+        js_string!("__readStdin"),
+        0,
+        NativeFunction::from_fn_ptr(read_stdin),
+    )?;
+    context.register_global_callable(js_string!("__exit"), 1, NativeFunction::from_fn_ptr(exit))?;
+    context.eval(Source::from_bytes(PRELUDE))?;
+    Ok(())
+}
+
+/// `__write(fd, text)`: 1 is stdout, anything else stderr.
+fn write(_: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+    let fd = args.get_or_undefined(0).to_i32(context)?;
+    let text = args
+        .get_or_undefined(1)
+        .to_string(context)?
+        .to_std_string_escaped();
+    let written = if fd == 1 {
+        std::io::stdout().write_all(text.as_bytes())
+    } else {
+        std::io::stderr().write_all(text.as_bytes())
+    };
+    written.map_err(|e| JsNativeError::error().with_message(e.to_string()))?;
+    Ok(JsValue::undefined())
+}
+
+/// `__readStdin()`: everything piped to the program.
+fn read_stdin(_: &JsValue, _: &[JsValue], _: &mut Context) -> JsResult<JsValue> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| JsNativeError::error().with_message(e.to_string()))?;
+    Ok(js_string!(input).into())
+}
+
+/// `__exit(code)`, behind `process.exit`.
+fn exit(_: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+    let code = args.get_or_undefined(0).to_i32(context)?;
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+    std::process::exit(code)
+}
+
+/// Prints an uncaught error the way Node does, close enough for the
+/// JavaScript diagnostics parser to find the message and line.
+fn report(error: &JsError, context: &mut Context) {
+    let message = match error.try_native(context) {
+        Ok(native) if matches!(native.kind, JsNativeErrorKind::RuntimeLimit) => {
+            "RangeError: Maximum call stack size exceeded".to_string()
+        }
+        _ => error.to_string(),
+    };
+
+    let mut stderr = std::io::stderr();
+    let _ = writeln!(stderr, "{}", message);
+    if let Some((line, column)) = syntax_error_position(&message) {
+        let _ = writeln!(stderr, "    at main.js:{}:{}", line, column);
+    }
+}
+
+/// Boa puts the position of a syntax error at the end of the message:
+/// `... at line 2, col 9`.
+fn syntax_error_position(message: &str) -> Option<(u32, u32)> {
+    let (_, position) = message.rsplit_once(" at line ")?;
+    let (line, column) = position.split_once(", col ")?;
+    Some((line.trim().parse().ok()?, column.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_syntax_error_position() {
+        assert_eq!(
+            syntax_error_position(
+                "SyntaxError: unexpected token ';', primary expression at line 2, col 9"
+            ),
+            Some((2, 9))
+        );
+        assert_eq!(
+            syntax_error_position("ReferenceError: x is not defined"),
+            None
+        );
+    }
+}
@@ -0,0 +1,287 @@
+// The parts of Node's globals and built-in modules that beginner exercises
+// use, on top of the three natives registered from Rust: __write(fd, text),
+// __readStdin() and __exit(code).
+(() => {
+  const write = globalThis.__write;
+  const readStdin = globalThis.__readStdin;
+  const exit = globalThis.__exit;
+  delete globalThis.__write;
+  delete globalThis.__readStdin;
+  delete globalThis.__exit;
+
+  // util.inspect, for what console.log prints for non-strings.
+  const inspect = (value, depth = 0, seen = []) => {
+    switch (typeof value) {
+      case 'string':
+        return depth === 0 ? value : `'${value.replace(/'/g, "\\'")}'`;
+      case 'number':
+        return Object.is(value, -0) ? '-0' : String(value);
+      case 'bigint':
+        return `${value}n`;
+      case 'symbol':
+        return value.toString();
+      case 'undefined':
+        return 'undefined';
+      case 'function':
+        return value.name ? `[Function: ${value.name}]` : '[Function (anonymous)]';
+    }
+    if (value === null) return 'null';
+    if (seen.includes(value)) return '[Circular *1]';
+    if (value instanceof Error) return value.stack || `${value.name}: ${value.message}`;
+    if (value instanceof Date) return value.toISOString();
+    if (value instanceof RegExp) return String(value);
+    if (depth > 2) return Array.isArray(value) ? '[Array]' : '[Object]';
+
+    const nested = [...seen, value];
+    const show = (item) => inspect(item, depth + 1, nested);
+    const wrap = (open, items, close) => {
+      if (items.length === 0) return `${open}${close}`;
+      const line = `${open} ${items.join(', ')} ${close}`;
+      return line.length <= 72
+        ? line
+        : `${open}\n${items.map((item) => '  '.repeat(depth + 1) + item).join(',\n')}\n${'  '.repeat(depth)}${close}`;
+    };
+
+    if (Array.isArray(value)) return wrap('[', value.map(show), ']');
+    if (value instanceof Map) {
+      const entries = [...value].map(([k, v]) => `${show(k)} => ${show(v)}`);
+      return `Map(${value.size}) ${wrap('{', entries, '}')}`;
+    }
+    if (value instanceof Set) return `Set(${value.size}) ${wrap('{', [...value].map(show), '}')}`;
+    if (value instanceof Promise) return 'Promise { <pending> }';
+
+    const key = (k) => (/^[A-Za-z_$][\w$]*$/.test(k) ? k : `'${k}'`);
+    const entries = Object.keys(value).map((k) => `${key(k)}: ${show(value[k])}`);
+    const name = value.constructor && value.constructor.name;
+    const prefix = name && name !== 'Object' ? `${name} ` : '';
+    return prefix + wrap('{', entries, '}');
+  };
+
+  // util.format: printf-style placeholders, then the rest space-separated.
+  const format = (...args) => {
+    let rest = args;
+    let out = '';
+    if (typeof args[0] === 'string' && args.length > 1 && args[0].includes('%')) {
+      let index = 1;
+      out = args[0].replace(/%([sdifjoO%])/g, (match, spec) => {
+        if (spec === '%') return '%';
+        if (index >= args.length) return match;
+        const arg = args[index++];
+        switch (spec) {
+          case 's':
+            return typeof arg === 'string' ? arg : inspect(arg, 1);
+          case 'd':
+          case 'i':
+            return String(spec === 'i' ? parseInt(arg) : Number(arg));
+          case 'f':
+            return String(parseFloat(arg));
+          case 'j':
+            return JSON.stringify(arg);
+          default:
+            return inspect(arg, 1);
+        }
+      });
+      rest = args.slice(index);
+      if (rest.length === 0) return out;
+      out += ' ';
+    }
+    return out + rest.map((arg) => inspect(arg)).join(' ');
+  };
+
+  const log = (...args) => write(1, format(...args) + '\n');
+  const error = (...args) => write(2, format(...args) + '\n');
+  globalThis.console = {
+    log,
+    info: log,
+    debug: log,
+    error,
+    warn: error,
+    trace: error,
+    table: (data) => log(data),
+  };
+
+  // Timers run on a virtual clock once the script and its promise jobs are
+  // done; Rust calls __runNextTimer until it returns false.
+  const timers = [];
+  let timerId = 0;
+  let now = 0;
+  const addTimer = (fn, ms, args, repeat) => {
+    const id = ++timerId;
+    const delay = Math.max(0, Number(ms) || 0);
+    timers.push({ id, fn, args, at: now + delay, delay, repeat });
+    return id;
+  };
+  const clearTimer = (id) => {
+    const index = timers.findIndex((timer) => timer.id === id);
+    if (index !== -1) timers.splice(index, 1);
+  };
+  globalThis.setTimeout = (fn, ms, ...args) => addTimer(fn, ms, args, false);
+  globalThis.setInterval = (fn, ms, ...args) => addTimer(fn, ms, args, true);
+  globalThis.setImmediate = (fn, ...args) => addTimer(fn, 0, args, false);
+  globalThis.clearTimeout = clearTimer;
+  globalThis.clearInterval = clearTimer;
+  globalThis.clearImmediate = clearTimer;
+  globalThis.queueMicrotask = (fn) => Promise.resolve().then(fn);
+  Object.defineProperty(globalThis, '__runNextTimer', {
+    value: () => {
+      if (timers.length === 0) return false;
+      let next = 0;
+      for (let i = 1; i < timers.length; i++) {
+        if (timers[i].at < timers[next].at) next = i;
+      }
+      const timer = timers[next];
+      now = timer.at;
+      if (timer.repeat) {
+        timer.at = now + Math.max(1, timer.delay);
+      } else {
+        timers.splice(next, 1);
+      }
+      timer.fn(...timer.args);
+      return true;
+    },
+  });
+
+  let stdin = null;
+  const input = () => (stdin === null ? (stdin = readStdin()) : stdin);
+
+  globalThis.process = {
+    argv: ['node', 'main.js'],
+    env: {},
+    platform: 'embedded',
+    exitCode: undefined,
+    stdout: { write: (text) => (write(1, String(text)), true) },
+    stderr: { write: (text) => (write(2, String(text)), true) },
+    stdin: {},
+    exit: (code) => exit(code ?? globalThis.process.exitCode ?? 0),
+  };
+
+  class AssertionError extends Error {
+    constructor(message) {
+      super(message);
+      this.name = 'AssertionError';
+    }
+  }
+
+  const deepEqual = (a, b, strict) => {
+    if (strict ? Object.is(a, b) : a == b) return true;
+    if (typeof a !== 'object' || typeof b !== 'object' || a === null || b === null) return false;
+    if (strict && Object.getPrototypeOf(a) !== Object.getPrototypeOf(b)) return false;
+    if (a instanceof Map || a instanceof Set) {
+      return deepEqual([...a], [...b], strict);
+    }
+    const keys = Object.keys(a);
+    if (keys.length !== Object.keys(b).length) return false;
+    return keys.every((k) => Object.prototype.hasOwnProperty.call(b, k) && deepEqual(a[k], b[k], strict));
+  };
+
+  const fail = (message, fallback) => {
+    if (message instanceof Error) throw message;
+    throw new AssertionError(message ?? fallback);
+  };
+  const compared = (heading, actual, operator, expected) =>
+    `${heading}:\n\n${inspect(actual, 1)} ${operator} ${inspect(expected, 1)}\n`;
+
+  const assert = (value, message) => {
+    if (!value) fail(message, 'The expression evaluated to a falsy value');
+  };
+  Object.assign(assert, {
+    AssertionError,
+    ok: assert,
+    fail: (message) => fail(message, 'Failed'),
+    equal: (a, b, message) =>
+      a == b || fail(message, compared('Expected values to be loosely equal', a, '==', b)),
+    notEqual: (a, b, message) =>
+      a != b || fail(message, compared('Expected "actual" to be loosely unequal to', a, '!=', b)),
+    strictEqual: (a, b, message) =>
+      Object.is(a, b) || fail(message, compared('Expected values to be strictly equal', a, '!==', b)),
+    notStrictEqual: (a, b, message) =>
+      !Object.is(a, b) ||
+      fail(message, compared('Expected "actual" to be strictly unequal to', a, '===', b)),
+    deepEqual: (a, b, message) =>
+      deepEqual(a, b, false) ||
+      fail(message, compared('Expected values to be loosely deep-equal', a, '!=', b)),
+    deepStrictEqual: (a, b, message) =>
+      deepEqual(a, b, true) ||
+      fail(message, compared('Expected values to be strictly deep-equal', a, '!==', b)),
+    notDeepStrictEqual: (a, b, message) =>
+      !deepEqual(a, b, true) ||
+      fail(message, compared('Expected "actual" not to be strictly deep-equal to', a, '===', b)),
+    throws: (fn, expected, message) => {
+      try {
+        fn();
+      } catch (e) {
+        if (typeof expected === 'function' && expected.prototype !== undefined && !(e instanceof expected)) {
+          throw e;
+        }
+        return;
+      }
+      fail(typeof expected === 'string' ? expected : message, 'Missing expected exception.');
+    },
+    doesNotThrow: (fn) => {
+      fn();
+    },
+  });
+  assert.strict = assert;
+
+  const fs = {
+    readFileSync: (file, options) => {
+      if (file === 0 || file === '/dev/stdin') return input();
+      throw new Error(`ENOENT: no such file or directory, open '${file}'`);
+    },
+  };
+
+  // readline.createInterface({ input: process.stdin }): emits 'line' for
+  // every line of stdin, then 'close'.
+  const readline = {
+    createInterface: () => {
+      const handlers = { line: [], close: [] };
+      const lines = input().split('\n');
+      if (lines[lines.length - 1] === '') lines.pop();
+      let closed = false;
+      const rl = {
+        on: (event, handler) => {
+          (handlers[event] = handlers[event] || []).push(handler);
+          return rl;
+        },
+        question: (query, answer) => {
+          write(1, query);
+          setTimeout(() => answer(lines.shift() ?? ''), 0);
+        },
+        close: () => {
+          if (closed) return;
+          closed = true;
+          handlers.close.forEach((handler) => handler());
+        },
+        [Symbol.asyncIterator]: async function* () {
+          while (lines.length > 0) yield lines.shift();
+        },
+      };
+      setTimeout(() => {
+        if (handlers.line.length === 0) return;
+        while (lines.length > 0 && !closed) {
+          const line = lines.shift();
+          handlers.line.forEach((handler) => handler(line));
+        }
+        rl.close();
+      }, 0);
+      return rl;
+    },
+  };
+
+  const modules = {
+    assert,
+    'assert/strict': assert,
+    fs,
+    readline,
+    util: { inspect: (value) => inspect(value, 0), format },
+  };
+  globalThis.require = (name) => {
+    const module = modules[String(name).replace(/^node:/, '')];
+    if (module === undefined) {
+      throw new Error(`Cannot find module '${name}' (niedostępny we wbudowanym silniku)`);
+    }
+    return module;
+  };
+  globalThis.module = { exports: {} };
+  globalThis.exports = globalThis.module.exports;
+})();
@@ -0,0 +1,372 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::Diagnostic;
+
+/// A beginner-friendly explanation of what went wrong, pointing at the
+/// line of the learner's code it is about.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hint {
+    pub line: Option<u32>,
+    /// The learner's code on that line.
+    pub code: Option<String>,
+    pub title: String,
+    pub explanation: String,
+}
+
+const PYTHON: &[&str] = &["python"];
+const JS: &[&str] = &["javascript", "typescript"];
+
+/// Error messages learners run into most, by language. `$1` in the title
+/// or explanation is replaced with the first group of the pattern.
+const EXPLANATIONS: &[(&[&str], &str, &str, &str)] = &[
+    (
+        PYTHON,
+        r"^NameError: name '(\w+)' is not defined",
+        "Nieznana nazwa „$1”",
+        "Python nie zna nazwy $1. Sprawdź pisownię (wielkość liter ma znaczenie) i czy zmienna jest przypisana albo funkcja zdefiniowana wyżej w kodzie. Jeśli to miał być tekst, ujmij go w cudzysłów.",
+    ),
+    (
+        PYTHON,
+        r"^IndentationError: expected an indented block",
+        "Brak wcięcia",
+        "Po linii zakończonej dwukropkiem (if, for, while, def…) ciało bloku musi być wcięte, zwykle o 4 spacje.",
+    ),
+    (
+        PYTHON,
+        r"^IndentationError: unexpected indent",
+        "Nieoczekiwane wcięcie",
+        "Ta linia jest wcięta, choć nie zaczyna się tu żaden blok. Usuń spacje z jej początku albo sprawdź, czy linia wyżej nie powinna kończyć się dwukropkiem.",
+    ),
+    (
+        PYTHON,
+        r"^IndentationError: unindent does not match",
+        "Niespójne wcięcia",
+        "Wcięcie tej linii nie pasuje do żadnego bloku wyżej. Linie jednego bloku muszą mieć dokładnie tyle samo spacji.",
+    ),
+    (
+        PYTHON,
+        r"^TabError",
+        "Tabulatory i spacje",
+        "W wcięciach pomieszano tabulatory ze spacjami. Używaj samych spacji, po 4 na poziom.",
+    ),
+    (
+        PYTHON,
+        r"^SyntaxError: expected ':'",
+        "Brak dwukropka",
+        "Linie zaczynające blok (if, elif, else, for, while, def, class) muszą kończyć się dwukropkiem.",
+    ),
+    (
+        PYTHON,
+        r"^SyntaxError: Missing parentheses in call to 'print'",
+        "print wymaga nawiasów",
+        "W Pythonie 3 print to funkcja: pisz print(\"tekst\"), a nie print \"tekst\".",
+    ),
+    (
+        PYTHON,
+        r"^SyntaxError: '(.)' was never closed",
+        "Niezamknięty nawias",
+        "Nawias $1 otwarty w tej linii nie został zamknięty. Policz nawiasy otwierające i zamykające.",
+    ),
+    (
+        PYTHON,
+        r"^SyntaxError: unterminated string literal",
+        "Niezamknięty tekst",
+        "Tekst w cudzysłowie nie został zamknięty. Sprawdź, czy zaczyna się i kończy tym samym cudzysłowem.",
+    ),
+    (
+        PYTHON,
+        r"^SyntaxError: invalid syntax. Maybe you meant '==' or ':=' instead of '='",
+        "Przypisanie zamiast porównania",
+        "Do porównania służy ==. Pojedyncze = przypisuje wartość zmiennej.",
+    ),
+    (
+        PYTHON,
+        r#"^TypeError: can only concatenate str \(not "(\w+)"\) to str"#,
+        "Łączenie tekstu z liczbą",
+        "Tekstu nie można dodać do wartości typu $1. Zamień ją na tekst przez str(...) albo użyj f-stringa: f\"wynik: {x}\".",
+    ),
+    (
+        PYTHON,
+        r"^TypeError: unsupported operand type\(s\)",
+        "Niezgodne typy",
+        "Działanie dostało wartości typów, których nie da się tak połączyć. Pamiętaj, że input() zawsze zwraca tekst — liczbę uzyskasz przez int(...) lub float(...).",
+    ),
+    (
+        PYTHON,
+        r"^ValueError: invalid literal for int\(\) with base 10",
+        "To nie jest liczba całkowita",
+        "int(...) dostał tekst, który nie jest liczbą całkowitą. Sprawdź dane wejściowe i usuń z nich spacje albo przecinki.",
+    ),
+    (
+        PYTHON,
+        r"^ZeroDivisionError",
+        "Dzielenie przez zero",
+        "Program dzieli przez zero. Sprawdź, czy dzielnik może być zerem, zanim go użyjesz.",
+    ),
+    (
+        PYTHON,
+        r"^IndexError: list index out of range",
+        "Indeks poza listą",
+        "Indeksy listy zaczynają się od 0, a ostatni to len(lista) - 1. Sprawdź zakres pętli i wartość indeksu.",
+    ),
+    (
+        PYTHON,
+        r"^KeyError: (.+)",
+        "Brak klucza $1",
+        "Słownik nie ma klucza $1. Sprawdź pisownię albo użyj .get(klucz), które zwraca None zamiast błędu.",
+    ),
+    (
+        PYTHON,
+        r"^AttributeError: '(\w+)' object has no attribute '(\w+)'",
+        "Nieznana metoda lub pole",
+        "Wartości typu $1 nie mają atrybutu $2. Sprawdź pisownię i typ zmiennej.",
+    ),
+    (
+        PYTHON,
+        r"^EOFError",
+        "Brak danych wejściowych",
+        "Program wywołuje input() więcej razy, niż jest linii na wejściu.",
+    ),
+    (
+        PYTHON,
+        r"^RecursionError",
+        "Zbyt głęboka rekurencja",
+        "Funkcja wywołuje samą siebie bez końca. Sprawdź, czy ma warunek stopu i czy każde wywołanie się do niego zbliża.",
+    ),
+    (
+        JS,
+        r"^ReferenceError: (\w+) is not defined",
+        "Nieznana nazwa „$1”",
+        "Nazwa $1 nie została zadeklarowana. Sprawdź pisownię (wielkość liter ma znaczenie) i zadeklaruj zmienną przez let albo const, zanim jej użyjesz.",
+    ),
+    (
+        JS,
+        r"^ReferenceError: Cannot access '(\w+)' before initialization",
+        "Użycie przed deklaracją",
+        "Zmienna $1 jest używana, zanim wykonała się linia z jej deklaracją (let/const). Przenieś deklarację wyżej.",
+    ),
+    (
+        JS,
+        r"^TypeError: Assignment to constant variable",
+        "Zmiana stałej",
+        "Zmiennej zadeklarowanej przez const nie można przypisać nowej wartości. Użyj let, jeśli wartość ma się zmieniać.",
+    ),
+    (
+        JS,
+        r"^TypeError: Cannot read properties of (undefined|null) \(reading '([^']+)'\)",
+        "Odczyt z $1",
+        "Próbujesz odczytać $2 z wartości $1. Sprawdź, czy zmienna ma wartość i czy indeks tablicy istnieje.",
+    ),
+    (
+        JS,
+        r"^TypeError: (\S+) is not a function",
+        "To nie jest funkcja",
+        "$1 nie jest funkcją. Sprawdź pisownię nazwy metody i czy wywołujesz ją na właściwym obiekcie.",
+    ),
+    (
+        JS,
+        r"^SyntaxError: Identifier '(\w+)' has already been declared",
+        "Podwójna deklaracja",
+        "Zmienna $1 jest zadeklarowana drugi raz. Przy kolejnym przypisaniu pomiń let/const.",
+    ),
+    (
+        JS,
+        r"^SyntaxError: (Unexpected end of input|missing \) after argument list)",
+        "Niezamknięty nawias",
+        "Brakuje nawiasu zamykającego ), } lub ]. Policz nawiasy otwierające i zamykające.",
+    ),
+    (
+        JS,
+        r"^SyntaxError: (Invalid or unexpected token|Unexpected token)",
+        "Błąd składni",
+        "W tej linii jest znak, którego JavaScript się tu nie spodziewa. Sprawdź cudzysłowy, przecinki i nawiasy.",
+    ),
+    (
+        JS,
+        r"^RangeError: Maximum call stack size exceeded",
+        "Zbyt głęboka rekurencja",
+        "Funkcja wywołuje samą siebie bez końca. Sprawdź, czy ma warunek stopu i czy każde wywołanie się do niego zbliża.",
+    ),
+];
+
+/// The patterns of `EXPLANATIONS`, compiled once and in the same order.
+fn patterns() -> &'static [Regex] {
+    static COMPILED: OnceLock<Vec<Regex>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        EXPLANATIONS
+            .iter()
+            .map(|(_, pattern, ..)| Regex::new(pattern).unwrap())
+            .collect()
+    })
+}
+
+/// Explains the errors in `diagnostics`, then adds what a quick look at
+/// the code finds on other lines. Only meant for runs that failed.
+pub fn explain(language: &str, code: &str, diagnostics: &[Diagnostic]) -> Vec<Hint> {
+    let mut hints: Vec<Hint> = diagnostics
+        .iter()
+        .filter_map(|diagnostic| {
+            let ((_, _, title, explanation), captures) = EXPLANATIONS
+                .iter()
+                .zip(patterns())
+                .filter(|((languages, ..), _)| languages.contains(&language))
+                .find_map(|(entry, regex)| Some((entry, regex.captures(&diagnostic.message)?)))?;
+            let mut hint = hint(code, diagnostic.line, "", "");
+            captures.expand(title, &mut hint.title);
+            captures.expand(explanation, &mut hint.explanation);
+            Some(hint)
+        })
+        .collect();
+
+    for found in lint(language, code) {
+        if !hints.iter().any(|hint| hint.line == found.line) {
+            hints.push(found);
+        }
+    }
+    hints
+}
+
+fn hint(code: &str, line: Option<u32>, title: &str, explanation: &str) -> Hint {
+    Hint {
+        line,
+        code: line
+            .and_then(|line| code.lines().nth(line.checked_sub(1)? as usize))
+            .map(|text| text.trim_end().to_string()),
+        title: title.to_string(),
+        explanation: explanation.to_string(),
+    }
+}
+
+/// Mistakes that are easy to spot without running the code, and that
+/// often hide behind a confusing error or a wrong answer.
+fn lint(language: &str, code: &str) -> Vec<Hint> {
+    static PATTERNS: OnceLock<[Regex; 4]> = OnceLock::new();
+    let [python_print, python_block, python_assign, js_assign] = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r#"^\s*print\s+[^\s(=]"#).unwrap(),
+            Regex::new(r"^\s*(if|elif|else|for|while|def|class|try|except|finally|with)\b")
+                .unwrap(),
+            Regex::new(r"^\s*(if|elif|while)\s[^=!<>]*[^=!<>]=[^=]").unwrap(),
+            Regex::new(r"\b(if|while)\s*\([^()=!<>]*[^=!<>]=[^=>]").unwrap(),
+        ]
+    });
+
+    let mut hints = Vec::new();
+    let mut indents = (false, false);
+    for (index, line) in code.lines().enumerate() {
+        let number = Some(index as u32 + 1);
+        let statement = line.split('#').next().unwrap_or_default().trim_end();
+        match language {
+            "python" => {
+                let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+                indents.0 |= indent.contains(' ');
+                indents.1 |= indent.contains('\t');
+
+                if python_print.is_match(statement) {
+                    hints.push(hint(
+                        code,
+                        number,
+                        "print wymaga nawiasów",
+                        "W Pythonie 3 print to funkcja: pisz print(\"tekst\"), a nie print \"tekst\".",
+                    ));
+                } else if python_assign.is_match(statement) {
+                    hints.push(hint(
+                        code,
+                        number,
+                        "Przypisanie zamiast porównania",
+                        "Do porównania służy ==. Pojedyncze = przypisuje wartość zmiennej.",
+                    ));
+                } else if python_block.is_match(statement)
+                    && !statement.ends_with(':')
+                    && balanced(statement)
+                {
+                    hints.push(hint(
+                        code,
+                        number,
+                        "Brak dwukropka",
+                        "Linie zaczynające blok (if, elif, else, for, while, def, class) muszą kończyć się dwukropkiem.",
+                    ));
+                }
+            }
+            "javascript" | "typescript" if js_assign.is_match(line) => {
+                hints.push(hint(
+                    code,
+                    number,
+                    "Przypisanie zamiast porównania",
+                    "W warunku = przypisuje wartość, więc warunek prawie zawsze jest prawdziwy. Do porównania użyj ===.",
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    if indents == (true, true) {
+        hints.push(hint(
+            code,
+            None,
+            "Tabulatory i spacje",
+            "W wcięciach pomieszano tabulatory ze spacjami. Używaj samych spacji, po 4 na poziom.",
+        ));
+    }
+    hints
+}
+
+/// Whether every bracket opened on the line is closed on it too; a line
+/// that continues on the next one can't be judged on its own.
+fn balanced(line: &str) -> bool {
+    let depth = line.chars().fold(0i32, |depth, c| match c {
+        '(' | '[' | '{' => depth + 1,
+        ')' | ']' | '}' => depth - 1,
+        _ => depth,
+    });
+    depth == 0 && !line.ends_with('\\')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_errors_and_points_at_the_line() {
+        let code = "imie = 'Ala'\nprint(imei)\n";
+        let diagnostics = vec![Diagnostic {
+            line: Some(2),
+            column: None,
+            message: "NameError: name 'imei' is not defined. Did you mean: 'imie'?".to_string(),
+        }];
+        let hints = explain("python", code, &diagnostics);
+
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].title, "Nieznana nazwa „imei”");
+        assert_eq!(hints[0].code.as_deref(), Some("print(imei)"));
+    }
+
+    #[test]
+    fn finds_mistakes_without_running_the_code() {
+        let code =
+            "x = int(input())\nif x = 5\n    print 'pięć'\nfor i in range(\n    3):\n    pass\n";
+        let titles: Vec<_> = lint("python", code)
+            .into_iter()
+            .map(|hint| (hint.line, hint.title))
+            .collect();
+
+        assert_eq!(
+            titles,
+            vec![
+                (Some(2), "Przypisanie zamiast porównania".to_string()),
+                (Some(3), "print wymaga nawiasów".to_string()),
+            ]
+        );
+        assert_eq!(
+            lint(
+                "javascript",
+                "if (a = 1) {}\nif (a === 1) {}\nif (a => 1) {}"
+            )
+            .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::io;
+
+use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+    SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_LIMIT_ACTIVE_PROCESS, JOB_OBJECT_LIMIT_JOB_TIME, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+};
+
+use super::limits::ResourceLimits;
+
+/// Windows counterpart of the Unix process group and rlimits: every process
+/// started by learner code stays in the job and dies with it, including the
+/// `node.exe` behind `ts-node.cmd`.
+pub struct Job(HANDLE);
+
+// The handle is only used through thread-safe kernel calls.
+unsafe impl Send for Job {}
+unsafe impl Sync for Job {}
+
+impl Job {
+    pub fn new(limits: &ResourceLimits) -> io::Result<Self> {
+        let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let job = Self(handle);
+
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE
+            | JOB_OBJECT_LIMIT_PROCESS_MEMORY
+            | JOB_OBJECT_LIMIT_JOB_TIME
+            | JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+        info.ProcessMemoryLimit = (limits.memory_mb as usize).saturating_mul(1024 * 1024);
+        // 100 ns units; exceeding it ends every process with ERROR_NOT_ENOUGH_QUOTA.
+        info.BasicLimitInformation.PerJobUserTimeLimit =
+            (limits.cpu_seconds as i64).saturating_mul(10_000_000);
+        info.BasicLimitInformation.ActiveProcessLimit = limits.max_processes as u32;
+        job.set_limits(&info)?;
+
+        Ok(job)
+    }
+
+    fn set_limits(&self, info: &JOBOBJECT_EXTENDED_LIMIT_INFORMATION) -> io::Result<()> {
+        let ok = unsafe {
+            SetInformationJobObject(
+                self.0,
+                JobObjectExtendedLimitInformation,
+                info as *const JOBOBJECT_EXTENDED_LIMIT_INFORMATION as *const _,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        };
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Processes the child spawned before this call are not in the job;
+    /// the window is a few instructions long.
+    pub fn assign(&self, child: &tokio::process::Child) -> io::Result<()> {
+        let handle = child
+            .raw_handle()
+            .ok_or_else(|| io::Error::other("process already exited"))?;
+        if unsafe { AssignProcessToJobObject(self.0, handle as HANDLE) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn terminate(&self) {
+        unsafe {
+            TerminateJobObject(self.0, 1);
+        }
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use super::{binary_name, located_message, Build, Diagnostic, LanguageRunner};
+use crate::runner::{ResourceLimits, Sandbox};
+
+pub struct C;
+
+impl LanguageRunner for C {
+    fn language(&self) -> &'static str {
+        "c"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "cc"
+    }
+
+    fn toolchain(&self) -> &'static [&'static str] {
+        &["cc", "gcc", "clang"]
+    }
+
+    fn prepare(&self, sandbox: &Sandbox, code: &str) -> Result<Vec<PathBuf>, String> {
+        Ok(vec![sandbox.write_file("main.c", code)?])
+    }
+
+    /// Every `.c` file of the project; headers are found through
+    /// `#include`.
+    fn project_sources(&self, entrypoint: &Path, files: &[PathBuf]) -> Vec<PathBuf> {
+        let mut sources = vec![entrypoint.to_path_buf()];
+        sources.extend(
+            files
+                .iter()
+                .filter(|file| *file != entrypoint)
+                .filter(|file| file.extension().is_some_and(|ext| ext == "c"))
+                .cloned(),
+        );
+        sources
+    }
+
+    fn build(&self, sandbox: &Sandbox, program: &Path, sources: &[PathBuf]) -> Option<Build> {
+        let mut command = sandbox.command_with_limits(program, &ResourceLimits::compiler());
+        command
+            .args(["-std=c11", "-Wall", "-o", binary_name()])
+            .args(sources)
+            .arg("-lm");
+
+        Some(Build {
+            command,
+            binary: sandbox.path(binary_name()),
+        })
+    }
+
+    /// Errors only; warnings don't stop the program from running.
+    fn parse_diagnostics(&self, stderr: &str) -> Vec<Diagnostic> {
+        stderr
+            .lines()
+            .filter_map(|line| located_message(line, "main.c"))
+            .filter_map(|mut diagnostic| {
+                let message = diagnostic
+                    .message
+                    .strip_prefix("error: ")
+                    .or_else(|| diagnostic.message.strip_prefix("fatal error: "))?;
+                diagnostic.message = message.to_string();
+                Some(diagnostic)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_errors_and_drops_warnings() {
+        let stderr = "main.c: In function 'main':\nmain.c:4:9: warning: unused variable 'y' [-Wunused-variable]\nmain.c:5:5: error: 'x' undeclared (first use in this function)\n";
+        let diagnostics = C.parse_diagnostics(stderr);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(5));
+        assert_eq!(
+            diagnostics[0].message,
+            "'x' undeclared (first use in this function)"
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{js_diagnostics, Diagnostic, LanguageRunner};
+use crate::runner::{embedded, unit_tests, Sandbox};
+
+/// JavaScript on the engine built into the app; see [`embedded`].
+pub struct EmbeddedJavaScript;
+
+impl LanguageRunner for EmbeddedJavaScript {
+    fn language(&self) -> &'static str {
+        "javascript"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "wbudowany silnik JavaScript"
+    }
+
+    fn toolchain(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn version_args(&self) -> &'static [&'static str] {
+        &[embedded::ARG, "--version"]
+    }
+
+    /// The engine is the app binary itself.
+    fn locate(&self) -> Result<PathBuf, String> {
+        std::env::current_exe().map_err(|e| e.to_string())
+    }
+
+    fn prepare(&self, sandbox: &Sandbox, code: &str) -> Result<Vec<PathBuf>, String> {
+        Ok(vec![sandbox.write_file("main.js", code)?])
+    }
+
+    fn with_tests(&self, code: &str, tests: &str, nonce: &str) -> Option<String> {
+        Some(unit_tests::js_harness(code, tests, nonce))
+    }
+
+    fn command(&self, sandbox: &Sandbox, program: &Path, args: &[PathBuf]) -> Command {
+        let mut cmd = sandbox.command(program);
+        cmd.arg(embedded::ARG).args(args);
+        cmd
+    }
+
+    fn parse_diagnostics(&self, stderr: &str) -> Vec<Diagnostic> {
+        js_diagnostics(stderr, "main.js")
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use super::{binary_name, located_message, runtime_diagnostics, Build, Diagnostic, LanguageRunner};
+use crate::runner::{ResourceLimits, Sandbox};
+
+pub struct Go;
+
+impl LanguageRunner for Go {
+    fn language(&self) -> &'static str {
+        "go"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Go"
+    }
+
+    fn toolchain(&self) -> &'static [&'static str] {
+        &["go"]
+    }
+
+    fn version_args(&self) -> &'static [&'static str] {
+        &["version"]
+    }
+
+    fn prepare(&self, sandbox: &Sandbox, code: &str) -> Result<Vec<PathBuf>, String> {
+        Ok(vec![sandbox.write_file("main.go", code)?])
+    }
+
+    /// The `.go` files next to the entrypoint, which make up its package.
+    fn project_sources(&self, entrypoint: &Path, files: &[PathBuf]) -> Vec<PathBuf> {
+        let mut sources = vec![entrypoint.to_path_buf()];
+        sources.extend(
+            files
+                .iter()
+                .filter(|file| *file != entrypoint)
+                .filter(|file| file.parent() == entrypoint.parent())
+                .filter(|file| file.extension().is_some_and(|ext| ext == "go"))
+                .cloned(),
+        );
+        sources
+    }
+
+    /// `go build` rather than `go run`, which would mix compiler errors
+    /// with the program's own output and exit code.
+    fn build(&self, sandbox: &Sandbox, program: &Path, sources: &[PathBuf]) -> Option<Build> {
+        let mut command = sandbox.command_with_limits(program, &ResourceLimits::compiler());
+        command
+            .env("GOCACHE", sandbox.path("go-build"))
+            .env("GOPATH", sandbox.path("go"))
+            // The sandbox has no network; never try to download a toolchain.
+            .env("GOTOOLCHAIN", "local")
+            .env("CGO_ENABLED", "0")
+            .args(["build", "-o", binary_name()])
+            .args(sources);
+
+        Some(Build {
+            command,
+            binary: sandbox.path(binary_name()),
+        })
+    }
+
+    fn parse_diagnostics(&self, stderr: &str) -> Vec<Diagnostic> {
+        let panic = stderr
+            .lines()
+            .find(|line| line.starts_with("panic: "))
+            .map(|line| line.to_string());
+        if panic.is_some() {
+            return runtime_diagnostics(stderr, "main.go", panic);
+        }
+
+        stderr
+            .lines()
+            .filter_map(|line| located_message(line, "main.go"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_compile_errors_and_panics() {
+        let stderr = "# command-line-arguments\n./main.go:5:2: undefined: x\n";
+        let diagnostics = Go.parse_diagnostics(stderr);
+        assert_eq!(diagnostics[0].line, Some(5));
+        assert_eq!(diagnostics[0].message, "undefined: x");
+
+        let stderr = "panic: runtime error: index out of range [5] with length 3\n\ngoroutine 1 [running]:\nmain.main()\n\t/tmp/sb/main.go:8 +0x1d\n";
+        let diagnostics = Go.parse_diagnostics(stderr);
+        assert_eq!(diagnostics[0].line, Some(8));
+        assert!(diagnostics[0].message.starts_with("panic: runtime error"));
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use super::embedded_js::EmbeddedJavaScript;
+use super::{js_diagnostics, Diagnostic, Formatter, LanguageRunner, PRETTIER};
+use crate::runner::{unit_tests, Sandbox};
+
+pub struct JavaScript;
+
+impl LanguageRunner for JavaScript {
+    fn language(&self) -> &'static str {
+        "javascript"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Node.js"
+    }
+
+    fn toolchain(&self) -> &'static [&'static str] {
+        &["node"]
+    }
+
+    fn prepare(&self, sandbox: &Sandbox, code: &str) -> Result<Vec<PathBuf>, String> {
+        Ok(vec![sandbox.write_file("main.js", code)?])
+    }
+
+    fn with_tests(&self, code: &str, tests: &str, nonce: &str) -> Option<String> {
+        Some(unit_tests::js_harness(code, tests, nonce))
+    }
+
+    fn embedded(&self) -> Option<&'static dyn LanguageRunner> {
+        Some(&EmbeddedJavaScript)
+    }
+
+    fn formatters(&self) -> &'static [Formatter] {
+        &[Formatter {
+            name: "prettier",
+            candidates: PRETTIER,
+            args: &["--stdin-filepath", "main.js"],
+        }]
+    }
+
+    fn repl_args(&self) -> Option<&'static [&'static str]> {
+        Some(&["-i"])
+    }
+
+    fn parse_diagnostics(&self, stderr: &str) -> Vec<Diagnostic> {
+        js_diagnostics(stderr, "main.js")
+    }
+}
@@ -0,0 +1,479 @@
+mod c;
+mod embedded_js;
+mod go;
+mod javascript;
+mod python;
+mod rust;
+mod typescript;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use super::process::{self, Observer, RunOutput};
+use super::toolchain::{self, Toolchain};
+use super::{ResourceLimits, Sandbox};
+
+/// A compiler or interpreter message pointing at the learner's code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+/// How to compile a prepared program and where the result ends up.
+pub struct Build {
+    pub command: Command,
+    pub binary: PathBuf,
+}
+
+/// A code formatter for a language. It reads the code on stdin and
+/// prints the formatted code on stdout.
+pub struct Formatter {
+    /// Also the key of the formatter's cached toolchain lookup.
+    pub name: &'static str,
+    /// Where to look for it, as for [`LanguageRunner::toolchain`].
+    pub candidates: &'static [&'static str],
+    pub args: &'static [&'static str],
+}
+
+#[derive(Clone)]
+pub enum Execution {
+    Ran(RunOutput),
+    /// The compiler rejected the code; the output is the compiler's.
+    CompileFailed(RunOutput),
+    /// Nothing ran: the language's toolchain is not installed.
+    ToolchainMissing(String),
+}
+
+/// Everything the validator needs to know about one language. Adding a
+/// language means implementing this for a new type and listing it in
+/// [`RUNNERS`]; the commands never match on language names.
+pub trait LanguageRunner: Send + Sync {
+    /// The `language` value used by courses and lessons.
+    fn language(&self) -> &'static str;
+
+    /// Human-readable toolchain name for error messages.
+    fn display_name(&self) -> &'static str;
+
+    /// Programs that can run this language, in order of preference.
+    fn toolchain(&self) -> &'static [&'static str];
+
+    /// Arguments that make the toolchain print its version.
+    fn version_args(&self) -> &'static [&'static str] {
+        &["--version"]
+    }
+
+    /// Finds the toolchain binary. Runs once per language; see
+    /// [`toolchain::cached`].
+    fn locate(&self) -> Result<PathBuf, String> {
+        toolchain::discover(self.toolchain())
+    }
+
+    /// Writes the learner's code into the sandbox and returns the
+    /// arguments the toolchain needs to run it.
+    fn prepare(&self, sandbox: &Sandbox, code: &str) -> Result<Vec<PathBuf>, String>;
+
+    /// Compiled languages return the compiler invocation here; the binary
+    /// it produces is then run without arguments instead of [`command`].
+    ///
+    /// [`command`]: LanguageRunner::command
+    fn build(&self, _sandbox: &Sandbox, _program: &Path, _sources: &[PathBuf]) -> Option<Build> {
+        None
+    }
+
+    /// Combines the learner's code with an exercise's hidden tests and a
+    /// harness that reports each test (see [`unit_tests`]). `None` when the
+    /// language has no test harness.
+    ///
+    /// [`unit_tests`]: crate::runner::unit_tests
+    fn with_tests(&self, _code: &str, _tests: &str, _nonce: &str) -> Option<String> {
+        None
+    }
+
+    /// What to hand the compiler or interpreter for a project of several
+    /// `files` starting at `entrypoint`. Interpreters and `rustc` find the
+    /// other files through imports and `mod` on their own.
+    fn project_sources(&self, entrypoint: &Path, _files: &[PathBuf]) -> Vec<PathBuf> {
+        vec![entrypoint.to_path_buf()]
+    }
+
+    /// A runner built into the app for this language, used when the
+    /// toolchain is missing or an exercise asks for [`Engine::Embedded`].
+    fn embedded(&self) -> Option<&'static dyn LanguageRunner> {
+        None
+    }
+
+    /// Formatters for the language, in order of preference. Empty when the
+    /// editor can't format it.
+    fn formatters(&self) -> &'static [Formatter] {
+        &[]
+    }
+
+    /// Finds one of [`formatters`](LanguageRunner::formatters).
+    fn locate_formatter(&self, formatter: &Formatter) -> Result<PathBuf, String> {
+        toolchain::discover(formatter.candidates)
+    }
+
+    /// Arguments that start the toolchain's interactive prompt reading from
+    /// a pipe. `None` when the language has no REPL.
+    fn repl_args(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+
+    /// Builds the command that runs the prepared program.
+    fn command(&self, sandbox: &Sandbox, program: &Path, args: &[PathBuf]) -> Command {
+        let mut cmd = sandbox.command(program);
+        cmd.args(args);
+        cmd
+    }
+
+    /// Pulls the location and message of the error out of stderr, for both
+    /// compiler and runtime errors.
+    fn parse_diagnostics(&self, stderr: &str) -> Vec<Diagnostic>;
+}
+
+static RUNNERS: &[&dyn LanguageRunner] = &[
+    &python::Python,
+    &javascript::JavaScript,
+    &typescript::TypeScript,
+    &rust::Rust,
+    &c::C,
+    &go::Go,
+];
+
+pub fn find(language: &str) -> Option<&'static dyn LanguageRunner> {
+    RUNNERS
+        .iter()
+        .copied()
+        .find(|runner| runner.language() == language)
+}
+
+/// Which engine runs an exercise. Set per exercise through `engine` in the
+/// lesson content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Engine {
+    /// The installed toolchain, or the embedded engine when it is missing.
+    #[default]
+    Toolchain,
+    /// Always the embedded engine, where the language has one.
+    Embedded,
+}
+
+/// The runner for `language`, honouring the exercise's choice of engine.
+pub fn select(language: &str, engine: Engine) -> Result<&'static dyn LanguageRunner, String> {
+    let runner = find(language).ok_or_else(|| format!("Unsupported language: {}", language))?;
+    Ok(match engine {
+        Engine::Embedded => runner.embedded().unwrap_or(runner),
+        Engine::Toolchain => runner,
+    })
+}
+
+/// Locates the toolchain, prepares the code, compiles it if the language
+/// needs that, and runs it in `sandbox`. Without the toolchain the
+/// language's embedded engine runs the code instead, if there is one. The
+/// observer sees only the program's own output, but can cancel the build
+/// too.
+pub async fn execute(
+    runner: &'static dyn LanguageRunner,
+    sandbox: &Sandbox,
+    code: &str,
+    stdin: Option<&str>,
+    limits: &ResourceLimits,
+    observer: Observer,
+) -> Result<Execution, String> {
+    let prepare = |runner: &dyn LanguageRunner| runner.prepare(sandbox, code);
+    execute_sources(runner, sandbox, prepare, stdin, limits, observer).await
+}
+
+/// Like [`execute`], for a project whose `files` are already in `sandbox`.
+pub async fn execute_project(
+    runner: &'static dyn LanguageRunner,
+    sandbox: &Sandbox,
+    entrypoint: &Path,
+    files: &[PathBuf],
+    stdin: Option<&str>,
+    limits: &ResourceLimits,
+    observer: Observer,
+) -> Result<Execution, String> {
+    let sources = |runner: &dyn LanguageRunner| Ok(runner.project_sources(entrypoint, files));
+    execute_sources(runner, sandbox, sources, stdin, limits, observer).await
+}
+
+/// `sources` gets the runner that will actually run the code, which is the
+/// embedded one when the toolchain is missing.
+async fn execute_sources(
+    runner: &'static dyn LanguageRunner,
+    sandbox: &Sandbox,
+    sources: impl FnOnce(&dyn LanguageRunner) -> Result<Vec<PathBuf>, String>,
+    stdin: Option<&str>,
+    limits: &ResourceLimits,
+    observer: Observer,
+) -> Result<Execution, String> {
+    let (runner, program) = match locate(runner).await {
+        Ok(toolchain) => (runner, toolchain.path),
+        Err(e) => match runner.embedded() {
+            Some(embedded) => {
+                println!(
+                    "{} not found, running on {}",
+                    runner.display_name(),
+                    embedded.display_name()
+                );
+                (embedded, locate(embedded).await?.path)
+            }
+            None => {
+                return Ok(Execution::ToolchainMissing(format!(
+                    "Nie można uruchomić {}: {}. Upewnij się, że jest zainstalowany.",
+                    runner.display_name(),
+                    e
+                )))
+            }
+        },
+    };
+    let args = sources(runner)?;
+
+    let cmd = match runner.build(sandbox, &program, &args) {
+        Some(build) => {
+            let build_observer = Observer {
+                cancel: observer.cancel.clone(),
+                ..Observer::default()
+            };
+            let output = process::run(
+                build.command,
+                &ResourceLimits::compiler(),
+                None,
+                build_observer,
+            )
+            .await
+            .map_err(|e| format!("Nie można uruchomić {}: {}", runner.display_name(), e))?;
+            // A cancelled build is reported like a cancelled run.
+            if output.cancelled {
+                return Ok(Execution::Ran(output));
+            }
+            if !output.status.is_some_and(|status| status.success()) {
+                return Ok(Execution::CompileFailed(output));
+            }
+            sandbox.command(&build.binary)
+        }
+        None => runner.command(sandbox, &program, &args),
+    };
+
+    process::run(cmd, limits, stdin, observer)
+        .await
+        .map(Execution::Ran)
+        .map_err(|e| format!("Nie można uruchomić {}: {}", runner.display_name(), e))
+}
+
+/// Cached toolchain lookup; the first call per language searches `PATH`
+/// and runs the toolchain once to read its version.
+pub async fn locate(runner: &'static dyn LanguageRunner) -> Result<Toolchain, String> {
+    tokio::task::spawn_blocking(move || {
+        toolchain::cached(
+            runner.display_name(),
+            || runner.locate(),
+            runner.version_args(),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Availability of one language's toolchain, as shown in the UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolchainStatus {
+    pub language: String,
+    pub name: String,
+    pub available: bool,
+    pub path: Option<String>,
+    pub version: Option<String>,
+    pub error: Option<String>,
+    /// Engine built into the app that runs the language without the
+    /// toolchain.
+    pub embedded: Option<String>,
+}
+
+/// Probes every registered runner. `refresh` forgets cached lookups first.
+pub async fn check_all(refresh: bool) -> Vec<ToolchainStatus> {
+    if refresh {
+        toolchain::forget_all();
+    }
+
+    let mut statuses = Vec::with_capacity(RUNNERS.len());
+    for runner in RUNNERS {
+        let status = match locate(*runner).await {
+            Ok(toolchain) => ToolchainStatus {
+                language: runner.language().to_string(),
+                name: runner.display_name().to_string(),
+                available: true,
+                path: Some(toolchain.path.display().to_string()),
+                version: toolchain.version,
+                error: None,
+                embedded: runner
+                    .embedded()
+                    .map(|engine| engine.display_name().to_string()),
+            },
+            Err(e) => ToolchainStatus {
+                language: runner.language().to_string(),
+                name: runner.display_name().to_string(),
+                available: false,
+                path: None,
+                version: None,
+                error: Some(e),
+                embedded: runner
+                    .embedded()
+                    .map(|engine| engine.display_name().to_string()),
+            },
+        };
+        statuses.push(status);
+    }
+    statuses
+}
+
+/// A global `prettier`, then the project's own copy, as for `ts-node`.
+const PRETTIER: &[&str] = if cfg!(windows) {
+    &[
+        "prettier",
+        ".\\node_modules\\.bin\\prettier.cmd",
+        "..\\node_modules\\.bin\\prettier.cmd",
+        "..\\..\\node_modules\\.bin\\prettier.cmd",
+    ]
+} else {
+    &[
+        "prettier",
+        "./node_modules/.bin/prettier",
+        "../node_modules/.bin/prettier",
+        "../../node_modules/.bin/prettier",
+    ]
+};
+
+/// Name of the executable compiled languages produce.
+fn binary_name() -> &'static str {
+    if cfg!(windows) {
+        "main.exe"
+    } else {
+        "main"
+    }
+}
+
+/// Finds `<file>:<line>[:<column>]` in a line of output, as printed by
+/// Node and most compilers.
+fn file_location(line: &str, file: &str) -> Option<(u32, Option<u32>)> {
+    let rest = line[line.find(file)? + file.len()..].strip_prefix(':')?;
+    let (number, rest) = leading_number(rest)?;
+    let column = rest
+        .strip_prefix(':')
+        .and_then(leading_number)
+        .map(|(column, _)| column);
+    Some((number, column))
+}
+
+fn leading_number(text: &str) -> Option<(u32, &str)> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    Some((text[..end].parse().ok()?, &text[end..]))
+}
+
+/// The `SomethingError: message` line of a JavaScript error report.
+fn js_error_message(stderr: &str) -> Option<String> {
+    stderr
+        .lines()
+        .map(str::trim)
+        .find(|line| {
+            line.split_once(':')
+                .is_some_and(|(name, _)| name.ends_with("Error") && !name.contains(' '))
+        })
+        .map(str::to_string)
+}
+
+fn js_diagnostics(stderr: &str, file: &str) -> Vec<Diagnostic> {
+    runtime_diagnostics(stderr, file, js_error_message(stderr))
+}
+
+/// One diagnostic for a crash: `message` plus the first location in the
+/// learner's file that the stack trace mentions.
+fn runtime_diagnostics(stderr: &str, file: &str, message: Option<String>) -> Vec<Diagnostic> {
+    let Some(message) = message else {
+        return Vec::new();
+    };
+
+    // Node prints a bare `main.js:N` header for some errors; the stack
+    // frame below it also has the column.
+    let locations: Vec<_> = stderr
+        .lines()
+        .filter_map(|line| file_location(line, file))
+        .collect();
+    let location = locations
+        .iter()
+        .find(|(_, column)| column.is_some())
+        .or(locations.first())
+        .copied();
+
+    vec![Diagnostic {
+        line: location.map(|(line, _)| line),
+        column: location.and_then(|(_, column)| column),
+        message,
+    }]
+}
+
+/// Parses `<file>:<line>:<column>: <message>` lines, the format of gcc,
+/// clang and the Go compiler.
+fn located_message(line: &str, file: &str) -> Option<Diagnostic> {
+    let (number, column) = file_location(line, file)?;
+    let rest = &line[line.find(file)? + file.len()..];
+    let message = rest
+        .trim_start_matches(|c: char| c == ':' || c.is_ascii_digit())
+        .trim();
+
+    Some(Diagnostic {
+        line: Some(number),
+        column,
+        message: message.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_every_registered_language() {
+        for language in ["python", "javascript", "typescript", "rust", "c", "go"] {
+            assert_eq!(find(language).map(|r| r.language()), Some(language));
+        }
+        assert!(find("cobol").is_none());
+    }
+
+    #[test]
+    fn parses_file_location() {
+        assert_eq!(
+            file_location("    at Object.<anonymous> (/tmp/x/main.js:3:7)", "main.js"),
+            Some((3, Some(7)))
+        );
+        assert_eq!(
+            file_location("/tmp/x/main.js:12", "main.js"),
+            Some((12, None))
+        );
+        assert_eq!(file_location("main.jsx:1", "main.js"), None);
+    }
+
+    #[test]
+    fn parses_located_message() {
+        let diagnostic = located_message("./main.go:3:2: undefined: x", "main.go").unwrap();
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(3), Some(2)));
+        assert_eq!(diagnostic.message, "undefined: x");
+    }
+
+    #[test]
+    fn parses_node_runtime_error() {
+        let stderr = "/tmp/x/main.js:2\nfoo()\n^\n\nReferenceError: foo is not defined\n    at Object.<anonymous> (/tmp/x/main.js:2:1)\n";
+        let diagnostics = js_diagnostics(stderr, "main.js");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(2));
+        assert_eq!(diagnostics[0].column, Some(1));
+        assert_eq!(diagnostics[0].message, "ReferenceError: foo is not defined");
+    }
+}
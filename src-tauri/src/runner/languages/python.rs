@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use super::{leading_number, Diagnostic, Formatter, LanguageRunner};
+use crate::runner::{unit_tests, Sandbox};
+
+pub struct Python;
+
+impl LanguageRunner for Python {
+    fn language(&self) -> &'static str {
+        "python"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Python"
+    }
+
+    fn toolchain(&self) -> &'static [&'static str] {
+        // `python` is still Python 2 on some older Unix systems.
+        if cfg!(windows) {
+            &["python", "python3"]
+        } else {
+            &["python3", "python"]
+        }
+    }
+
+    fn prepare(&self, sandbox: &Sandbox, code: &str) -> Result<Vec<PathBuf>, String> {
+        Ok(vec![sandbox.write_file("main.py", code)?])
+    }
+
+    fn with_tests(&self, code: &str, tests: &str, nonce: &str) -> Option<String> {
+        Some(unit_tests::python_harness(code, tests, nonce))
+    }
+
+    fn formatters(&self) -> &'static [Formatter] {
+        &[
+            Formatter {
+                name: "black",
+                candidates: &["black"],
+                args: &["--quiet", "-"],
+            },
+            Formatter {
+                name: "ruff",
+                candidates: &["ruff"],
+                args: &["format", "--no-cache", "-"],
+            },
+        ]
+    }
+
+    // `-i` keeps the prompt up without a terminal; `-u` so output isn't
+    // held back until the buffer fills.
+    fn repl_args(&self) -> Option<&'static [&'static str]> {
+        Some(&["-i", "-q", "-u"])
+    }
+
+    /// Uses the innermost `File "main.py", line N` frame of the traceback
+    /// and its final `Error: message` line.
+    fn parse_diagnostics(&self, stderr: &str) -> Vec<Diagnostic> {
+        let Some(message) = stderr.lines().rev().find(|line| !line.trim().is_empty()) else {
+            return Vec::new();
+        };
+        let line = stderr
+            .lines()
+            .rev()
+            .find(|line| line.trim_start().starts_with("File ") && line.contains("main.py"))
+            .and_then(|frame| frame.split_once(", line "))
+            .and_then(|(_, rest)| leading_number(rest))
+            .map(|(line, _)| line);
+
+        vec![Diagnostic {
+            line,
+            column: None,
+            message: message.trim().to_string(),
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_traceback() {
+        let stderr = "Traceback (most recent call last):\n  File \"/tmp/sb/main.py\", line 4, in <module>\n    print(1 / 0)\nZeroDivisionError: division by zero\n";
+        let diagnostics = Python.parse_diagnostics(stderr);
+        assert_eq!(diagnostics[0].line, Some(4));
+        assert_eq!(
+            diagnostics[0].message,
+            "ZeroDivisionError: division by zero"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+
+use super::{binary_name, file_location, toolchain, Build, Diagnostic, Formatter, LanguageRunner};
+use crate::runner::{ResourceLimits, Sandbox};
+
+const RUSTC: &str = if cfg!(windows) { "rustc.exe" } else { "rustc" };
+const RUSTFMT: &str = if cfg!(windows) {
+    "rustfmt.exe"
+} else {
+    "rustfmt"
+};
+
+pub struct Rust;
+
+impl LanguageRunner for Rust {
+    fn language(&self) -> &'static str {
+        "rust"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "rustc"
+    }
+
+    fn toolchain(&self) -> &'static [&'static str] {
+        &["rustc"]
+    }
+
+    /// `rustc` on `PATH` is usually the rustup proxy, which looks for its
+    /// toolchains under `$HOME` — the sandbox's scratch dir. The real
+    /// compiler lives in the sysroot and needs no environment.
+    fn locate(&self) -> Result<PathBuf, String> {
+        let proxy = toolchain::discover(self.toolchain())?;
+
+        let sysroot = std::process::Command::new(&proxy)
+            .args(["--print", "sysroot"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()));
+        let real = sysroot
+            .map(|sysroot| sysroot.join("bin").join(RUSTC))
+            .filter(|path| path.is_file());
+
+        Ok(real.unwrap_or(proxy))
+    }
+
+    fn prepare(&self, sandbox: &Sandbox, code: &str) -> Result<Vec<PathBuf>, String> {
+        Ok(vec![sandbox.write_file("main.rs", code)?])
+    }
+
+    /// Debug build on purpose: overflow checks catch mistakes learners make.
+    fn build(&self, sandbox: &Sandbox, program: &Path, sources: &[PathBuf]) -> Option<Build> {
+        let mut command = sandbox.command_with_limits(program, &ResourceLimits::compiler());
+        command
+            .args(["--edition", "2021", "-o", binary_name()])
+            .args(sources);
+
+        Some(Build {
+            command,
+            binary: sandbox.path(binary_name()),
+        })
+    }
+
+    fn formatters(&self) -> &'static [Formatter] {
+        &[Formatter {
+            name: "rustfmt",
+            candidates: &["rustfmt"],
+            args: &["--edition", "2021"],
+        }]
+    }
+
+    /// The rustup proxy has the same problem as `rustc`'s, so `rustfmt` is
+    /// taken from the sysroot when it is installed there.
+    fn locate_formatter(&self, formatter: &Formatter) -> Result<PathBuf, String> {
+        let beside = self
+            .locate()
+            .ok()
+            .map(|rustc| rustc.with_file_name(RUSTFMT))
+            .filter(|path| path.is_file());
+        match beside {
+            Some(path) => Ok(path),
+            None => toolchain::discover(formatter.candidates),
+        }
+    }
+
+    /// Compiler errors are an `error[E…]: message` header followed by a
+    /// ` --> main.rs:L:C` pointer; panics name the location on their first
+    /// line and put the message on the next one.
+    fn parse_diagnostics(&self, stderr: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = stderr.lines().collect();
+        let mut diagnostics = Vec::new();
+
+        for (index, line) in lines.iter().enumerate() {
+            if let Some(message) = compiler_error(line) {
+                let location = lines[index + 1..]
+                    .iter()
+                    .take_while(|next| compiler_error(next).is_none())
+                    .find(|next| next.trim_start().starts_with("-->"))
+                    .and_then(|next| file_location(next, "main.rs"));
+                diagnostics.push(Diagnostic {
+                    line: location.map(|(line, _)| line),
+                    column: location.and_then(|(_, column)| column),
+                    message: message.to_string(),
+                });
+            } else if line.contains("panicked at") {
+                let location = file_location(line, "main.rs");
+                let message = lines
+                    .get(index + 1)
+                    .filter(|_| line.ends_with(':'))
+                    .map(|next| next.to_string())
+                    .unwrap_or_else(|| line.to_string());
+                diagnostics.push(Diagnostic {
+                    line: location.map(|(line, _)| line),
+                    column: location.and_then(|(_, column)| column),
+                    message,
+                });
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// `error[E0425]: cannot find value` or `error: expected one of ...`,
+/// minus the summary lines rustc prints at the end.
+fn compiler_error(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("error")?;
+    let (code, message) = rest.split_once(": ")?;
+    let is_summary = message.starts_with("aborting due to");
+    (!is_summary && (code.is_empty() || code.starts_with('['))).then_some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_compiler_errors() {
+        let stderr = "error[E0425]: cannot find value `x` in this scope\n --> main.rs:2:20\n  |\n2 |     println!(\"{}\", x);\n  |                    ^ not found in this scope\n\nerror: aborting due to 1 previous error\n";
+        let diagnostics = Rust.parse_diagnostics(stderr);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(2), Some(20))
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "cannot find value `x` in this scope"
+        );
+    }
+
+    #[test]
+    fn parses_panics() {
+        let stderr = "\nthread 'main' panicked at main.rs:3:13:\nattempt to divide by zero\nnote: run with `RUST_BACKTRACE=1`\n";
+        let diagnostics = Rust.parse_diagnostics(stderr);
+        assert_eq!(diagnostics[0].line, Some(3));
+        assert_eq!(diagnostics[0].message, "attempt to divide by zero");
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::{js_diagnostics, Diagnostic, Formatter, LanguageRunner, PRETTIER};
+use crate::runner::{unit_tests, Sandbox};
+
+pub struct TypeScript;
+
+impl LanguageRunner for TypeScript {
+    fn language(&self) -> &'static str {
+        "typescript"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "ts-node"
+    }
+
+    /// A global `ts-node` first, then the project's own copy when the app
+    /// is started from the repository.
+    fn toolchain(&self) -> &'static [&'static str] {
+        if cfg!(windows) {
+            &[
+                "ts-node",
+                ".\\node_modules\\.bin\\ts-node.cmd",
+                "..\\node_modules\\.bin\\ts-node.cmd",
+                "..\\..\\node_modules\\.bin\\ts-node.cmd",
+            ]
+        } else {
+            &[
+                "ts-node",
+                "./node_modules/.bin/ts-node",
+                "../node_modules/.bin/ts-node",
+                "../../node_modules/.bin/ts-node",
+            ]
+        }
+    }
+
+    fn prepare(&self, sandbox: &Sandbox, code: &str) -> Result<Vec<PathBuf>, String> {
+        Ok(vec![sandbox.write_file("main.ts", code)?])
+    }
+
+    fn command(&self, sandbox: &Sandbox, program: &Path, args: &[PathBuf]) -> Command {
+        let mut cmd = sandbox.command(program);
+        cmd.env("TS_NODE_TRANSPILE_ONLY", "true")
+            .env(
+                "TS_NODE_COMPILER_OPTIONS",
+                r#"{"module":"commonjs","target":"es2019"}"#,
+            )
+            .args(args);
+        cmd
+    }
+
+    fn with_tests(&self, code: &str, tests: &str, nonce: &str) -> Option<String> {
+        Some(unit_tests::js_harness(code, tests, nonce))
+    }
+
+    /// Same prettier as for JavaScript; the file name picks its parser.
+    fn formatters(&self) -> &'static [Formatter] {
+        &[Formatter {
+            name: "prettier",
+            candidates: PRETTIER,
+            args: &["--stdin-filepath", "main.ts"],
+        }]
+    }
+
+    fn parse_diagnostics(&self, stderr: &str) -> Vec<Diagnostic> {
+        js_diagnostics(stderr, "main.ts")
+    }
+}
@@ -0,0 +1,337 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::process::RunOutput;
+
+/// Caps files written into the scratch directory.
+pub const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+const MEMORY_MARKERS: &[&str] = &[
+    "JavaScript heap out of memory",
+    "Cannot allocate memory",
+    "std::bad_alloc",
+    "memory allocation of",
+    "runtime: out of memory",
+];
+
+const PROCESS_MARKERS: &[&str] = &["Resource temporarily unavailable", "spawn EAGAIN"];
+
+// Python and Node ignore SIGXFSZ, so the write fails with EFBIG instead.
+const FILE_SIZE_MARKERS: &[&str] = &["File too large", "EFBIG"];
+
+// Exit code of processes killed by a job object's CPU time limit.
+#[cfg(windows)]
+const ERROR_NOT_ENOUGH_QUOTA: i32 = 1816;
+
+/// Per-run resource limits. Exercises can override any subset of them
+/// through a `limits` object in the lesson content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    #[serde(alias = "timeoutMs")]
+    pub timeout_ms: u64,
+    #[serde(alias = "memoryMb")]
+    pub memory_mb: u64,
+    #[serde(alias = "cpuSeconds")]
+    pub cpu_seconds: u64,
+    #[serde(alias = "maxProcesses")]
+    pub max_processes: u64,
+    /// Applies to stdout and stderr separately.
+    #[serde(alias = "maxOutputBytes")]
+    pub max_output_bytes: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 10_000,
+            memory_mb: 512,
+            cpu_seconds: 5,
+            max_processes: 64,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl ResourceLimits {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    /// Limits for compiling learner code. Not lesson-configurable: `rustc`
+    /// and a cold Go build cache need far more than the program itself.
+    pub fn compiler() -> Self {
+        Self {
+            timeout_ms: 60_000,
+            memory_mb: 2048,
+            cpu_seconds: 60,
+            max_processes: 512,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+
+    /// Keeps lesson-supplied values within what the runtimes need to start
+    /// and what the app is willing to give a single run.
+    pub fn clamped(self) -> Self {
+        Self {
+            timeout_ms: self.timeout_ms.clamp(500, 60_000),
+            memory_mb: self.memory_mb.clamp(64, 4096),
+            cpu_seconds: self.cpu_seconds.clamp(1, 60),
+            max_processes: self.max_processes.clamp(16, 512),
+            max_output_bytes: self.max_output_bytes.clamp(1024, 16 * 1024 * 1024),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    Memory,
+    Cpu,
+    Processes,
+    Output,
+    FileSize,
+}
+
+impl LimitKind {
+    /// Works out which limit stopped the program, if any. Only a program
+    /// that actually failed can have hit a limit; stderr alone is under the
+    /// learner's control.
+    pub fn detect(output: &RunOutput, limits: &ResourceLimits) -> Option<Self> {
+        if output.output_truncated {
+            return Some(Self::Output);
+        }
+
+        let status = output.status?;
+        if status.success() {
+            return None;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+
+            match status.signal() {
+                Some(libc::SIGXCPU) => return Some(Self::Cpu),
+                Some(libc::SIGXFSZ) => return Some(Self::FileSize),
+                // The hard CPU limit sends SIGKILL to programs that ignore SIGXCPU.
+                Some(libc::SIGKILL)
+                    if output.cpu_time.map_or(!cfg!(target_os = "linux"), |cpu| {
+                        cpu.as_secs() >= limits.cpu_seconds
+                    }) =>
+                {
+                    return Some(Self::Cpu)
+                }
+                _ => {}
+            }
+        }
+
+        #[cfg(windows)]
+        if status.code() == Some(ERROR_NOT_ENOUGH_QUOTA) {
+            return Some(Self::Cpu);
+        }
+
+        #[cfg(not(unix))]
+        let _ = limits;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if is_out_of_memory(&stderr) {
+            Some(Self::Memory)
+        } else if PROCESS_MARKERS.iter().any(|marker| stderr.contains(marker)) {
+            Some(Self::Processes)
+        } else if FILE_SIZE_MARKERS
+            .iter()
+            .any(|marker| stderr.contains(marker))
+        {
+            Some(Self::FileSize)
+        } else {
+            None
+        }
+    }
+
+    pub fn message(&self, limits: &ResourceLimits) -> String {
+        match self {
+            Self::Memory => format!("Przekroczono limit pamięci ({} MB).", limits.memory_mb),
+            Self::Cpu => format!(
+                "Przekroczono limit czasu procesora ({} s).",
+                limits.cpu_seconds
+            ),
+            Self::Processes => format!(
+                "Przekroczono limit liczby procesów ({}).",
+                limits.max_processes
+            ),
+            Self::Output => format!(
+                "Program wypisał zbyt dużo danych (limit {} KB).",
+                limits.max_output_bytes / 1024
+            ),
+            Self::FileSize => format!(
+                "Program zapisał zbyt duży plik (limit {} MB).",
+                MAX_FILE_BYTES / (1024 * 1024)
+            ),
+        }
+    }
+}
+
+fn is_out_of_memory(stderr: &str) -> bool {
+    // A real allocation failure in Python ends the traceback with a bare
+    // `MemoryError`; `raise MemoryError("...")` in learner code does not.
+    let python = stderr
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| line.trim() == "MemoryError");
+
+    python || MEMORY_MARKERS.iter().any(|marker| stderr.contains(marker))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn output(status: Option<ExitStatus>, stderr: &str) -> RunOutput {
+        RunOutput {
+            status,
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+            timed_out: false,
+            cancelled: false,
+            output_truncated: false,
+            cpu_time: None,
+            duration: Duration::ZERO,
+        }
+    }
+
+    fn exited(code: i32) -> Option<ExitStatus> {
+        Some(ExitStatus::from_raw(code << 8))
+    }
+
+    fn signaled(signal: i32) -> Option<ExitStatus> {
+        Some(ExitStatus::from_raw(signal))
+    }
+
+    #[test]
+    fn successful_run_never_hits_a_limit() {
+        let out = output(exited(0), "MemoryError\nResource temporarily unavailable");
+        assert_eq!(LimitKind::detect(&out, &ResourceLimits::default()), None);
+    }
+
+    #[test]
+    fn learner_raised_memory_error_is_not_a_limit() {
+        let out = output(
+            exited(1),
+            "Traceback (most recent call last):\nMemoryError: za mało miejsca",
+        );
+        assert_eq!(LimitKind::detect(&out, &ResourceLimits::default()), None);
+    }
+
+    #[test]
+    fn allocation_failure_is_memory_limit() {
+        let out = output(
+            exited(1),
+            "Traceback (most recent call last):\nMemoryError\n",
+        );
+        assert_eq!(
+            LimitKind::detect(&out, &ResourceLimits::default()),
+            Some(LimitKind::Memory)
+        );
+
+        let out = output(
+            signaled(libc::SIGABRT),
+            "FATAL ERROR: Reached heap limit Allocation failed - JavaScript heap out of memory",
+        );
+        assert_eq!(
+            LimitKind::detect(&out, &ResourceLimits::default()),
+            Some(LimitKind::Memory)
+        );
+    }
+
+    #[test]
+    fn failed_fork_is_process_limit() {
+        let out = output(
+            exited(1),
+            "BlockingIOError: [Errno 11] Resource temporarily unavailable",
+        );
+        assert_eq!(
+            LimitKind::detect(&out, &ResourceLimits::default()),
+            Some(LimitKind::Processes)
+        );
+    }
+
+    #[test]
+    fn cpu_signals_are_cpu_limit() {
+        let limits = ResourceLimits::default();
+        let out = output(signaled(libc::SIGXCPU), "");
+        assert_eq!(LimitKind::detect(&out, &limits), Some(LimitKind::Cpu));
+
+        let mut out = output(signaled(libc::SIGKILL), "");
+        out.cpu_time = Some(Duration::from_secs(limits.cpu_seconds + 1));
+        assert_eq!(LimitKind::detect(&out, &limits), Some(LimitKind::Cpu));
+    }
+
+    #[test]
+    fn sigkill_without_cpu_use_is_not_cpu_limit() {
+        let mut out = output(signaled(libc::SIGKILL), "");
+        out.cpu_time = Some(Duration::from_millis(10));
+        assert_eq!(LimitKind::detect(&out, &ResourceLimits::default()), None);
+    }
+
+    #[test]
+    fn file_size_signal_is_file_limit() {
+        let out = output(signaled(libc::SIGXFSZ), "");
+        assert_eq!(
+            LimitKind::detect(&out, &ResourceLimits::default()),
+            Some(LimitKind::FileSize)
+        );
+
+        let out = output(exited(1), "OSError: [Errno 27] File too large");
+        assert_eq!(
+            LimitKind::detect(&out, &ResourceLimits::default()),
+            Some(LimitKind::FileSize)
+        );
+    }
+
+    #[test]
+    fn truncated_output_wins() {
+        let mut out = output(signaled(libc::SIGKILL), "");
+        out.output_truncated = true;
+        assert_eq!(
+            LimitKind::detect(&out, &ResourceLimits::default()),
+            Some(LimitKind::Output)
+        );
+    }
+
+    #[test]
+    fn timed_out_run_is_not_a_resource_limit() {
+        let mut out = output(None, "MemoryError");
+        out.timed_out = true;
+        assert_eq!(LimitKind::detect(&out, &ResourceLimits::default()), None);
+    }
+
+    #[test]
+    fn clamped_rejects_zero_and_huge_values() {
+        let limits = ResourceLimits {
+            timeout_ms: 0,
+            memory_mb: 0,
+            cpu_seconds: 0,
+            max_processes: 0,
+            max_output_bytes: 0,
+        }
+        .clamped();
+        assert!(limits.timeout_ms > 0 && limits.memory_mb > 0 && limits.cpu_seconds > 0);
+        assert!(limits.max_processes > 0 && limits.max_output_bytes > 0);
+
+        let limits = ResourceLimits {
+            timeout_ms: u64::MAX,
+            memory_mb: u64::MAX,
+            cpu_seconds: u64::MAX,
+            max_processes: u64::MAX,
+            max_output_bytes: usize::MAX,
+        }
+        .clamped();
+        assert_eq!(limits.timeout_ms, 60_000);
+        assert_eq!(limits.memory_mb, 4096);
+    }
+}
@@ -0,0 +1,17 @@
+pub mod compare;
+pub mod embedded;
+pub mod hints;
+#[cfg(windows)]
+mod job;
+pub mod languages;
+pub mod limits;
+pub mod process;
+pub mod sandbox;
+pub mod toolchain;
+pub mod unit_tests;
+
+pub use compare::{Comparison, DiffLine};
+pub use languages::{Diagnostic, LanguageRunner};
+pub use limits::{LimitKind, ResourceLimits};
+pub use sandbox::{Confinement, Sandbox};
+pub use unit_tests::UnitTestResult;
@@ -0,0 +1,532 @@
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::{mpsc, oneshot, watch};
+
+#[cfg(windows)]
+use super::job::Job;
+use super::limits::ResourceLimits;
+
+// Grandchildren that left the process group can keep the pipes open after
+// the kill; don't wait for them forever.
+const DRAIN_GRACE: Duration = Duration::from_millis(500);
+/// Lines an [`Interactive`] program may leave unread before more are
+/// refused.
+const INPUT_BACKLOG: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct RunOutput {
+    pub status: Option<ExitStatus>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub timed_out: bool,
+    /// Stopped through [`Observer::cancel`].
+    pub cancelled: bool,
+    pub output_truncated: bool,
+    /// CPU time of the leader and its reaped children, where the platform
+    /// lets us read it before the leader is reaped.
+    pub cpu_time: Option<Duration>,
+    /// Wall-clock time from spawn until the program exited or was killed.
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Output as the program printed it, before the run is over.
+#[derive(Debug)]
+pub struct Chunk {
+    pub stream: Stream,
+    pub data: Vec<u8>,
+}
+
+/// Lets a caller follow a run while it happens. The default watches
+/// nothing.
+#[derive(Debug, Clone, Default)]
+pub struct Observer {
+    /// Receives every chunk that also goes into the captured output.
+    pub output: Option<mpsc::UnboundedSender<Chunk>>,
+    /// Kills the process group once it turns `true`.
+    pub cancel: Option<watch::Receiver<bool>>,
+    /// Receives the run's place in the execution queue while it waits for
+    /// a free slot, and 0 once it starts.
+    pub queued: Option<mpsc::UnboundedSender<usize>>,
+}
+
+/// Spawns a sandboxed command and waits for it at most `limits.timeout()`.
+/// The whole process group is killed afterwards, so background
+/// processes started by learner code don't outlive the run.
+///
+/// `stdin` is written to the program and then closed; without it the
+/// program reads EOF straight away.
+pub async fn run(
+    cmd: std::process::Command,
+    limits: &ResourceLimits,
+    stdin: Option<&str>,
+    observer: Observer,
+) -> std::io::Result<RunOutput> {
+    let input = if stdin.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    };
+    let mut cmd = Command::from(cmd);
+    cmd.stdin(input)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let started = Instant::now();
+    let mut child = cmd.spawn()?;
+    let group = Arc::new(ProcessGroup::attach(&child, limits)?);
+    let truncated = Arc::new(AtomicBool::new(false));
+    let stdout_buf = Arc::new(Mutex::new(Vec::new()));
+    let stderr_buf = Arc::new(Mutex::new(Vec::new()));
+
+    // Written from a task so a program that never reads its input can't
+    // block us on a full pipe; a closed pipe just means it stopped reading.
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), stdin) {
+        let input = input.as_bytes().to_vec();
+        tokio::spawn(async move {
+            let _ = pipe.write_all(&input).await;
+        });
+    }
+
+    let stdout_task = tokio::spawn(read_capped(
+        child.stdout.take(),
+        limits.max_output_bytes,
+        stdout_buf.clone(),
+        group.clone(),
+        truncated.clone(),
+        observer
+            .output
+            .clone()
+            .map(|output| (Stream::Stdout, output)),
+    ));
+    let stderr_task = tokio::spawn(read_capped(
+        child.stderr.take(),
+        limits.max_output_bytes,
+        stderr_buf.clone(),
+        group.clone(),
+        truncated.clone(),
+        observer.output.map(|output| (Stream::Stderr, output)),
+    ));
+
+    let (timed_out, cancelled, cpu_time) = tokio::select! {
+        exited = tokio::time::timeout(limits.timeout(), group.exited(&mut child)) => match exited {
+            Ok(cpu_time) => (false, false, cpu_time),
+            Err(_) => (true, false, None),
+        },
+        _ = cancelled(observer.cancel) => (false, true, None),
+    };
+    let duration = started.elapsed();
+
+    // The leader is not reaped yet, so its pid still names our group.
+    group.kill();
+    group.release();
+    let status = child.wait().await?;
+
+    let stdout = drain(stdout_task, &stdout_buf).await;
+    let stderr = drain(stderr_task, &stderr_buf).await;
+
+    Ok(RunOutput {
+        status: (!timed_out && !cancelled).then_some(status),
+        stdout,
+        stderr,
+        timed_out,
+        cancelled,
+        output_truncated: truncated.load(Ordering::Relaxed),
+        cpu_time,
+        duration,
+    })
+}
+
+/// A sandboxed program that keeps running between inputs, such as a REPL.
+/// Output goes to the `output` channel as it is printed and is not kept.
+/// There is no wall-clock timeout: the CPU time limit still applies to the
+/// whole session, and `limits.max_output_bytes` to what each input prints.
+pub struct Interactive {
+    /// Lines for the writer task, which alone waits on the stdin pipe.
+    input: mpsc::Sender<String>,
+    output_exceeded: Arc<AtomicBool>,
+    kill: Option<oneshot::Sender<()>>,
+    exit: oneshot::Receiver<Option<ExitStatus>>,
+}
+
+impl Interactive {
+    pub fn spawn(
+        cmd: std::process::Command,
+        limits: &ResourceLimits,
+        output: mpsc::UnboundedSender<Chunk>,
+    ) -> std::io::Result<Self> {
+        let mut cmd = Command::from(cmd);
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = cmd.spawn()?;
+        let group = Arc::new(ProcessGroup::attach(&child, limits)?);
+        let stdin = child.stdin.take().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "stdin is not piped")
+        })?;
+        let printed = Arc::new(AtomicUsize::new(0));
+        let output_exceeded = Arc::new(AtomicBool::new(false));
+
+        let meter = Meter {
+            cap: limits.max_output_bytes,
+            printed: printed.clone(),
+            exceeded: output_exceeded.clone(),
+            group: group.clone(),
+        };
+        let readers = [
+            tokio::spawn(read_metered(
+                child.stdout.take(),
+                Stream::Stdout,
+                output.clone(),
+                meter.clone(),
+            )),
+            tokio::spawn(read_metered(
+                child.stderr.take(),
+                Stream::Stderr,
+                output,
+                meter,
+            )),
+        ];
+
+        // Dropping `kill` stops the program too, so a dropped session
+        // doesn't leave it running.
+        let (kill, killed) = oneshot::channel::<()>();
+        let (exit_tx, exit) = oneshot::channel();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = group.exited(&mut child) => {}
+                _ = killed => {}
+            }
+            group.kill();
+            group.release();
+            let status = child.wait().await.ok();
+            for reader in readers {
+                finish(reader).await;
+            }
+            let _ = exit_tx.send(status);
+        });
+
+        // A program that stops reading fills the pipe; only this task
+        // then waits, the caller keeps noticing exits and timeouts.
+        let (input, mut lines) = mpsc::channel::<String>(INPUT_BACKLOG);
+        let allowance = printed.clone();
+        tokio::spawn(async move {
+            let mut stdin = stdin;
+            while let Some(line) = lines.recv().await {
+                allowance.store(0, Ordering::Relaxed);
+                let written = match stdin.write_all(line.as_bytes()).await {
+                    Ok(()) => stdin.flush().await,
+                    Err(e) => Err(e),
+                };
+                if written.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            input,
+            output_exceeded,
+            kill: Some(kill),
+            exit,
+        })
+    }
+
+    /// Queues `input` for the program; writing it starts a new output
+    /// allowance. `WouldBlock` when the program has left
+    /// [`INPUT_BACKLOG`] lines unread, `BrokenPipe` once it is gone.
+    pub fn send(&self, input: String) -> std::io::Result<()> {
+        self.input.try_send(input).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => std::io::ErrorKind::WouldBlock.into(),
+            mpsc::error::TrySendError::Closed(_) => std::io::ErrorKind::BrokenPipe.into(),
+        })
+    }
+
+    /// Resolves once the program has ended and its output has been
+    /// forwarded. Call it at most once to completion.
+    pub async fn exited(&mut self) -> Option<ExitStatus> {
+        (&mut self.exit).await.ok().flatten()
+    }
+
+    /// Kills the program and waits until it is gone.
+    pub async fn stop(mut self) -> Option<ExitStatus> {
+        if let Some(kill) = self.kill.take() {
+            let _ = kill.send(());
+        }
+        self.exited().await
+    }
+
+    /// The program was killed for printing too much after one input.
+    pub fn output_exceeded(&self) -> bool {
+        self.output_exceeded.load(Ordering::Relaxed)
+    }
+}
+
+/// Output allowance shared by both pipes of an [`Interactive`] program.
+#[derive(Clone)]
+struct Meter {
+    cap: usize,
+    printed: Arc<AtomicUsize>,
+    exceeded: Arc<AtomicBool>,
+    group: Arc<ProcessGroup>,
+}
+
+/// Forwards a pipe of an [`Interactive`] program, killing it once what it
+/// printed since the last input goes over the cap.
+async fn read_metered<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    stream: Stream,
+    output: mpsc::UnboundedSender<Chunk>,
+    meter: Meter,
+) {
+    let Some(mut pipe) = pipe else {
+        return;
+    };
+
+    let mut chunk = [0u8; 8192];
+    loop {
+        match pipe.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let before = meter.printed.fetch_add(n, Ordering::Relaxed);
+                let kept = n.min(meter.cap.saturating_sub(before));
+                if kept > 0 {
+                    let _ = output.send(Chunk {
+                        stream,
+                        data: chunk[..kept].to_vec(),
+                    });
+                }
+                if kept < n {
+                    meter.exceeded.store(true, Ordering::Relaxed);
+                    meter.group.kill();
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Says how a failed program ended, for when it left nothing on stderr.
+pub fn describe_failure(status: &ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            let reason = match signal {
+                libc::SIGSEGV => " (naruszenie ochrony pamięci)",
+                libc::SIGABRT => " (przerwanie programu)",
+                libc::SIGFPE => " (błąd arytmetyczny)",
+                libc::SIGBUS => " (błąd szyny)",
+                libc::SIGKILL => " (zabity)",
+                _ => "",
+            };
+            return format!("Program został przerwany sygnałem {}{}.", signal, reason);
+        }
+    }
+
+    match status.code() {
+        Some(code) => format!("Program zakończył się kodem błędu {}.", code),
+        None => "Program zakończył się błędem.".to_string(),
+    }
+}
+
+/// Resolves once `cancel` turns `true`; never without one, or once its
+/// sender is gone.
+async fn cancelled(cancel: Option<watch::Receiver<bool>>) {
+    if let Some(mut cancel) = cancel {
+        if cancel.wait_for(|cancelled| *cancelled).await.is_ok() {
+            return;
+        }
+    }
+    std::future::pending().await
+}
+
+/// Reads a pipe up to `cap` bytes and kills the process group once the
+/// program tries to write more, so `print("x" * 10**9)` stops early.
+async fn read_capped<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    cap: usize,
+    buf: Arc<Mutex<Vec<u8>>>,
+    group: Arc<ProcessGroup>,
+    truncated: Arc<AtomicBool>,
+    forward: Option<(Stream, mpsc::UnboundedSender<Chunk>)>,
+) {
+    let Some(mut pipe) = pipe else {
+        return;
+    };
+
+    let mut chunk = [0u8; 8192];
+    loop {
+        match pipe.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let mut buf = buf.lock().unwrap();
+                let room = cap.saturating_sub(buf.len());
+                let kept = n.min(room);
+                buf.extend_from_slice(&chunk[..kept]);
+                if let Some((stream, output)) = &forward {
+                    if kept > 0 {
+                        let _ = output.send(Chunk {
+                            stream: *stream,
+                            data: chunk[..kept].to_vec(),
+                        });
+                    }
+                }
+                if n > room {
+                    truncated.store(true, Ordering::Relaxed);
+                    group.kill();
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Waits briefly for the reader to hit EOF, then keeps whatever it
+/// captured so far.
+async fn drain(task: tokio::task::JoinHandle<()>, buf: &Mutex<Vec<u8>>) -> Vec<u8> {
+    finish(task).await;
+    std::mem::take(&mut *buf.lock().unwrap())
+}
+
+/// Gives a pipe reader [`DRAIN_GRACE`] to hit EOF, then stops it.
+async fn finish(mut task: tokio::task::JoinHandle<()>) {
+    if tokio::time::timeout(DRAIN_GRACE, &mut task).await.is_err() {
+        task.abort();
+    }
+}
+
+/// Everything the learner's program started: a process group on Unix,
+/// a job object on Windows. `kill` is a no-op once the leader is reaped.
+struct ProcessGroup {
+    #[cfg(unix)]
+    pid: Option<u32>,
+    #[cfg(windows)]
+    job: Job,
+    alive: Mutex<bool>,
+}
+
+impl ProcessGroup {
+    fn attach(child: &tokio::process::Child, limits: &ResourceLimits) -> std::io::Result<Self> {
+        // On Windows the job object is what enforces the resource limits,
+        // so a run without one is refused.
+        #[cfg(windows)]
+        let job = {
+            let job = Job::new(limits)?;
+            job.assign(child)?;
+            job
+        };
+
+        #[cfg(not(windows))]
+        let _ = limits;
+
+        Ok(Self {
+            #[cfg(unix)]
+            pid: child.id(),
+            #[cfg(windows)]
+            job,
+            alive: Mutex::new(true),
+        })
+    }
+
+    /// Resolves once the leader has exited, without reaping it on Unix.
+    async fn exited(&self, child: &mut tokio::process::Child) -> Option<Duration> {
+        #[cfg(unix)]
+        {
+            let _ = child;
+            let pid = self.pid?;
+            tokio::task::spawn_blocking(move || {
+                wait_no_reap(pid);
+                cpu_time(pid)
+            })
+            .await
+            .ok()
+            .flatten()
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = child.wait().await;
+            None
+        }
+    }
+
+    fn kill(&self) {
+        let alive = self.alive.lock().unwrap();
+        if !*alive {
+            return;
+        }
+
+        // The sandbox makes every child a session leader, so its pid is the pgid.
+        #[cfg(unix)]
+        if let Some(pid) = self.pid {
+            unsafe {
+                libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+
+        #[cfg(windows)]
+        self.job.terminate();
+    }
+
+    fn release(&self) {
+        *self.alive.lock().unwrap() = false;
+    }
+}
+
+#[cfg(unix)]
+fn wait_no_reap(pid: u32) {
+    loop {
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        let res = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if res == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::EINTR) {
+            return;
+        }
+    }
+}
+
+/// Reads utime + stime (+ reaped children) of a zombie leader.
+#[cfg(target_os = "linux")]
+fn cpu_time(pid: u32) -> Option<Duration> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // Fields after the parenthesised command name start at field 3 (state).
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let ticks: u64 = fields
+        .get(11..15)?
+        .iter()
+        .map(|field| field.parse::<u64>().unwrap_or(0))
+        .sum();
+    let per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if per_second <= 0 {
+        return None;
+    }
+    Some(Duration::from_millis(ticks * 1000 / per_second as u64))
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn cpu_time(_pid: u32) -> Option<Duration> {
+    None
+}
@@ -0,0 +1,539 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::limits::ResourceLimits;
+#[cfg(unix)]
+use super::limits::MAX_FILE_BYTES;
+
+static SANDBOX_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+const PASSTHROUGH_ENV: &[&str] = &["PATH", "SystemRoot", "SYSTEMROOT", "PATHEXT", "WINDIR"];
+
+#[derive(Debug, Clone)]
+pub struct SandboxPolicy {
+    pub isolate_network: bool,
+    pub read_only_fs: bool,
+    /// Refuse to run when the kernel cannot provide the requested isolation
+    /// instead of falling back to rlimit-only confinement.
+    pub strict: bool,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self {
+            isolate_network: true,
+            read_only_fs: true,
+            strict: false,
+        }
+    }
+}
+
+impl SandboxPolicy {
+    /// Default policy; `SANDBOX_STRICT=1` turns on strict mode.
+    pub fn from_env() -> Self {
+        let strict = std::env::var("SANDBOX_STRICT")
+            .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        Self {
+            strict,
+            ..Self::default()
+        }
+    }
+}
+
+/// What the sandbox actually managed to enforce for a run. Returned to the
+/// frontend so a degraded sandbox is never silent.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Confinement {
+    pub network_isolated: bool,
+    pub filesystem_read_only: bool,
+    /// Memory, CPU time and file size limits.
+    pub resource_limits: bool,
+    pub process_limit: bool,
+    pub warnings: Vec<String>,
+}
+
+impl Confinement {
+    fn is_complete(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Throwaway working directory plus the confinement applied to every
+/// process spawned from it. The directory is removed on drop.
+pub struct Sandbox {
+    dir: PathBuf,
+    limits: ResourceLimits,
+    confinement: Confinement,
+    #[cfg(target_os = "linux")]
+    ruleset: Option<std::os::fd::OwnedFd>,
+}
+
+impl Sandbox {
+    pub fn new(limits: ResourceLimits) -> Result<Self, String> {
+        Self::with_policy(SandboxPolicy::from_env(), limits)
+    }
+
+    pub fn with_policy(policy: SandboxPolicy, limits: ResourceLimits) -> Result<Self, String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "desktop_app_sandbox_{}_{}_{}",
+            std::process::id(),
+            now,
+            SANDBOX_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        fs::create_dir(&dir)
+            .map_err(|e| format!("Nie można utworzyć katalogu roboczego: {}", e))?;
+
+        let mut confinement = Confinement::default();
+
+        #[cfg(target_os = "linux")]
+        let ruleset = {
+            let support = linux::support();
+
+            if policy.isolate_network {
+                if support.namespaces {
+                    confinement.network_isolated = true;
+                } else {
+                    confinement.warnings.push(
+                        "Brak izolacji sieci: system nie pozwala na przestrzenie nazw użytkownika."
+                            .to_string(),
+                    );
+                }
+            }
+
+            let mut ruleset = None;
+            if policy.read_only_fs {
+                match linux::write_ruleset(&dir, support.landlock_abi) {
+                    Ok(fd) => {
+                        confinement.filesystem_read_only = true;
+                        ruleset = Some(fd);
+                    }
+                    Err(e) => confinement.warnings.push(format!(
+                        "System plików poza katalogiem roboczym nie jest chroniony (Landlock niedostępny: {}).",
+                        e
+                    )),
+                }
+            }
+            ruleset
+        };
+
+        #[cfg(not(target_os = "linux"))]
+        if policy.isolate_network || policy.read_only_fs {
+            confinement.warnings.push(
+                "Izolacja sieci i systemu plików jest dostępna tylko na Linuksie.".to_string(),
+            );
+        }
+
+        // rlimits on Unix, the job object on Windows (see `process::run`).
+        confinement.resource_limits = cfg!(any(unix, windows));
+        // RLIMIT_NPROC is only scoped to the run inside our user namespace.
+        confinement.process_limit = if cfg!(target_os = "linux") {
+            confinement.network_isolated
+        } else {
+            cfg!(windows)
+        };
+        if !confinement.resource_limits {
+            confinement.warnings.push(
+                "Limity pamięci i czasu procesora nie są egzekwowane na tym systemie.".to_string(),
+            );
+        }
+        if !confinement.process_limit {
+            confinement.warnings.push(format!(
+                "Limit liczby procesów ({}) nie jest egzekwowany na tym systemie.",
+                limits.max_processes
+            ));
+        }
+
+        if policy.strict && !confinement.is_complete() {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(format!(
+                "Tryb ścisły piaskownicy: {}",
+                confinement.warnings.join(" ")
+            ));
+        }
+
+        Ok(Self {
+            dir,
+            limits,
+            confinement,
+            #[cfg(target_os = "linux")]
+            ruleset,
+        })
+    }
+
+    pub fn confinement(&self) -> &Confinement {
+        &self.confinement
+    }
+
+    /// Where `name` ends up inside the scratch directory.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn write_file(&self, name: &str, contents: &str) -> Result<PathBuf, String> {
+        let path = self.path(name);
+        fs::write(&path, contents.as_bytes())
+            .map_err(|e| format!("Nie można zapisać pliku tymczasowego: {}", e))?;
+        Ok(path)
+    }
+
+    /// Where a project's file at `relative` lives. Paths that would leave
+    /// the scratch directory are rejected.
+    pub fn project_path(&self, relative: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(relative);
+        let inside = relative
+            .components()
+            .all(|part| matches!(part, Component::Normal(_) | Component::CurDir));
+        if !inside || relative.file_name().is_none() {
+            return Err(format!(
+                "Nieprawidłowa ścieżka pliku: {}",
+                relative.display()
+            ));
+        }
+        Ok(self.dir.join(relative))
+    }
+
+    /// Writes one file of a project, creating its directories.
+    pub fn write_nested(&self, relative: &str, contents: &str) -> Result<PathBuf, String> {
+        let path = self.project_path(relative)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Nie można utworzyć katalogu: {}", e))?;
+        }
+        fs::write(&path, contents.as_bytes())
+            .map_err(|e| format!("Nie można zapisać pliku tymczasowego: {}", e))?;
+        Ok(path)
+    }
+
+    /// Builds a command that runs inside the scratch directory with a
+    /// scrubbed environment and, where the platform allows it, without
+    /// network access or write access outside the scratch directory.
+    pub fn command(&self, program: impl AsRef<std::ffi::OsStr>) -> Command {
+        self.command_with_limits(program, &self.limits)
+    }
+
+    /// Same confinement with different limits, e.g. for a compiler that
+    /// needs more memory than the learner's program is allowed.
+    pub fn command_with_limits(
+        &self,
+        program: impl AsRef<std::ffi::OsStr>,
+        limits: &ResourceLimits,
+    ) -> Command {
+        let mut cmd = Command::new(program);
+        cmd.current_dir(&self.dir).env_clear();
+
+        for key in PASSTHROUGH_ENV {
+            if let Some(value) = std::env::var_os(key) {
+                cmd.env(key, value);
+            }
+        }
+
+        cmd.env("HOME", &self.dir)
+            .env("USERPROFILE", &self.dir)
+            .env("TMPDIR", &self.dir)
+            .env("TEMP", &self.dir)
+            .env("TMP", &self.dir)
+            .env("LANG", "C.UTF-8")
+            .env("PYTHONIOENCODING", "utf-8")
+            .env("PYTHONDONTWRITEBYTECODE", "1")
+            .env("NO_COLOR", "1");
+
+        #[cfg(unix)]
+        self.confine(&mut cmd, limits);
+        #[cfg(not(unix))]
+        let _ = limits;
+
+        cmd
+    }
+
+    #[cfg(unix)]
+    fn confine(&self, cmd: &mut Command, limits: &ResourceLimits) {
+        use std::os::unix::process::CommandExt;
+
+        let isolate_network = self.confinement.network_isolated;
+        let memory_bytes = limits.memory_mb.saturating_mul(1024 * 1024);
+        let cpu_seconds = limits.cpu_seconds;
+        let max_processes = limits.max_processes;
+        #[cfg(target_os = "linux")]
+        let ruleset = self.ruleset.as_ref().map(|fd| {
+            use std::os::fd::AsRawFd;
+            fd.as_raw_fd()
+        });
+
+        // Runs in the forked child: only async-signal-safe syscalls, no allocation.
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+
+                set_limit(libc::RLIMIT_CORE, 0, 0)?;
+                set_limit(libc::RLIMIT_FSIZE, MAX_FILE_BYTES, MAX_FILE_BYTES)?;
+                // RLIMIT_DATA rather than RLIMIT_AS: V8 reserves far more
+                // address space than it ever touches.
+                set_limit(libc::RLIMIT_DATA, memory_bytes, memory_bytes)?;
+                // SIGXCPU at the soft limit, SIGKILL one second later.
+                set_limit(libc::RLIMIT_CPU, cpu_seconds, cpu_seconds + 1)?;
+
+                #[cfg(target_os = "linux")]
+                {
+                    // The probe said this works, so a failure here must not
+                    // quietly fall back to an unconfined run.
+                    if isolate_network
+                        && libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) == -1
+                    {
+                        return Err(std::io::Error::last_os_error());
+                    }
+
+                    // RLIMIT_NPROC counts every process of the user, so it is
+                    // only meaningful once we have a user namespace of our own.
+                    if isolate_network {
+                        set_limit(libc::RLIMIT_NPROC, max_processes, max_processes)?;
+                    }
+
+                    if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+
+                    if let Some(fd) = ruleset {
+                        if linux::restrict_self(fd) == -1 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                    }
+                }
+
+                #[cfg(not(target_os = "linux"))]
+                let _ = (isolate_network, max_processes);
+
+                Ok(())
+            });
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type Resource = libc::c_int;
+
+/// Lowers a limit, never above the hard limit we inherited.
+#[cfg(unix)]
+fn set_limit(resource: Resource, soft: u64, hard: u64) -> std::io::Result<()> {
+    let mut current = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(resource, &mut current) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let hard = (hard as libc::rlim_t).min(current.rlim_max);
+    let limit = libc::rlimit {
+        rlim_cur: (soft as libc::rlim_t).min(hard),
+        rlim_max: hard,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CString;
+    use std::os::fd::{FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::sync::OnceLock;
+
+    const LANDLOCK_CREATE_RULESET_VERSION: libc::c_uint = 1;
+    const LANDLOCK_RULE_PATH_BENEATH: libc::c_int = 1;
+
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+    const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+    const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+    const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+    const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+    const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+    const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+    const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+    const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+    // ABI 2
+    const ACCESS_FS_REFER: u64 = 1 << 13;
+    // ABI 3
+    const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+
+    const WRITE_ACCESS_V1: u64 = ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_REMOVE_DIR
+        | ACCESS_FS_REMOVE_FILE
+        | ACCESS_FS_MAKE_CHAR
+        | ACCESS_FS_MAKE_DIR
+        | ACCESS_FS_MAKE_REG
+        | ACCESS_FS_MAKE_SOCK
+        | ACCESS_FS_MAKE_FIFO
+        | ACCESS_FS_MAKE_BLOCK
+        | ACCESS_FS_MAKE_SYM;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: libc::c_int,
+    }
+
+    pub struct Support {
+        pub namespaces: bool,
+        pub landlock_abi: i64,
+    }
+
+    /// Kernel features are probed once per app run and logged if missing.
+    pub fn support() -> &'static Support {
+        static SUPPORT: OnceLock<Support> = OnceLock::new();
+        SUPPORT.get_or_init(|| {
+            let support = Support {
+                namespaces: probe_namespaces(),
+                landlock_abi: landlock_abi(),
+            };
+            if !support.namespaces {
+                println!("Sandbox: user namespaces unavailable, code runs keep network access");
+            }
+            if support.landlock_abi < 1 {
+                println!("Sandbox: Landlock unavailable, filesystem stays writable");
+            }
+            support
+        })
+    }
+
+    fn probe_namespaces() -> bool {
+        // The forked child only calls unshare and _exit, which is safe even
+        // though the parent is multi-threaded.
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            let code = if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } == 0 {
+                0
+            } else {
+                1
+            };
+            unsafe { libc::_exit(code) };
+        }
+        if pid < 0 {
+            return false;
+        }
+
+        let mut status = 0;
+        let waited = unsafe { libc::waitpid(pid, &mut status, 0) };
+        waited == pid && libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0
+    }
+
+    fn landlock_abi() -> i64 {
+        unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        }
+    }
+
+    fn write_access(abi: i64) -> u64 {
+        let mut access = WRITE_ACCESS_V1;
+        if abi >= 2 {
+            access |= ACCESS_FS_REFER;
+        }
+        if abi >= 3 {
+            access |= ACCESS_FS_TRUNCATE;
+        }
+        access
+    }
+
+    /// Landlock ruleset that denies every write outside `scratch`
+    /// (and `/dev`, so `/dev/null` keeps working).
+    pub fn write_ruleset(scratch: &Path, abi: i64) -> std::io::Result<OwnedFd> {
+        if abi < 1 {
+            return Err(std::io::Error::from_raw_os_error(libc::ENOSYS));
+        }
+
+        let handled = write_access(abi);
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0,
+            )
+        };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) };
+
+        allow(&ruleset, scratch, handled)?;
+        allow(
+            &ruleset,
+            Path::new("/dev"),
+            handled & (ACCESS_FS_WRITE_FILE | ACCESS_FS_TRUNCATE),
+        )?;
+
+        Ok(ruleset)
+    }
+
+    fn allow(ruleset: &OwnedFd, path: &Path, access: u64) -> std::io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        let raw = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+        if raw < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let parent = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let attr = PathBeneathAttr {
+            allowed_access: access,
+            parent_fd: parent.as_raw_fd(),
+        };
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                LANDLOCK_RULE_PATH_BENEATH,
+                &attr as *const PathBeneathAttr,
+                0,
+            )
+        };
+        if res < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn restrict_self(ruleset: libc::c_int) -> libc::c_long {
+        unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0) }
+    }
+}
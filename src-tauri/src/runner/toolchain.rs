@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+static FOUND: OnceLock<Mutex<HashMap<&'static str, Toolchain>>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct Toolchain {
+    pub path: PathBuf,
+    /// First line of `--version`, e.g. `Python 3.12.1`.
+    pub version: Option<String>,
+}
+
+/// Runs `locate` once per runner and remembers where the toolchain is
+/// and which version it reports. Only successes are cached, so installing
+/// a missing compiler while the app is open works without a restart.
+pub fn cached(
+    runner: &'static str,
+    locate: impl FnOnce() -> Result<PathBuf, String>,
+    version_args: &[&str],
+) -> Result<Toolchain, String> {
+    let found = FOUND.get_or_init(Default::default);
+
+    let known = found.lock().unwrap().get(runner).cloned();
+    if let Some(toolchain) = known.filter(|toolchain| toolchain.path.exists()) {
+        return Ok(toolchain);
+    }
+
+    let path = locate()?;
+    let toolchain = Toolchain {
+        version: probe_version(&path, version_args),
+        path,
+    };
+    println!(
+        "Toolchain for {}: {} ({})",
+        runner,
+        toolchain.path.display(),
+        toolchain.version.as_deref().unwrap_or("unknown version")
+    );
+    found.lock().unwrap().insert(runner, toolchain.clone());
+    Ok(toolchain)
+}
+
+/// Drops every cached toolchain so the next lookup searches again.
+pub fn forget_all() {
+    if let Some(found) = FOUND.get() {
+        found.lock().unwrap().clear();
+    }
+}
+
+/// Runs the toolchain itself, outside the sandbox: it is the user's own
+/// installation, not learner code. Python 2 prints its version to stderr.
+fn probe_version(path: &Path, args: &[&str]) -> Option<String> {
+    let mut child = Command::new(path)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    let deadline = Instant::now() + VERSION_TIMEOUT;
+    while child.try_wait().ok()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let output = child.wait_with_output().ok()?;
+    [output.stdout, output.stderr].iter().find_map(|stream| {
+        String::from_utf8_lossy(stream)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    })
+}
+
+/// Finds the first candidate that exists. Bare names are looked up on
+/// `PATH`; candidates with a path separator (e.g. a project-local
+/// `node_modules/.bin/ts-node`) are resolved against our working
+/// directory, because the sandbox runs programs from its scratch dir.
+pub fn discover(candidates: &[&str]) -> Result<PathBuf, String> {
+    candidates
+        .iter()
+        .find_map(|candidate| locate(candidate))
+        .ok_or_else(|| {
+            format!(
+                "Nie znaleziono żadnego z programów: {}",
+                candidates.join(", ")
+            )
+        })
+}
+
+fn locate(candidate: &str) -> Option<PathBuf> {
+    if candidate.contains('/') || candidate.contains('\\') {
+        return std::fs::canonicalize(candidate)
+            .ok()
+            .filter(|path| is_executable(path));
+    }
+
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| {
+        executable_names(candidate)
+            .into_iter()
+            .map(|name| dir.join(name))
+            .find(|path| is_executable(path))
+    })
+}
+
+/// On Windows `ts-node` is really `ts-node.cmd`, so every `PATHEXT`
+/// extension is tried as well.
+fn executable_names(name: &str) -> Vec<OsString> {
+    let mut names = vec![OsString::from(name)];
+
+    if cfg!(windows) && Path::new(name).extension().is_none() {
+        let extensions =
+            std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+        names.extend(
+            extensions
+                .split(';')
+                .filter(|ext| !ext.is_empty())
+                .map(|ext| OsString::from(format!("{}{}", name, ext.to_ascii_lowercase()))),
+        );
+    }
+
+    names
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,153 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+/// Result of one hidden test from the exercise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnitTestResult {
+    pub name: String,
+    pub passed: bool,
+    /// Assertion message, or the failing `assert` line when there is none.
+    pub message: Option<String>,
+}
+
+/// Marks the harness's result lines on stdout. Random per run, so a
+/// learner's `print` can't pass for a test result by accident.
+pub fn nonce() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default(),
+    );
+    format!("__test_result_{:016x}__", hasher.finish())
+}
+
+/// Splits the harness's result lines from what the program printed itself.
+pub fn parse(stdout: &str, nonce: &str) -> (Vec<UnitTestResult>, String) {
+    let mut results = Vec::new();
+    let mut printed = String::new();
+
+    for line in stdout.split_inclusive('\n') {
+        match line.strip_prefix(nonce) {
+            Some(json) => {
+                if let Ok(result) = serde_json::from_str(json.trim()) {
+                    results.push(result);
+                }
+            }
+            None => printed.push_str(line),
+        }
+    }
+
+    (results, printed)
+}
+
+/// Learner code first so its line numbers stay right in error messages,
+/// then the runner. The hidden tests run in a namespace of their own, so a
+/// learner's function can neither replace a hidden `test_*` nor be taken
+/// for one; only what the tests define themselves is run.
+pub fn python_harness(code: &str, tests: &str, nonce: &str) -> String {
+    // A JSON string is also a valid Python string literal.
+    let tests = serde_json::to_string(tests).unwrap_or_default();
+    format!(
+        r#"{code}
+
+def __run_tests():
+    import json, traceback
+    learner = globals()
+    namespace = dict(learner)
+    exec(compile({tests}, "tests.py", "exec"), namespace)
+    for name, fn in list(namespace.items()):
+        if not name.startswith("test_") or not callable(fn) or fn is learner.get(name):
+            continue
+        try:
+            fn()
+            passed, message = True, None
+        except AssertionError as e:
+            frame = traceback.extract_tb(e.__traceback__)[-1]
+            passed, message = False, str(e) or (frame.line or "assert")
+        except Exception as e:
+            passed, message = False, f"{{type(e).__name__}}: {{e}}"
+        print("{nonce}" + json.dumps({{"name": name, "passed": passed, "message": message}}), flush=True)
+
+__run_tests()
+"#
+    )
+}
+
+/// Same layout for JavaScript and TypeScript. `test` is a function
+/// declaration so the hidden tests can call it before the harness code
+/// below them has run; the test bodies only run at the end.
+pub fn js_harness(code: &str, tests: &str, nonce: &str) -> String {
+    format!(
+        r#"{code}
+;
+{tests}
+;
+function test(name, fn) {{
+  (globalThis.__tests = globalThis.__tests || []).push([name, fn]);
+}}
+if (typeof assert === "undefined") {{
+  globalThis.assert = require("assert");
+}}
+(async () => {{
+  for (const [name, fn] of globalThis.__tests || []) {{
+    let passed = true;
+    let message = null;
+    try {{
+      await fn();
+    }} catch (e) {{
+      passed = false;
+      message = e && e.message ? e.message : String(e);
+    }}
+    process.stdout.write("{nonce}" + JSON.stringify({{ name, passed, message }}) + "\n");
+  }}
+}})();
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separates_results_from_printed_output() {
+        let nonce = nonce();
+        let stdout = format!(
+            "hello\n{nonce}{{\"name\": \"test_add\", \"passed\": true, \"message\": null}}\n{nonce}{{\"name\": \"test_sub\", \"passed\": false, \"message\": \"assert sub(2, 1) == 2\"}}\nbye\n"
+        );
+        let (results, printed) = parse(&stdout, &nonce);
+        assert_eq!(printed, "hello\nbye\n");
+        assert_eq!(results.len(), 2);
+        assert!(results[0].passed);
+        assert_eq!(results[1].message.as_deref(), Some("assert sub(2, 1) == 2"));
+    }
+
+    #[test]
+    fn learner_functions_cannot_shadow_hidden_tests() {
+        let code = "def add(a, b):\n    return a - b\n\ndef test_add():\n    pass\n\ndef test_mine():\n    pass\n";
+        let tests = "def test_add():\n    assert add(2, 2) == 4, \"2 + 2\"\n";
+        let nonce = nonce();
+        let harness = python_harness(code, tests, &nonce);
+
+        let Ok(output) = std::process::Command::new("python3")
+            .args(["-c", &harness])
+            .output()
+        else {
+            return println!("python3 not available, skipped");
+        };
+        let (results, _) = parse(&String::from_utf8_lossy(&output.stdout), &nonce);
+        assert_eq!(results.len(), 1, "{:?}", results);
+        assert_eq!(results[0].name, "test_add");
+        assert!(!results[0].passed);
+        assert_eq!(results[0].message.as_deref(), Some("2 + 2"));
+    }
+
+    #[test]
+    fn nonces_differ() {
+        assert_ne!(nonce(), nonce());
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, State, Window};
+use tokio::sync::{mpsc, watch};
+
+use crate::pool;
+use crate::runner::languages::{self, Engine};
+use crate::runner::process::{Chunk, Observer, Stream};
+use crate::runner::ResourceLimits;
+use crate::{run_validation, CodeValidationResponse, Grading};
+
+/// Runs started with `run_code_streaming` that are still going, by run id.
+#[derive(Default)]
+pub struct Runs {
+    active: Mutex<HashMap<String, watch::Sender<bool>>>,
+}
+
+/// Payload of the `run-output` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunOutputEvent {
+    run_id: String,
+    stream: Stream,
+    data: String,
+}
+
+/// Runs the code like the playground's Run button, emitting what it prints
+/// as `run-output` events while it runs. The frontend picks `run_id`, so it
+/// can listen before the run starts; the final result is returned the same
+/// way as from `validate_code`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_code_streaming(
+    window: Window,
+    runs: State<'_, Runs>,
+    run_id: String,
+    code: String,
+    language: String,
+    stdin: Option<String>,
+    limits: Option<ResourceLimits>,
+    engine: Option<Engine>,
+) -> Result<CodeValidationResponse, String> {
+    let runner = languages::select(&language, engine.unwrap_or_default())?;
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    {
+        let mut active = runs.active.lock().unwrap();
+        if active.contains_key(&run_id) {
+            return Err(format!("Uruchomienie {} już trwa", run_id));
+        }
+        active.insert(run_id.clone(), cancel_tx);
+    }
+
+    let queued = pool::report_position(window.clone(), Some(run_id.clone()));
+    let (output_tx, output_rx) = mpsc::unbounded_channel();
+    let forwarder = tauri::async_runtime::spawn(forward(output_rx, {
+        let run_id = run_id.clone();
+        move |stream, data| {
+            let event = RunOutputEvent {
+                run_id: run_id.clone(),
+                stream,
+                data,
+            };
+            if let Err(e) = window.emit_to(window.label(), "run-output", event) {
+                println!("Failed to emit run output: {}", e);
+            }
+        }
+    }));
+
+    let observer = Observer {
+        output: Some(output_tx),
+        cancel: Some(cancel_rx),
+        queued: Some(queued),
+    };
+    let response = run_validation(
+        code,
+        runner,
+        stdin,
+        limits.unwrap_or_default().clamped(),
+        &Grading::RunOnly,
+        observer,
+    )
+    .await;
+
+    runs.active.lock().unwrap().remove(&run_id);
+    // Every chunk is emitted before the caller gets the final result.
+    let _ = forwarder.await;
+    response
+}
+
+/// Kills a run started with `run_code_streaming`. Returns `false` when the
+/// run has already finished.
+#[tauri::command]
+pub fn cancel_run(runs: State<'_, Runs>, run_id: String) -> Result<bool, String> {
+    match runs.active.lock().unwrap().get(&run_id) {
+        Some(cancel) => {
+            println!("Cancelling run {}", run_id);
+            let _ = cancel.send(true);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Hands output to `emit` as text until every sender is gone. A character
+/// split between two reads is held back until the rest of it arrives.
+pub(crate) async fn forward(
+    mut output: mpsc::UnboundedReceiver<Chunk>,
+    emit: impl Fn(Stream, String),
+) {
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+
+    while let Some(chunk) = output.recv().await {
+        let pending = match chunk.stream {
+            Stream::Stdout => &mut stdout,
+            Stream::Stderr => &mut stderr,
+        };
+        pending.extend_from_slice(&chunk.data);
+        let data = take_complete(pending);
+        if !data.is_empty() {
+            emit(chunk.stream, data);
+        }
+    }
+
+    for (stream, rest) in [(Stream::Stdout, stdout), (Stream::Stderr, stderr)] {
+        if !rest.is_empty() {
+            emit(stream, String::from_utf8_lossy(&rest).into_owned());
+        }
+    }
+}
+
+/// Decodes `pending` up to an incomplete character at its end, which stays
+/// in `pending`. Invalid bytes are replaced.
+fn take_complete(pending: &mut Vec<u8>) -> String {
+    let complete = match std::str::from_utf8(pending) {
+        Ok(text) => text.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let rest = pending.split_off(complete);
+    let text = String::from_utf8_lossy(pending).into_owned();
+    *pending = rest;
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_back_a_split_character() {
+        let bytes = "zażółć".as_bytes();
+        let mut pending = bytes[..3].to_vec();
+        assert_eq!(take_complete(&mut pending), "za");
+        assert_eq!(pending, &bytes[2..3]);
+
+        pending.extend_from_slice(&bytes[3..]);
+        assert_eq!(take_complete(&mut pending), "żółć");
+        assert!(pending.is_empty());
+    }
+}
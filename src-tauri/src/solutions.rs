@@ -0,0 +1,292 @@
+//! Checks that the `solution` of an exercise lesson really passes the
+//! lesson's own test cases, hidden tests or markup assertions, so a lesson
+//! nobody can pass is never published.
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+
+use crate::markup;
+use crate::runner::languages;
+use crate::runner::process::Observer;
+use crate::runner::unit_tests;
+use crate::supabase::{types::Course, SupabaseClient};
+use crate::{run_test_cases, run_validation, ExerciseChecks, Grading, Outcome, TestCasesResponse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SolutionStatus {
+    Passed,
+    Failed,
+    /// Not an exercise, or an exercise with nothing to check against.
+    Skipped,
+    /// The solution could not be run here, e.g. the toolchain is missing.
+    Unverified,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SolutionCheck {
+    status: SolutionStatus,
+    /// What failed, or why the solution was not checked.
+    problems: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LessonSolutionReport {
+    lesson_id: String,
+    title: String,
+    module: String,
+    #[serde(flatten)]
+    check: SolutionCheck,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CourseSolutionsReport {
+    course_id: String,
+    passed: usize,
+    failed: usize,
+    unverified: usize,
+    /// Every exercise lesson of the course, in course order.
+    lessons: Vec<LessonSolutionReport>,
+}
+
+impl SolutionCheck {
+    fn new(status: SolutionStatus, problems: Vec<String>) -> Self {
+        Self { status, problems }
+    }
+}
+
+/// Runs an exercise's `solution` against its `testCases` and hidden
+/// `tests`, or for HTML and CSS checks it against the `assertions`.
+/// Lessons of other types are skipped.
+pub async fn check(language: &str, lesson_type: &str, content: &Value) -> SolutionCheck {
+    if lesson_type != "exercise" {
+        return SolutionCheck::new(SolutionStatus::Skipped, Vec::new());
+    }
+    if matches!(language, "html" | "css") {
+        return check_markup(language, content);
+    }
+    match run_checks(language, content).await {
+        Ok(check) => check,
+        Err(e) => SolutionCheck::new(SolutionStatus::Unverified, vec![e]),
+    }
+}
+
+fn solution(content: &Value) -> Result<&str, SolutionCheck> {
+    content
+        .get("solution")
+        .and_then(Value::as_str)
+        .filter(|solution| !solution.trim().is_empty())
+        .ok_or_else(|| {
+            SolutionCheck::new(
+                SolutionStatus::Failed,
+                vec!["Ćwiczenie nie ma rozwiązania".to_string()],
+            )
+        })
+}
+
+/// Nothing is run: the solution page is parsed and held against the
+/// lesson's assertions.
+fn check_markup(language: &str, content: &Value) -> SolutionCheck {
+    let solution = match solution(content) {
+        Ok(solution) => solution,
+        Err(check) => return check,
+    };
+    match markup::check_solution(solution, language, content) {
+        Ok(None) => SolutionCheck::new(SolutionStatus::Skipped, Vec::new()),
+        Ok(Some(failed)) if failed.is_empty() => {
+            SolutionCheck::new(SolutionStatus::Passed, Vec::new())
+        }
+        Ok(Some(failed)) => SolutionCheck::new(SolutionStatus::Failed, failed),
+        Err(e) => SolutionCheck::new(SolutionStatus::Failed, vec![e]),
+    }
+}
+
+async fn run_checks(language: &str, content: &Value) -> Result<SolutionCheck, String> {
+    let checks = match ExerciseChecks::from_content(content) {
+        Ok(checks) => checks,
+        Err(e) => return Ok(SolutionCheck::new(SolutionStatus::Failed, vec![e])),
+    };
+    let solution = match solution(content) {
+        Ok(solution) => solution,
+        Err(check) => return Ok(check),
+    };
+    if checks.test_cases.is_empty() && checks.tests.is_none() {
+        return Ok(SolutionCheck::new(SolutionStatus::Skipped, Vec::new()));
+    }
+
+    let runner = languages::select(language, checks.engine.unwrap_or_default())?;
+    let limits = checks.limits.unwrap_or_default().clamped();
+    let mut problems = Vec::new();
+    let mut unverified = Vec::new();
+
+    if !checks.test_cases.is_empty() {
+        let report = run_test_cases(
+            solution,
+            runner,
+            checks.test_cases,
+            limits.clone(),
+            &checks.comparison.unwrap_or_default(),
+        )
+        .await?;
+        describe_cases(&report, &mut problems, &mut unverified);
+    }
+
+    if let Some(tests) = &checks.tests {
+        let grading = Grading::UnitTests {
+            tests,
+            nonce: unit_tests::nonce(),
+        };
+        let response = run_validation(
+            solution.to_string(),
+            runner,
+            None,
+            limits,
+            &grading,
+            Observer::default(),
+        )
+        .await?;
+        if cannot_run(&response.outcome) {
+            unverified.extend(response.error);
+        } else if !response.is_correct {
+            let failed: Vec<_> = response
+                .tests
+                .iter()
+                .filter(|test| !test.passed)
+                .map(|test| test.name.clone())
+                .collect();
+            problems.push(if failed.is_empty() {
+                format!(
+                    "Testy jednostkowe: {}",
+                    response
+                        .compile_error
+                        .or(response.error)
+                        .unwrap_or_else(|| "rozwiązanie nie przechodzi testów".to_string())
+                )
+            } else {
+                format!("Niezaliczone testy jednostkowe: {}", failed.join(", "))
+            });
+        }
+    }
+
+    Ok(if !problems.is_empty() {
+        SolutionCheck::new(SolutionStatus::Failed, problems)
+    } else if !unverified.is_empty() {
+        SolutionCheck::new(SolutionStatus::Unverified, unverified)
+    } else {
+        SolutionCheck::new(SolutionStatus::Passed, Vec::new())
+    })
+}
+
+fn describe_cases(
+    report: &TestCasesResponse,
+    problems: &mut Vec<String>,
+    unverified: &mut Vec<String>,
+) {
+    for (index, case) in report.cases.iter().enumerate() {
+        if case.passed {
+            continue;
+        }
+        let name = case
+            .description
+            .clone()
+            .unwrap_or_else(|| format!("#{}", index + 1));
+        if cannot_run(&case.outcome) {
+            unverified.extend(case.error.clone());
+        } else {
+            problems.push(match &case.error {
+                Some(error) => format!("Test {}: {}", name, error),
+                None => format!(
+                    "Test {}: oczekiwano „{}”, rozwiązanie wypisało „{}”",
+                    name,
+                    case.expected_output.trim(),
+                    case.actual_output
+                ),
+            });
+        }
+    }
+    unverified.dedup();
+}
+
+/// Outcomes that say nothing about the solution itself.
+fn cannot_run(outcome: &Outcome) -> bool {
+    matches!(outcome, Outcome::ToolchainMissing | Outcome::SandboxError)
+}
+
+/// Refuses to save an exercise whose solution fails its own tests. A
+/// solution that can't be run on this machine is saved with a warning in
+/// the log, so a missing compiler doesn't block editing.
+pub async fn ensure_valid(
+    language: &str,
+    lesson_type: &str,
+    content: &Value,
+) -> Result<(), String> {
+    let check = check(language, lesson_type, content).await;
+    match check.status {
+        SolutionStatus::Failed => Err(format!(
+            "Rozwiązanie nie przechodzi testów ćwiczenia:\n{}",
+            check.problems.join("\n")
+        )),
+        SolutionStatus::Unverified => {
+            println!("Solution not verified: {}", check.problems.join("; "));
+            Ok(())
+        }
+        SolutionStatus::Passed | SolutionStatus::Skipped => Ok(()),
+    }
+}
+
+/// Checks the solution of every exercise in the course, published or not.
+#[tauri::command]
+pub async fn verify_course_solutions(
+    client: State<'_, SupabaseClient>,
+    course_id: String,
+) -> Result<CourseSolutionsReport, String> {
+    let courses: Vec<Course> = client
+        .rest_request(
+            Method::GET,
+            &format!("courses?select=*,modules(*,lessons(*))&id=eq.{}", course_id),
+            None,
+        )
+        .await?;
+    let mut course = courses
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Course {} not found", course_id))?;
+
+    course.modules.sort_by_key(|module| module.order_index);
+    let mut lessons = Vec::new();
+    for module in &mut course.modules {
+        module.lessons.sort_by_key(|lesson| lesson.order_index);
+        for lesson in &module.lessons {
+            let check = check(&lesson.language, &lesson.lesson_type, &lesson.content).await;
+            if check.status != SolutionStatus::Skipped {
+                lessons.push(LessonSolutionReport {
+                    lesson_id: lesson.id.clone(),
+                    title: lesson.title.clone(),
+                    module: module.title.clone(),
+                    check,
+                });
+            }
+        }
+    }
+
+    let count = |status| {
+        lessons
+            .iter()
+            .filter(|lesson| lesson.check.status == status)
+            .count()
+    };
+    let report = CourseSolutionsReport {
+        course_id,
+        passed: count(SolutionStatus::Passed),
+        failed: count(SolutionStatus::Failed),
+        unverified: count(SolutionStatus::Unverified),
+        lessons,
+    };
+    println!(
+        "Course solutions: {} passed, {} failed, {} unverified",
+        report.passed, report.failed, report.unverified
+    );
+    Ok(report)
+}
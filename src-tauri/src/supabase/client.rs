@@ -1,13 +1,37 @@
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use serde::Deserialize;
 use serde_json::{json, Value};
 
-use super::types::{SupabaseAuthResponse, SupabaseError};
+use super::config::get_supabase_config;
+use super::error::SupabaseError;
+use super::session::SessionManager;
+use super::types::SupabaseAuthResponse;
+
+/// Which sessions `sign_out` revokes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignOutScope {
+    /// Only the session this app holds.
+    Local,
+    /// Every session of the user, on all devices.
+    Global,
+}
+
+impl SignOutScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Global => "global",
+        }
+    }
+}
 
+/// One per app, kept in Tauri's managed state, so every command shares the
+/// connection pool.
 pub struct SupabaseClient {
     url: String,
     anon_key: String,
     client: Client,
+    session: SessionManager,
 }
 
 #[allow(dead_code)]
@@ -17,15 +41,25 @@ impl SupabaseClient {
             url,
             anon_key,
             client: Client::new(),
+            session: SessionManager::default(),
         }
     }
 
+    pub fn session(&self) -> &SessionManager {
+        &self.session
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        let (url, anon_key) = get_supabase_config()?;
+        Ok(Self::new(url, anon_key))
+    }
+
     pub async fn sign_up(
         &self,
         email: &str,
         password: &str,
         username: &str,
-    ) -> Result<SupabaseAuthResponse, String> {
+    ) -> Result<SupabaseAuthResponse, SupabaseError> {
         let url = format!("{}/auth/v1/signup", self.url);
 
         let body = json!({
@@ -50,31 +84,19 @@ impl SupabaseClient {
             .json(&body)
             .send()
             .await
-            .map_err(|e| format!("Network error: {}", e))?;
+            .map_err(SupabaseError::network)?;
 
         println!("Response status: {}", response.status());
 
         if response.status().is_success() {
-            let response_text = response
-                .text()
-                .await
-                .map_err(|e| format!("Failed to read response: {}", e))?;
+            let response_text = response.text().await.map_err(SupabaseError::network)?;
 
             println!("Response body: {}", response_text);
 
             serde_json::from_str::<SupabaseAuthResponse>(&response_text)
-                .map_err(|e| format!("Failed to parse response: {}", e))
+                .map_err(SupabaseError::parse)
         } else {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-
-            if let Ok(error) = serde_json::from_str::<SupabaseError>(&error_text) {
-                Err(error.error_description.unwrap_or(error.error))
-            } else {
-                Err(error_text)
-            }
+            Err(SupabaseError::from_response(response).await)
         }
     }
 
@@ -82,7 +104,7 @@ impl SupabaseClient {
         &self,
         email: &str,
         password: &str,
-    ) -> Result<SupabaseAuthResponse, String> {
+    ) -> Result<SupabaseAuthResponse, SupabaseError> {
         let url = format!("{}/auth/v1/token?grant_type=password", self.url);
 
         let body = json!({
@@ -104,78 +126,63 @@ impl SupabaseClient {
             .json(&body)
             .send()
             .await
-            .map_err(|e| format!("Network error: {}", e))?;
+            .map_err(SupabaseError::network)?;
 
         println!("📥 Response status: {}", response.status());
 
         if response.status().is_success() {
-            let response_text = response
-                .text()
-                .await
-                .map_err(|e| format!("Failed to read response: {}", e))?;
+            let response_text = response.text().await.map_err(SupabaseError::network)?;
 
             println!("📋 Response body: {}", response_text);
 
             serde_json::from_str::<SupabaseAuthResponse>(&response_text)
-                .map_err(|e| format!("Failed to parse response: {}", e))
+                .map_err(SupabaseError::parse)
         } else {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-
-            if let Ok(error) = serde_json::from_str::<SupabaseError>(&error_text) {
-                Err(error.error_description.unwrap_or(error.error))
-            } else {
-                Err(error_text)
-            }
+            Err(SupabaseError::from_response(response).await)
         }
     }
 
-    pub async fn sign_in_with_google(&self) -> Result<String, String> {
+    pub async fn sign_in_with_google(&self) -> Result<String, SupabaseError> {
         let url = format!("{}/auth/v1/authorize?provider=google", self.url);
         Ok(url)
     }
 
-    pub async fn get_user_profile_with_role(&self, access_token: &str) -> Result<Value, String> {
+    pub async fn get_user_profile_with_role(&self) -> Result<Value, SupabaseError> {
         let url = format!("{}/auth/v1/user", self.url);
 
         let user_response = self
-            .client
-            .get(&url)
-            .header("apikey", &self.anon_key)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .send()
-            .await
-            .map_err(|e| format!("Network error getting user: {}", e))?;
+            .send_authorized(|token| {
+                self.client
+                    .get(&url)
+                    .header("apikey", &self.anon_key)
+                    .header("Authorization", format!("Bearer {}", token))
+            })
+            .await?;
 
         if !user_response.status().is_success() {
-            return Err("Failed to get user from token".to_string());
+            return Err(SupabaseError::from_response(user_response).await);
         }
 
-        let user_data: Value = user_response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse user: {}", e))?;
+        let user_data: Value = user_response.json().await.map_err(SupabaseError::parse)?;
 
         let user_id = user_data
             .get("id")
             .and_then(|id| id.as_str())
-            .ok_or("No user ID in response")?;
+            .ok_or_else(|| SupabaseError::parse("No user ID in response"))?;
 
         println!("User ID from token: {}", user_id);
 
         let profile_url = format!("{}/rest/v1/profiles?id=eq.{}&select=*", self.url, user_id);
 
         let profile_response = self
-            .client
-            .get(&profile_url)
-            .header("apikey", &self.anon_key)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-            .map_err(|e| format!("Network error getting profile: {}", e))?;
+            .send_authorized(|token| {
+                self.client
+                    .get(&profile_url)
+                    .header("apikey", &self.anon_key)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .header("Content-Type", "application/json")
+            })
+            .await?;
 
         println!("Profile response status: {}", profile_response.status());
 
@@ -183,31 +190,28 @@ impl SupabaseClient {
             let profile_text = profile_response
                 .text()
                 .await
-                .map_err(|e| format!("Failed to read profile response: {}", e))?;
+                .map_err(SupabaseError::network)?;
 
             println!("Profile data: {}", profile_text);
 
-            let profiles: Vec<Value> = serde_json::from_str(&profile_text)
-                .map_err(|e| format!("Failed to parse profile: {}", e))?;
+            let profiles: Vec<Value> =
+                serde_json::from_str(&profile_text).map_err(SupabaseError::parse)?;
 
             profiles
                 .first()
                 .cloned()
-                .ok_or("No profile found".to_string())
+                .ok_or_else(|| SupabaseError::not_found("No profile found"))
         } else {
-            let error_text = profile_response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            println!("Profile error: {}", error_text);
-            Err(error_text)
+            let error = SupabaseError::from_response(profile_response).await;
+            println!("Profile error: {:?}", error);
+            Err(error)
         }
     }
 
     pub async fn exchange_code_for_session(
         &self,
         code: &str,
-    ) -> Result<SupabaseAuthResponse, String> {
+    ) -> Result<SupabaseAuthResponse, SupabaseError> {
         let url = format!("{}/auth/v1/token?grant_type=authorization_code", self.url);
 
         let body = json!({
@@ -222,63 +226,72 @@ impl SupabaseClient {
             .json(&body)
             .send()
             .await
-            .map_err(|e| format!("Network error: {}", e))?;
+            .map_err(SupabaseError::network)?;
 
         if response.status().is_success() {
-            let response_text = response
-                .text()
-                .await
-                .map_err(|e| format!("Failed to read response: {}", e))?;
+            let response_text = response.text().await.map_err(SupabaseError::network)?;
 
             serde_json::from_str::<SupabaseAuthResponse>(&response_text)
-                .map_err(|e| format!("Failed to parse response: {}", e))
+                .map_err(SupabaseError::parse)
         } else {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
+            Err(SupabaseError::from_response(response).await)
+        }
+    }
 
-            if let Ok(error) = serde_json::from_str::<SupabaseError>(&error_text) {
-                Err(error.error_description.unwrap_or(error.error))
-            } else {
-                Err(error_text)
-            }
+    pub async fn get_user_profile(&self, user_id: &str) -> Result<Value, SupabaseError> {
+        let url = format!("{}/rest/v1/profiles?id=eq.{}&select=*", self.url, user_id);
+
+        let response = self
+            .send_authorized(|token| {
+                self.client
+                    .get(&url)
+                    .header("apikey", &self.anon_key)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .header("Content-Type", "application/json")
+            })
+            .await?;
+
+        if response.status().is_success() {
+            response.json::<Value>().await.map_err(SupabaseError::parse)
+        } else {
+            Err(SupabaseError::from_response(response).await)
         }
     }
 
-    pub async fn get_user_profile(
+    pub async fn refresh_session(
         &self,
-        user_id: &str,
-        access_token: &str,
-    ) -> Result<Value, String> {
-        let url = format!("{}/rest/v1/profiles?id=eq.{}&select=*", self.url, user_id);
+        refresh_token: &str,
+    ) -> Result<SupabaseAuthResponse, SupabaseError> {
+        let url = format!("{}/auth/v1/token?grant_type=refresh_token", self.url);
 
         let response = self
             .client
-            .get(&url)
+            .post(&url)
             .header("apikey", &self.anon_key)
-            .header("Authorization", format!("Bearer {}", access_token))
             .header("Content-Type", "application/json")
+            .json(&json!({ "refresh_token": refresh_token }))
             .send()
             .await
-            .map_err(|e| format!("Network error: {}", e))?;
+            .map_err(SupabaseError::network)?;
 
         if response.status().is_success() {
             response
-                .json::<Value>()
+                .json::<SupabaseAuthResponse>()
                 .await
-                .map_err(|e| format!("Failed to parse response: {}", e))
+                .map_err(SupabaseError::parse)
         } else {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            Err(error_text)
+            Err(SupabaseError::from_response(response).await)
         }
     }
 
-    pub async fn sign_out(&self, access_token: &str) -> Result<(), String> {
-        let url = format!("{}/auth/v1/logout", self.url);
+    /// Revokes the refresh tokens of `scope`; access tokens already handed
+    /// out stay valid until they expire.
+    pub async fn sign_out(
+        &self,
+        access_token: &str,
+        scope: SignOutScope,
+    ) -> Result<(), SupabaseError> {
+        let url = format!("{}/auth/v1/logout?scope={}", self.url, scope.as_str());
 
         let response = self
             .client
@@ -287,16 +300,12 @@ impl SupabaseClient {
             .header("Authorization", format!("Bearer {}", access_token))
             .send()
             .await
-            .map_err(|e| format!("Network error: {}", e))?;
+            .map_err(SupabaseError::network)?;
 
         if response.status().is_success() {
             Ok(())
         } else {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            Err(error_text)
+            Err(SupabaseError::from_response(response).await)
         }
     }
 
@@ -304,47 +313,58 @@ impl SupabaseClient {
         &self,
         method: reqwest::Method,
         endpoint: &str,
-        access_token: &str,
         body: Option<Value>,
-    ) -> Result<T, String> {
+    ) -> Result<T, SupabaseError> {
         let url = format!("{}/rest/v1/{}", self.url, endpoint);
 
-        let mut request = self
-            .client
-            .request(method, &url)
-            .header("apikey", &self.anon_key)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Content-Type", "application/json");
-
-        if body.is_some() {
-            request = request.header("Prefer", "return=representation");
-        }
-
-        if let Some(json_body) = body {
-            request = request.json(&json_body);
-        }
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| format!("Network error: {}", e))?;
+        let response = self
+            .send_authorized(|token| {
+                let mut request = self
+                    .client
+                    .request(method.clone(), &url)
+                    .header("apikey", &self.anon_key)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .header("Content-Type", "application/json");
+
+                if let Some(json_body) = &body {
+                    request = request
+                        .header("Prefer", "return=representation")
+                        .json(json_body);
+                }
+                request
+            })
+            .await?;
 
         if response.status().is_success() {
-            if response.status() == reqwest::StatusCode::NO_CONTENT {
-                serde_json::from_value(serde_json::Value::Null)
-                    .map_err(|e| format!("Failed to parse empty response: {}", e))
+            if response.status() == StatusCode::NO_CONTENT {
+                serde_json::from_value(serde_json::Value::Null).map_err(SupabaseError::parse)
             } else {
-                response
-                    .json()
-                    .await
-                    .map_err(|e| format!("Failed to parse response: {}", e))
+                response.json().await.map_err(SupabaseError::parse)
             }
         } else {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            Err(error_text)
+            Err(SupabaseError::from_response(response).await)
+        }
+    }
+
+    /// Sends the request with the user's access token, or the anon key when
+    /// nobody is signed in. A 401 means the token expired early or was
+    /// revoked: the session is refreshed and the request sent once more.
+    async fn send_authorized(
+        &self,
+        build: impl Fn(&str) -> RequestBuilder,
+    ) -> Result<Response, SupabaseError> {
+        let token = match self.session.access_token(self).await? {
+            Some(token) => token,
+            None => self.anon_key.clone(),
+        };
+        let response = build(&token).send().await.map_err(SupabaseError::network)?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        match self.session.refresh_after(self, &token).await? {
+            Some(token) => build(&token).send().await.map_err(SupabaseError::network),
+            None => Ok(response),
         }
     }
 }
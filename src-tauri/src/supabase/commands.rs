@@ -1,43 +1,49 @@
 use reqwest::Method;
 use serde_json::{json, Value};
+use tauri::State;
 
+use crate::solutions;
 use crate::supabase::types::UserProfile;
 
 use super::{
     client::SupabaseClient,
-    config::get_supabase_config,
+    error::{ErrorDetails, SupabaseError},
     types::{
-        Course, CourseRow, CreateCourseInput, CreateLessonInput, CreateModuleInput,
+        AttemptRecord, Course, CourseRow, CreateCourseInput, CreateLessonInput, CreateModuleInput,
         CreateProgressInput, Lesson, Module, ModuleRow, SearchResult, UserProgress,
     },
 };
 
-fn get_supabase_client() -> Result<SupabaseClient, String> {
-    let (url, anon_key) = get_supabase_config()?;
-    Ok(SupabaseClient::new(url, anon_key))
-}
-
 #[tauri::command]
-pub async fn get_all_courses(access_token: String) -> Result<Vec<Course>, String> {
-    let client = get_supabase_client()?;
+pub async fn get_all_courses(
+    client: State<'_, SupabaseClient>,
+) -> Result<Vec<Course>, SupabaseError> {
     client
         .rest_request(
             Method::GET,
             "courses?select=*,modules(*,lessons(*))&is_published=eq.true&order=order_index",
-            &access_token,
             None,
         )
         .await
 }
 
 #[tauri::command]
-pub async fn get_lesson_by_id(lesson_id: String, access_token: String) -> Result<Lesson, String> {
-    let client = get_supabase_client()?;
+pub async fn get_lesson_by_id(
+    client: State<'_, SupabaseClient>,
+    lesson_id: String,
+) -> Result<Lesson, SupabaseError> {
+    fetch_lesson(&client, lesson_id).await
+}
+
+/// `get_lesson_by_id` for callers that aren't commands themselves.
+pub async fn fetch_lesson(
+    client: &SupabaseClient,
+    lesson_id: String,
+) -> Result<Lesson, SupabaseError> {
     let lessons: Vec<Lesson> = client
         .rest_request(
             Method::GET,
             &format!("lessons?select=*&id=eq.{}", lesson_id),
-            &access_token,
             None,
         )
         .await?;
@@ -45,16 +51,14 @@ pub async fn get_lesson_by_id(lesson_id: String, access_token: String) -> Result
     lessons
         .into_iter()
         .next()
-        .ok_or_else(|| format!("Lesson {} not found", lesson_id))
+        .ok_or_else(|| SupabaseError::not_found(format!("Lesson {} not found", lesson_id)))
 }
 
 #[tauri::command]
 pub async fn create_course(
+    client: State<'_, SupabaseClient>,
     course: CreateCourseInput,
-    access_token: String,
-) -> Result<Course, String> {
-    let client = get_supabase_client()?;
-
+) -> Result<Course, SupabaseError> {
     let body = json!({
         "title": course.title,
         "description": course.description,
@@ -68,13 +72,13 @@ pub async fn create_course(
     });
 
     let course_rows: Vec<CourseRow> = client
-        .rest_request(Method::POST, "courses", &access_token, Some(body))
+        .rest_request(Method::POST, "courses", Some(body))
         .await?;
 
     let course_row = course_rows
         .into_iter()
         .next()
-        .ok_or_else(|| "No course returned".to_string())?;
+        .ok_or_else(|| SupabaseError::not_found("No course returned"))?;
 
     Ok(Course {
         id: course_row.id,
@@ -93,11 +97,9 @@ pub async fn create_course(
 
 #[tauri::command]
 pub async fn create_module(
+    client: State<'_, SupabaseClient>,
     module: CreateModuleInput,
-    access_token: String,
-) -> Result<Module, String> {
-    let client = get_supabase_client()?;
-
+) -> Result<Module, SupabaseError> {
     let body = json!({
         "course_id": module.course_id,
         "title": module.title,
@@ -107,13 +109,13 @@ pub async fn create_module(
     });
 
     let module_rows: Vec<ModuleRow> = client
-        .rest_request(Method::POST, "modules", &access_token, Some(body))
+        .rest_request(Method::POST, "modules", Some(body))
         .await?;
 
     let module_row = module_rows
         .into_iter()
         .next()
-        .ok_or_else(|| "No module returned".to_string())?;
+        .ok_or_else(|| SupabaseError::not_found("No module returned"))?;
 
     Ok(Module {
         id: module_row.id,
@@ -126,13 +128,16 @@ pub async fn create_module(
     })
 }
 
+/// Exercises are only saved when their `solution` passes their own test
+/// cases; see [`solutions::ensure_valid`].
 #[tauri::command]
 pub async fn create_lesson(
+    client: State<'_, SupabaseClient>,
     lesson: CreateLessonInput,
-    access_token: String,
-) -> Result<Lesson, String> {
-    let client = get_supabase_client()?;
-
+) -> Result<Lesson, SupabaseError> {
+    solutions::ensure_valid(&lesson.language, &lesson.lesson_type, &lesson.content)
+        .await
+        .map_err(SupabaseError::validation)?;
     let body = json!({
         "module_id": lesson.module_id,
         "title": lesson.title,
@@ -147,28 +152,49 @@ pub async fn create_lesson(
     });
 
     let lessons: Vec<Lesson> = client
-        .rest_request(Method::POST, "lessons", &access_token, Some(body))
+        .rest_request(Method::POST, "lessons", Some(body))
         .await?;
 
     lessons
         .into_iter()
         .next()
-        .ok_or_else(|| "No lesson returned".to_string())
+        .ok_or_else(|| SupabaseError::not_found("No lesson returned"))
 }
 
+/// Like [`create_lesson`], checks the solution when `updates` change the
+/// content; the language and type not in `updates` come from the lesson.
 #[tauri::command]
 pub async fn update_lesson(
+    client: State<'_, SupabaseClient>,
     lesson_id: String,
     updates: Value,
-    access_token: String,
-) -> Result<Lesson, String> {
-    let client = get_supabase_client()?;
+) -> Result<Lesson, SupabaseError> {
+    if let Some(content) = updates.get("content") {
+        let field = |name: &str| {
+            updates
+                .get(name)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let (language, lesson_type) = match (field("language"), field("lesson_type")) {
+            (Some(language), Some(lesson_type)) => (language, lesson_type),
+            (language, lesson_type) => {
+                let current = fetch_lesson(&client, lesson_id.clone()).await?;
+                (
+                    language.unwrap_or(current.language),
+                    lesson_type.unwrap_or(current.lesson_type),
+                )
+            }
+        };
+        solutions::ensure_valid(&language, &lesson_type, content)
+            .await
+            .map_err(SupabaseError::validation)?;
+    }
 
     let lessons: Vec<Lesson> = client
         .rest_request(
             Method::PATCH,
             &format!("lessons?id=eq.{}", lesson_id),
-            &access_token,
             Some(updates),
         )
         .await?;
@@ -176,17 +202,18 @@ pub async fn update_lesson(
     lessons
         .into_iter()
         .next()
-        .ok_or_else(|| "No lesson returned".to_string())
+        .ok_or_else(|| SupabaseError::not_found("No lesson returned"))
 }
 
 #[tauri::command]
-pub async fn delete_lesson(lesson_id: String, access_token: String) -> Result<(), String> {
-    let client = get_supabase_client()?;
+pub async fn delete_lesson(
+    client: State<'_, SupabaseClient>,
+    lesson_id: String,
+) -> Result<(), SupabaseError> {
     let _: Option<Value> = client
         .rest_request(
             Method::DELETE,
             &format!("lessons?id=eq.{}", lesson_id),
-            &access_token,
             None,
         )
         .await?;
@@ -194,13 +221,14 @@ pub async fn delete_lesson(lesson_id: String, access_token: String) -> Result<()
 }
 
 #[tauri::command]
-pub async fn delete_course(course_id: String, access_token: String) -> Result<(), String> {
-    let client = get_supabase_client()?;
+pub async fn delete_course(
+    client: State<'_, SupabaseClient>,
+    course_id: String,
+) -> Result<(), SupabaseError> {
     let _: Option<Value> = client
         .rest_request(
             Method::DELETE,
             &format!("courses?id=eq.{}", course_id),
-            &access_token,
             None,
         )
         .await?;
@@ -209,11 +237,9 @@ pub async fn delete_course(course_id: String, access_token: String) -> Result<()
 
 #[tauri::command]
 pub async fn search_lessons(
+    client: State<'_, SupabaseClient>,
     query: String,
-    access_token: String,
-) -> Result<Vec<SearchResult>, String> {
-    let client = get_supabase_client()?;
-
+) -> Result<Vec<SearchResult>, SupabaseError> {
     let encoded_query = urlencoding::encode(&query);
 
     let courses_endpoint = format!(
@@ -223,7 +249,7 @@ pub async fn search_lessons(
     );
 
     let courses: Vec<CourseRow> = client
-        .rest_request(Method::GET, &courses_endpoint, &access_token, None)
+        .rest_request(Method::GET, &courses_endpoint, None)
         .await
         .unwrap_or_default();
 
@@ -233,8 +259,8 @@ pub async fn search_lessons(
         encoded_query
     );
 
-    let lessons_response: Result<Vec<Value>, String> = client
-        .rest_request(Method::GET, &lessons_endpoint, &access_token, None)
+    let lessons_response: Result<Vec<Value>, SupabaseError> = client
+        .rest_request(Method::GET, &lessons_endpoint, None)
         .await;
 
     let mut results = Vec::new();
@@ -296,25 +322,19 @@ pub async fn search_lessons(
 
 #[tauri::command]
 pub async fn get_user_progress(
+    client: State<'_, SupabaseClient>,
     user_id: String,
-    access_token: String,
-) -> Result<Vec<UserProgress>, String> {
-    let client = get_supabase_client()?;
-
+) -> Result<Vec<UserProgress>, SupabaseError> {
     let endpoint = format!("user_progress?select=*&user_id=eq.{}", user_id);
 
-    client
-        .rest_request(Method::GET, &endpoint, &access_token, None)
-        .await
+    client.rest_request(Method::GET, &endpoint, None).await
 }
 
 #[tauri::command]
 pub async fn update_lesson_progress(
+    client: State<'_, SupabaseClient>,
     progress: CreateProgressInput,
-    access_token: String,
-) -> Result<UserProgress, String> {
-    let client = get_supabase_client()?;
-
+) -> Result<UserProgress, SupabaseError> {
     let existing: Vec<UserProgress> = client
         .rest_request(
             Method::GET,
@@ -322,7 +342,6 @@ pub async fn update_lesson_progress(
                 "user_progress?select=*&user_id=eq.{}&lesson_id=eq.{}",
                 progress.user_id, progress.lesson_id
             ),
-            &access_token,
             None,
         )
         .await
@@ -344,7 +363,6 @@ pub async fn update_lesson_progress(
                     "user_progress?id=eq.{}",
                     existing_progress.id.as_ref().unwrap()
                 ),
-                &access_token,
                 Some(body),
             )
             .await?;
@@ -352,7 +370,7 @@ pub async fn update_lesson_progress(
         updated
             .into_iter()
             .next()
-            .ok_or_else(|| "Failed to update progress".to_string())
+            .ok_or_else(|| SupabaseError::not_found("Failed to update progress"))
     } else {
         let body = json!({
             "user_id": progress.user_id,
@@ -365,29 +383,125 @@ pub async fn update_lesson_progress(
         });
 
         let created: Vec<UserProgress> = client
-            .rest_request(Method::POST, "user_progress", &access_token, Some(body))
+            .rest_request(Method::POST, "user_progress", Some(body))
             .await?;
 
         created
             .into_iter()
             .next()
-            .ok_or_else(|| "Failed to create progress".to_string())
+            .ok_or_else(|| SupabaseError::not_found("Failed to create progress"))
     }
 }
 
+/// How many failed attempts it takes before a lesson's hint is shown,
+/// unless the lesson sets `hintAfterAttempts`.
+const DEFAULT_HINT_AFTER_ATTEMPTS: i64 = 3;
+
+/// Reads and writes of the attempt counter before giving up on it while
+/// other submissions keep changing it.
+const ATTEMPT_RETRIES: usize = 5;
+
+/// Counts one more attempt by the signed-in user at the lesson and returns
+/// the lesson's hint once they have tried `content.hintAfterAttempts` times.
 #[tauri::command]
-pub async fn get_user_profile(user_id: String, access_token: String) -> Result<UserProfile, String> {
-    let client = get_supabase_client()?;
+pub async fn record_attempt(
+    client: State<'_, SupabaseClient>,
+    lesson_id: String,
+) -> Result<AttemptRecord, SupabaseError> {
+    let user_id = client
+        .session()
+        .user_id()
+        .await
+        .ok_or_else(|| SupabaseError::AuthExpired(ErrorDetails::new("Not signed in")))?;
 
+    let mut attempts = None;
+    for _ in 0..ATTEMPT_RETRIES {
+        let existing: Vec<UserProgress> = client
+            .rest_request(
+                Method::GET,
+                &format!(
+                    "user_progress?select=*&user_id=eq.{}&lesson_id=eq.{}",
+                    user_id, lesson_id
+                ),
+                None,
+            )
+            .await?;
+
+        let recorded: Vec<UserProgress> = match existing.first() {
+            Some(existing_progress) => {
+                let id = existing_progress
+                    .id
+                    .as_ref()
+                    .ok_or_else(|| SupabaseError::parse("Progress row without an id"))?;
+                // Matches nothing when another submission counted an
+                // attempt since the read; then it is read again.
+                client
+                    .rest_request(
+                        Method::PATCH,
+                        &format!(
+                            "user_progress?id=eq.{}&attempts=eq.{}",
+                            id, existing_progress.attempts
+                        ),
+                        Some(json!({ "attempts": existing_progress.attempts + 1 })),
+                    )
+                    .await?
+            }
+            None => {
+                let body = json!({
+                    "user_id": user_id,
+                    "lesson_id": lesson_id,
+                    "status": "in_progress",
+                    "attempts": 1,
+                });
+                match client
+                    .rest_request(Method::POST, "user_progress", Some(body))
+                    .await
+                {
+                    // Another submission created the row first.
+                    Err(SupabaseError::Conflict(_)) => Vec::new(),
+                    recorded => recorded?,
+                }
+            }
+        };
+        if let Some(progress) = recorded.first() {
+            attempts = Some(progress.attempts);
+            break;
+        }
+    }
+    let attempts = attempts.ok_or_else(|| {
+        SupabaseError::Conflict(ErrorDetails::new(
+            "Attempt not recorded, the progress kept changing",
+        ))
+    })?;
+
+    let lesson = fetch_lesson(&client, lesson_id).await?;
+    let hint_after = lesson
+        .content
+        .get("hintAfterAttempts")
+        .and_then(Value::as_i64)
+        .unwrap_or(DEFAULT_HINT_AFTER_ATTEMPTS);
+    let hint = lesson
+        .content
+        .get("hint")
+        .and_then(Value::as_str)
+        .filter(|_| i64::from(attempts) >= hint_after)
+        .map(str::to_string);
+
+    Ok(AttemptRecord { attempts, hint })
+}
+
+#[tauri::command]
+pub async fn get_user_profile(
+    client: State<'_, SupabaseClient>,
+    user_id: String,
+) -> Result<UserProfile, SupabaseError> {
     let endpoint = format!("profiles?select=*&id=eq.{}", user_id);
-    let profiles: Vec<UserProfile> = client
-        .rest_request(Method::GET, &endpoint, &access_token, None)
-        .await?;
+    let profiles: Vec<UserProfile> = client.rest_request(Method::GET, &endpoint, None).await?;
 
     let mut profile = profiles
         .into_iter()
         .next()
-        .ok_or_else(|| "Profile not found".to_string())?;
+        .ok_or_else(|| SupabaseError::not_found("Profile not found"))?;
 
     let completed_lessons: Vec<Value> = client
         .rest_request(
@@ -396,7 +510,6 @@ pub async fn get_user_profile(user_id: String, access_token: String) -> Result<U
                 "user_progress?select=lesson_id,lessons(xp_reward)&user_id=eq.{}&status=eq.completed",
                 user_id
             ),
-            &access_token,
             None,
         )
         .await
@@ -421,9 +534,10 @@ pub async fn get_user_profile(user_id: String, access_token: String) -> Result<U
 }
 
 #[tauri::command]
-pub async fn get_user_statistics(user_id: String, access_token: String) -> Result<Value, String> {
-    let client = get_supabase_client()?;
-
+pub async fn get_user_statistics(
+    client: State<'_, SupabaseClient>,
+    user_id: String,
+) -> Result<Value, SupabaseError> {
     let completed_lessons: Vec<UserProgress> = client
         .rest_request(
             Method::GET,
@@ -431,7 +545,6 @@ pub async fn get_user_statistics(user_id: String, access_token: String) -> Resul
                 "user_progress?select=*&user_id=eq.{}&status=eq.completed",
                 user_id
             ),
-            &access_token,
             None,
         )
         .await
@@ -454,38 +567,32 @@ pub async fn get_user_statistics(user_id: String, access_token: String) -> Resul
 
 #[tauri::command]
 pub async fn get_user_achievements(
+    client: State<'_, SupabaseClient>,
     user_id: String,
-    access_token: String,
-) -> Result<Vec<Value>, String> {
-    let client = get_supabase_client()?;
-
+) -> Result<Vec<Value>, SupabaseError> {
     let endpoint = format!(
         "user_achievements?select=*,achievements(*)&user_id=eq.{}",
         user_id
     );
 
-    client
-        .rest_request(Method::GET, &endpoint, &access_token, None)
-        .await
+    client.rest_request(Method::GET, &endpoint, None).await
 }
 
 #[tauri::command]
-pub async fn get_available_achievements(access_token: String) -> Result<Vec<Value>, String> {
-    let client = get_supabase_client()?;
-
+pub async fn get_available_achievements(
+    client: State<'_, SupabaseClient>,
+) -> Result<Vec<Value>, SupabaseError> {
     client
-        .rest_request(Method::GET, "achievements?select=*", &access_token, None)
+        .rest_request(Method::GET, "achievements?select=*", None)
         .await
 }
 
 #[tauri::command]
 pub async fn update_user_avatar(
+    client: State<'_, SupabaseClient>,
     user_id: String,
     avatar_url: String,
-    access_token: String,
-) -> Result<(), String> {
-    let client = get_supabase_client()?;
-
+) -> Result<(), SupabaseError> {
     let body = json!({
         "avatar_url": avatar_url,
     });
@@ -494,7 +601,6 @@ pub async fn update_user_avatar(
         .rest_request(
             Method::PATCH,
             &format!("profiles?id=eq.{}", user_id),
-            &access_token,
             Some(body),
         )
         .await?;
@@ -504,12 +610,10 @@ pub async fn update_user_avatar(
 
 #[tauri::command]
 pub async fn update_user_username(
+    client: State<'_, SupabaseClient>,
     user_id: String,
     username: String,
-    access_token: String,
-) -> Result<(), String> {
-    let client = get_supabase_client()?;
-
+) -> Result<(), SupabaseError> {
     let body = json!({
         "username": username,
     });
@@ -518,7 +622,6 @@ pub async fn update_user_username(
         .rest_request(
             Method::PATCH,
             &format!("profiles?id=eq.{}", user_id),
-            &access_token,
             Some(body),
         )
         .await?;
@@ -1,8 +1,14 @@
+/// Reads the project URL and anon key from the environment, falling back
+/// to the values `build.rs` baked in from `.env.production`.
 pub fn get_supabase_config() -> Result<(String, String), String> {
     let url = std::env::var("SUPABASE_URL")
-        .map_err(|_| "SUPABASE_URL not set in environment".to_string())?;
+        .ok()
+        .or(option_env!("SUPABASE_URL").map(String::from))
+        .ok_or_else(|| "SUPABASE_URL not set in environment".to_string())?;
     let anon_key = std::env::var("SUPABASE_ANON_KEY")
-        .map_err(|_| "SUPABASE_ANON_KEY not set in environment".to_string())?;
+        .ok()
+        .or(option_env!("SUPABASE_ANON_KEY").map(String::from))
+        .ok_or_else(|| "SUPABASE_ANON_KEY not set in environment".to_string())?;
 
     Ok((url, anon_key))
 }
@@ -0,0 +1,227 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use super::types::ErrorBody;
+
+/// Why a Supabase request failed, serialized with a `kind` tag so the
+/// frontend can tell the cases apart without reading messages.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SupabaseError {
+    /// No response: offline, DNS, TLS or a timeout.
+    Network {
+        message: String,
+    },
+    /// The access token is missing, invalid or expired; logging in again
+    /// helps.
+    AuthExpired(ErrorDetails),
+    /// Row-level security or the user's role refused the request.
+    Forbidden(ErrorDetails),
+    NotFound(ErrorDetails),
+    /// A unique or foreign-key constraint was violated.
+    Conflict(ErrorDetails),
+    /// The request itself was rejected: bad credentials, a value breaking
+    /// a column constraint, or an exercise whose solution fails its tests.
+    Validation(ErrorDetails),
+    /// Any other unsuccessful status.
+    Http {
+        status: u16,
+        #[serde(flatten)]
+        details: ErrorDetails,
+    },
+    /// The response could not be read or didn't have the expected shape.
+    Parse {
+        message: String,
+    },
+}
+
+/// The error as PostgREST or GoTrue described it. `code` is the Postgres
+/// SQLSTATE or PostgREST `PGRST…` code, or GoTrue's `error_code`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    pub message: String,
+    pub code: Option<String>,
+    pub hint: Option<String>,
+    pub details: Option<String>,
+}
+
+impl ErrorDetails {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Self::default()
+        }
+    }
+}
+
+impl SupabaseError {
+    pub fn network(error: reqwest::Error) -> Self {
+        Self::Network {
+            message: error.to_string(),
+        }
+    }
+
+    pub fn parse(error: impl fmt::Display) -> Self {
+        Self::Parse {
+            message: error.to_string(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(ErrorDetails::new(message))
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation(ErrorDetails::new(message))
+    }
+
+    /// Reads the body of an unsuccessful response.
+    pub async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Self::from_status(status, &body)
+    }
+
+    /// Classifies by the Postgres or PostgREST code first, which is more
+    /// specific than the status: an RLS violation is a 401 for anonymous
+    /// users but still a `Forbidden`.
+    pub fn from_status(status: StatusCode, body: &str) -> Self {
+        let details = match serde_json::from_str::<ErrorBody>(body) {
+            Ok(body) => body.into_details(),
+            Err(_) if body.trim().is_empty() => ErrorDetails::new(
+                status
+                    .canonical_reason()
+                    .unwrap_or("Unknown error")
+                    .to_string(),
+            ),
+            Err(_) => ErrorDetails::new(body.trim()),
+        };
+
+        match details.code.as_deref() {
+            Some("42501") => return Self::Forbidden(details),
+            Some("PGRST301" | "PGRST302" | "bad_jwt" | "session_expired" | "session_not_found") => {
+                return Self::AuthExpired(details)
+            }
+            Some("PGRST116") => return Self::NotFound(details),
+            Some("23505" | "23503" | "user_already_exists" | "email_exists") => {
+                return Self::Conflict(details)
+            }
+            Some(code) if code.starts_with("22") || code.starts_with("23") => {
+                return Self::Validation(details)
+            }
+            _ => {}
+        }
+
+        match status {
+            StatusCode::UNAUTHORIZED => Self::AuthExpired(details),
+            StatusCode::FORBIDDEN => Self::Forbidden(details),
+            StatusCode::NOT_FOUND => Self::NotFound(details),
+            StatusCode::CONFLICT => Self::Conflict(details),
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Self::Validation(details),
+            _ => Self::Http {
+                status: status.as_u16(),
+                details,
+            },
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Network { message } | Self::Parse { message } => message,
+            Self::AuthExpired(details)
+            | Self::Forbidden(details)
+            | Self::NotFound(details)
+            | Self::Conflict(details)
+            | Self::Validation(details)
+            | Self::Http { details, .. } => &details.message,
+        }
+    }
+}
+
+impl fmt::Display for SupabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network { message } => write!(f, "Network error: {}", message),
+            Self::Parse { message } => write!(f, "Failed to parse response: {}", message),
+            Self::Http { status, details } => write!(f, "HTTP {}: {}", status, details.message),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl std::error::Error for SupabaseError {}
+
+/// Commands outside the Supabase layer still report errors as strings.
+impl From<SupabaseError> for String {
+    fn from(error: SupabaseError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_postgrest_and_auth_errors() {
+        let rls = r#"{"code":"42501","details":null,"hint":null,"message":"new row violates row-level security policy for table \"lessons\""}"#;
+        assert!(matches!(
+            SupabaseError::from_status(StatusCode::UNAUTHORIZED, rls),
+            SupabaseError::Forbidden(_)
+        ));
+
+        let unique = r#"{"code":"23505","details":"Key (title)=(Python) already exists.","hint":null,"message":"duplicate key value violates unique constraint \"courses_title_key\""}"#;
+        match SupabaseError::from_status(StatusCode::CONFLICT, unique) {
+            SupabaseError::Conflict(details) => {
+                assert_eq!(details.code.as_deref(), Some("23505"));
+                assert_eq!(
+                    details.details.as_deref(),
+                    Some("Key (title)=(Python) already exists.")
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+
+        let expired = r#"{"code":"PGRST301","details":null,"hint":null,"message":"JWT expired"}"#;
+        assert!(matches!(
+            SupabaseError::from_status(StatusCode::UNAUTHORIZED, expired),
+            SupabaseError::AuthExpired(_)
+        ));
+
+        let credentials =
+            r#"{"error":"invalid_grant","error_description":"Invalid login credentials"}"#;
+        let error = SupabaseError::from_status(StatusCode::BAD_REQUEST, credentials);
+        assert_eq!(error.message(), "Invalid login credentials");
+        assert!(matches!(error, SupabaseError::Validation(_)));
+
+        let gotrue =
+            r#"{"code":422,"error_code":"user_already_exists","msg":"User already registered"}"#;
+        let error = SupabaseError::from_status(StatusCode::UNPROCESSABLE_ENTITY, gotrue);
+        assert_eq!(error.message(), "User already registered");
+        assert!(matches!(error, SupabaseError::Conflict(_)));
+
+        let error = SupabaseError::from_status(StatusCode::BAD_GATEWAY, "");
+        assert_eq!(error.to_string(), "HTTP 502: Bad Gateway");
+    }
+
+    #[test]
+    fn serializes_with_a_kind_tag() {
+        let error = SupabaseError::Http {
+            status: 500,
+            details: ErrorDetails::new("boom"),
+        };
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "http",
+                "status": 500,
+                "message": "boom",
+                "code": null,
+                "hint": null,
+                "details": null,
+            })
+        );
+    }
+}
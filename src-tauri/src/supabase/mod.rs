@@ -1,8 +1,11 @@
 pub mod client;
 pub mod commands;
 pub mod config;
+pub mod error;
+pub mod session;
+pub mod store;
 pub mod types;
 
-pub use client::SupabaseClient;
+pub use client::{SignOutScope, SupabaseClient};
 pub use commands::*;
-pub use config::get_supabase_config;
+pub use error::SupabaseError;
@@ -0,0 +1,360 @@
+//! The signed-in user's session. The backend keeps the tokens: commands
+//! don't take an access token any more, the client attaches it, refreshes
+//! it shortly before it expires and once more when a request is rejected
+//! with 401.
+
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::{watch, Mutex};
+
+use super::client::{SignOutScope, SupabaseClient};
+use super::error::{ErrorDetails, SupabaseError};
+use super::store::SessionStore;
+use super::types::SupabaseAuthResponse;
+
+/// Refresh this many seconds before the access token expires.
+const REFRESH_MARGIN: i64 = 60;
+/// GoTrue's default lifetime, for responses that don't state one.
+const DEFAULT_LIFETIME: i64 = 3600;
+/// Wait before trying again after a refresh failed for lack of network.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub user_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Unix time in seconds.
+    pub expires_at: i64,
+}
+
+impl Session {
+    /// `None` when the response carries no tokens, e.g. a sign-up waiting
+    /// for email confirmation.
+    pub fn from_auth(auth: &SupabaseAuthResponse) -> Option<Self> {
+        Some(Self {
+            user_id: auth.user.id.clone(),
+            access_token: auth.access_token.clone()?,
+            refresh_token: auth.refresh_token.clone()?,
+            expires_at: auth
+                .expires_at
+                .unwrap_or_else(|| now() + auth.expires_in.unwrap_or(DEFAULT_LIFETIME)),
+        })
+    }
+
+    fn needs_refresh(&self) -> bool {
+        now() >= self.expires_at - REFRESH_MARGIN
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// Payload of the `session-changed` event. The frontend only learns who is
+/// signed in, never the tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionChanged {
+    user_id: Option<String>,
+    expires_at: Option<i64>,
+}
+
+impl From<Option<&Session>> for SessionChanged {
+    fn from(session: Option<&Session>) -> Self {
+        Self {
+            user_id: session.map(|session| session.user_id.clone()),
+            expires_at: session.map(|session| session.expires_at),
+        }
+    }
+}
+
+pub struct SessionManager {
+    /// Held across a refresh, so requests that find the token expiring
+    /// wait for one refresh instead of each starting their own.
+    current: Mutex<Option<Session>>,
+    changes: watch::Sender<Option<Session>>,
+    /// Set at startup once the app data directory is known.
+    store: OnceLock<SessionStore>,
+}
+
+impl Default for SessionManager {
+    fn default() -> Self {
+        Self {
+            current: Mutex::new(None),
+            changes: watch::channel(None).0,
+            store: OnceLock::new(),
+        }
+    }
+}
+
+impl SessionManager {
+    pub fn attach_store(&self, store: SessionStore) {
+        let _ = self.store.set(store);
+    }
+
+    pub async fn set(&self, session: Option<Session>) {
+        let mut current = self.current.lock().await;
+        *current = session;
+        self.publish(&current);
+    }
+
+    /// Saves the session, or removes the saved one when it ended, and
+    /// tells the watchers. Runs under the lock, so saves don't overtake
+    /// each other.
+    fn publish(&self, session: &Option<Session>) {
+        if let Some(store) = self.store.get() {
+            let saved = match session {
+                Some(session) => store.save(session),
+                None => {
+                    store.clear();
+                    Ok(())
+                }
+            };
+            if let Err(e) = saved {
+                println!("Session not persisted: {}", e);
+            }
+        }
+        self.changes.send_replace(session.clone());
+    }
+
+    /// Signs the user back in with the session saved by the previous run,
+    /// refreshing it straight away: that checks the refresh token is still
+    /// valid and starts with a fresh access token. Offline, the saved
+    /// session is kept and refreshed once the network is back.
+    pub async fn restore(&self, client: &SupabaseClient) -> Option<Session> {
+        let mut current = self.current.lock().await;
+        if current.is_some() {
+            return current.clone();
+        }
+        let store = self.store.get()?;
+        let stored = match store.load() {
+            Ok(stored) => stored?,
+            Err(e) => {
+                println!("Stored session discarded: {}", e);
+                store.clear();
+                return None;
+            }
+        };
+
+        println!("Restoring session for user: {}", stored.user_id);
+        *current = Some(stored);
+        match self.refresh(client, &mut current).await {
+            Ok(_) => {}
+            Err(e @ (SupabaseError::Network { .. } | SupabaseError::Http { .. })) => {
+                println!("Session restored without refresh: {}", e);
+                self.changes.send_replace(current.clone());
+            }
+            // The refresh token was refused and the session already cleared.
+            Err(_) => {}
+        }
+        current.clone()
+    }
+
+    /// Revokes the session with Supabase and forgets it here, saved copy
+    /// included. It is forgotten even when revoking fails, e.g. offline:
+    /// the user is signed out of this app either way.
+    pub async fn sign_out(
+        &self,
+        client: &SupabaseClient,
+        scope: SignOutScope,
+    ) -> Result<(), SupabaseError> {
+        let mut current = self.current.lock().await;
+        let access_token = match current.as_ref() {
+            None => return Ok(()),
+            Some(session) if !session.needs_refresh() => Ok(session.access_token.clone()),
+            Some(_) => self.refresh(client, &mut current).await,
+        };
+        let revoked = match access_token {
+            Ok(access_token) => client.sign_out(&access_token, scope).await,
+            Err(e) => Err(e),
+        };
+
+        if let Some(session) = current.take() {
+            println!("Signed out user: {}", session.user_id);
+        }
+        self.publish(&current);
+        match revoked {
+            // Supabase has already let the session go.
+            Err(SupabaseError::AuthExpired(_)) => Ok(()),
+            revoked => revoked,
+        }
+    }
+
+    /// Who is signed in, if anyone.
+    pub async fn user_id(&self) -> Option<String> {
+        self.current
+            .lock()
+            .await
+            .as_ref()
+            .map(|session| session.user_id.clone())
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Option<Session>> {
+        self.changes.subscribe()
+    }
+
+    /// The current access token, refreshed first if it is about to expire;
+    /// `None` when nobody is signed in.
+    pub async fn access_token(
+        &self,
+        client: &SupabaseClient,
+    ) -> Result<Option<String>, SupabaseError> {
+        let mut current = self.current.lock().await;
+        match current.as_ref() {
+            None => Ok(None),
+            Some(session) if !session.needs_refresh() => Ok(Some(session.access_token.clone())),
+            Some(_) => self.refresh(client, &mut current).await.map(Some),
+        }
+    }
+
+    /// A token to retry with after `stale` was rejected. Another request
+    /// may have refreshed it meanwhile; then its token is used as is.
+    pub async fn refresh_after(
+        &self,
+        client: &SupabaseClient,
+        stale: &str,
+    ) -> Result<Option<String>, SupabaseError> {
+        let mut current = self.current.lock().await;
+        match current.as_ref() {
+            None => Ok(None),
+            Some(session) if session.access_token != stale => {
+                Ok(Some(session.access_token.clone()))
+            }
+            Some(_) => self.refresh(client, &mut current).await.map(Some),
+        }
+    }
+
+    /// Trades the refresh token for a new session. When Supabase refuses
+    /// the refresh token the user is signed out; network trouble keeps the
+    /// session for a later try.
+    async fn refresh(
+        &self,
+        client: &SupabaseClient,
+        current: &mut Option<Session>,
+    ) -> Result<String, SupabaseError> {
+        let Some(refresh_token) = current
+            .as_ref()
+            .map(|session| session.refresh_token.clone())
+        else {
+            return Err(SupabaseError::AuthExpired(ErrorDetails::new(
+                "Not signed in",
+            )));
+        };
+
+        let refreshed = match client.refresh_session(&refresh_token).await {
+            Ok(auth) => Session::from_auth(&auth)
+                .ok_or_else(|| SupabaseError::parse("Refresh returned no tokens")),
+            Err(e) => Err(e),
+        };
+        match refreshed {
+            Ok(session) => {
+                println!("Session refreshed for user: {}", session.user_id);
+                let access_token = session.access_token.clone();
+                *current = Some(session);
+                self.publish(current);
+                Ok(access_token)
+            }
+            Err(e @ (SupabaseError::Network { .. } | SupabaseError::Http { .. })) => Err(e),
+            Err(e) => {
+                println!("Session ended, refresh refused: {}", e);
+                *current = None;
+                self.publish(current);
+                Err(SupabaseError::AuthExpired(ErrorDetails::new(e.message())))
+            }
+        }
+    }
+}
+
+/// Restores the session saved by the previous run; the frontend calls it
+/// on startup and learns who, if anyone, is signed in.
+#[tauri::command]
+pub async fn restore_session(
+    client: State<'_, SupabaseClient>,
+) -> Result<SessionChanged, SupabaseError> {
+    let session = client.session().restore(&client).await;
+    Ok(SessionChanged::from(session.as_ref()))
+}
+
+/// Emits `session-changed` whenever the session changes, and refreshes it
+/// ahead of expiry so it stays valid while the app sits idle.
+pub fn watch(app: AppHandle) {
+    let mut changes = app.state::<SupabaseClient>().session().subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let expires_at = changes
+                .borrow_and_update()
+                .as_ref()
+                .map(|session| session.expires_at);
+            let refresh_due = async {
+                match expires_at {
+                    Some(expires_at) => {
+                        let wait = (expires_at - REFRESH_MARGIN - now()).max(0) as u64;
+                        tokio::time::sleep(Duration::from_secs(wait)).await
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                changed = changes.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    let event = SessionChanged::from(changes.borrow().as_ref());
+                    if let Err(e) = app.emit("session-changed", event) {
+                        println!("Failed to emit session change: {}", e);
+                    }
+                }
+                _ = refresh_due => {
+                    let client = app.state::<SupabaseClient>();
+                    if let Err(e) = client.session().access_token(&client).await {
+                        println!("Session refresh failed: {}", e);
+                        tokio::time::sleep(RETRY_DELAY).await;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::supabase::types::SupabaseUser;
+
+    fn auth(expires_in: Option<i64>, expires_at: Option<i64>) -> SupabaseAuthResponse {
+        SupabaseAuthResponse {
+            access_token: Some("access".to_string()),
+            refresh_token: Some("refresh".to_string()),
+            expires_in,
+            expires_at,
+            user: SupabaseUser {
+                id: "user-1".to_string(),
+                confirmation_sent_at: None,
+            },
+        }
+    }
+
+    #[test]
+    fn session_expiry_comes_from_the_auth_response() {
+        let session = Session::from_auth(&auth(None, Some(1_700_000_000))).unwrap();
+        assert_eq!(session.expires_at, 1_700_000_000);
+        assert!(session.needs_refresh());
+
+        let session = Session::from_auth(&auth(Some(3600), None)).unwrap();
+        assert!((session.expires_at - now() - 3600).abs() <= 1);
+        assert!(!session.needs_refresh());
+
+        let session = Session::from_auth(&auth(Some(30), None)).unwrap();
+        assert!(session.needs_refresh(), "refreshed within the margin");
+
+        let mut unconfirmed = auth(Some(3600), None);
+        unconfirmed.access_token = None;
+        assert_eq!(Session::from_auth(&unconfirmed), None);
+    }
+}
@@ -0,0 +1,264 @@
+//! Keeps the session between app runs, encrypted with AES-256-GCM. The
+//! key is derived from a secret kept apart from the app data dir: in the
+//! keychain on macOS and Windows, elsewhere in a file in the config dir
+//! combined with the machine id. A copy of the data dir alone doesn't
+//! reveal the session; whoever can read the user's keychain or files on
+//! this machine can, as they could the user's other secrets.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use sha2::{Digest, Sha256};
+
+use super::session::Session;
+
+const NONCE_LEN: usize = 12;
+/// Bumping this makes every stored session unreadable, i.e. signs users
+/// out once.
+const KEY_CONTEXT: &[u8] = b"session-store v1";
+
+/// Where the secret the encryption key is derived from comes from.
+pub trait KeyProvider: Send + Sync {
+    fn secret(&self) -> Result<Vec<u8>, String>;
+}
+
+/// The keychain where the platform has one the app can use without
+/// asking, a key file otherwise.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+pub fn platform_keys(identifier: &str, _key_file: PathBuf) -> impl KeyProvider {
+    KeychainKeyProvider::new(identifier)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn platform_keys(_identifier: &str, key_file: PathBuf) -> impl KeyProvider {
+    FileKeyProvider::new(key_file)
+}
+
+/// A random secret in the macOS Keychain or the Windows Credential
+/// Manager, created on first use.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+pub struct KeychainKeyProvider {
+    service: String,
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+impl KeychainKeyProvider {
+    const ACCOUNT: &'static str = "session-key";
+
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+impl KeyProvider for KeychainKeyProvider {
+    fn secret(&self) -> Result<Vec<u8>, String> {
+        let entry = keyring::Entry::new(&self.service, Self::ACCOUNT)
+            .map_err(|e| format!("Keychain unavailable: {}", e))?;
+        match entry.get_secret() {
+            Ok(secret) => Ok(secret),
+            Err(keyring::Error::NoEntry) => {
+                let secret = Aes256Gcm::generate_key(&mut OsRng).to_vec();
+                entry
+                    .set_secret(&secret)
+                    .map_err(|e| format!("Cannot store the session key: {}", e))?;
+                Ok(secret)
+            }
+            Err(e) => Err(format!("Cannot read the session key: {}", e)),
+        }
+    }
+}
+
+/// A random secret in a file only the user can read, created on first
+/// use, combined with the machine id where the system has one.
+pub struct FileKeyProvider {
+    path: PathBuf,
+}
+
+impl FileKeyProvider {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn create(&self) -> Result<Vec<u8>, String> {
+        let secret = Aes256Gcm::generate_key(&mut OsRng).to_vec();
+        write_private(&self.path, &secret)
+            .map_err(|e| format!("Cannot create {}: {}", self.path.display(), e))?;
+        Ok(secret)
+    }
+}
+
+impl KeyProvider for FileKeyProvider {
+    /// Only a missing or empty file gets a new secret; one that can't be
+    /// read is left alone, so the saved session survives the error.
+    fn secret(&self) -> Result<Vec<u8>, String> {
+        let mut secret = match fs::read(&self.path) {
+            Ok(secret) if !secret.is_empty() => secret,
+            Ok(_) => self.create()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => self.create()?,
+            Err(e) => return Err(format!("Cannot read {}: {}", self.path.display(), e)),
+        };
+        secret.extend(machine_id());
+        Ok(secret)
+    }
+}
+
+fn machine_id() -> Vec<u8> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| fs::read(path).ok())
+        .unwrap_or_default()
+}
+
+pub struct SessionStore {
+    file: PathBuf,
+    keys: Box<dyn KeyProvider>,
+    /// Derived once, so the keychain is only asked on first use.
+    key: OnceLock<Key<Aes256Gcm>>,
+}
+
+impl SessionStore {
+    pub fn new(file: PathBuf, keys: impl KeyProvider + 'static) -> Self {
+        Self {
+            file,
+            keys: Box::new(keys),
+            key: OnceLock::new(),
+        }
+    }
+
+    fn cipher(&self) -> Result<Aes256Gcm, String> {
+        if let Some(key) = self.key.get() {
+            return Ok(Aes256Gcm::new(key));
+        }
+        let digest = Sha256::new()
+            .chain_update(KEY_CONTEXT)
+            .chain_update(self.keys.secret()?)
+            .finalize();
+        let key = self
+            .key
+            .get_or_init(|| *Key::<Aes256Gcm>::from_slice(&digest));
+        Ok(Aes256Gcm::new(key))
+    }
+
+    /// Writes the nonce followed by the encrypted session.
+    pub fn save(&self, session: &Session) -> Result<(), String> {
+        let json = serde_json::to_vec(session).map_err(|e| e.to_string())?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = self
+            .cipher()?
+            .encrypt(&nonce, json.as_slice())
+            .map_err(|_| "Session encryption failed".to_string())?;
+
+        let mut contents = nonce.to_vec();
+        contents.extend(sealed);
+        write_private(&self.file, &contents)
+            .map_err(|e| format!("Cannot save session to {}: {}", self.file.display(), e))
+    }
+
+    /// `Ok(None)` when nothing is stored; an error when the file was
+    /// written with another key or has been tampered with.
+    pub fn load(&self) -> Result<Option<Session>, String> {
+        let contents = match fs::read(&self.file) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        if contents.len() <= NONCE_LEN {
+            return Err("Stored session is truncated".to_string());
+        }
+
+        let (nonce, sealed) = contents.split_at(NONCE_LEN);
+        let json = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| "Stored session cannot be decrypted".to_string())?;
+        serde_json::from_slice(&json)
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn clear(&self) {
+        if let Err(e) = fs::remove_file(&self.file) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                println!("Stored session not removed: {}", e);
+            }
+        }
+    }
+}
+
+/// Replaces `path` through a temporary file readable only by the user.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".tmp");
+    let partial = PathBuf::from(partial);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(&partial)?, contents)?;
+    fs::rename(&partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("session-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn session() -> Session {
+        Session {
+            user_id: "user-1".to_string(),
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            expires_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn round_trips_only_with_the_same_key() {
+        let dir = temp_dir("keys");
+        let store = |key: &str| {
+            SessionStore::new(dir.join("session.bin"), FileKeyProvider::new(dir.join(key)))
+        };
+
+        assert_eq!(store("a.key").load(), Ok(None));
+        store("a.key").save(&session()).unwrap();
+        assert_eq!(store("a.key").load(), Ok(Some(session())));
+
+        let on_disk = fs::read(dir.join("session.bin")).unwrap();
+        assert!(!String::from_utf8_lossy(&on_disk).contains("refresh"));
+        assert!(store("b.key").load().is_err());
+
+        store("a.key").clear();
+        assert_eq!(store("a.key").load(), Ok(None));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unreadable_key_file_is_not_replaced() {
+        let dir = temp_dir("unreadable");
+        fs::create_dir_all(dir.join("session.key")).unwrap();
+
+        assert!(FileKeyProvider::new(dir.join("session.key"))
+            .secret()
+            .is_err());
+        assert!(dir.join("session.key").is_dir());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
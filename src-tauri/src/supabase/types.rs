@@ -1,10 +1,16 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::error::ErrorDetails;
+
 #[derive(Debug, Deserialize)]
 pub struct SupabaseAuthResponse {
     pub access_token: Option<String>,
     pub refresh_token: Option<String>,
+    /// Seconds the access token stays valid.
+    pub expires_in: Option<i64>,
+    /// Unix time the access token expires at.
+    pub expires_at: Option<i64>,
     pub user: SupabaseUser,
 }
 
@@ -14,10 +20,39 @@ pub struct SupabaseUser {
     pub confirmation_sent_at: Option<String>,
 }
 
+/// Error body of GoTrue (`error` and `error_description`, or `msg` and
+/// `error_code` in newer versions) and of PostgREST (`code`, `message`,
+/// `details`, `hint`).
 #[derive(Debug, Deserialize)]
-pub struct SupabaseError {
-    pub error: String,
+pub struct ErrorBody {
+    pub error: Option<String>,
     pub error_description: Option<String>,
+    pub msg: Option<String>,
+    pub message: Option<String>,
+    /// A string in PostgREST, the HTTP status in GoTrue.
+    pub code: Option<Value>,
+    pub error_code: Option<String>,
+    pub details: Option<String>,
+    pub hint: Option<String>,
+}
+
+impl ErrorBody {
+    pub fn into_details(self) -> ErrorDetails {
+        let code = self
+            .error_code
+            .or_else(|| self.code.as_ref().and_then(Value::as_str).map(String::from));
+        ErrorDetails {
+            message: self
+                .error_description
+                .or(self.msg)
+                .or(self.message)
+                .or(self.error)
+                .unwrap_or_else(|| "Unknown error".to_string()),
+            code,
+            hint: self.hint,
+            details: self.details,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -171,6 +206,14 @@ pub struct CreateProgressInput {
     pub time_spent_seconds: Option<i32>,
 }
 
+/// A failed attempt at a lesson, as recorded by `record_attempt`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    pub attempts: i32,
+    /// The lesson's hint, once the learner has failed often enough to see it.
+    pub hint: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserProfile {
     pub id: String,
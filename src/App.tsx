@@ -13,6 +13,7 @@ import { useAuth } from './hooks/useAuth'
 import { invoke } from '@tauri-apps/api/core'
 import './styles/App.css'
 import ThemeToggle from './components/ThemeToggle'
+import { describeError } from './services/errors'
 
 function AppContent() {
   const { isAuthenticated, isAdmin, refreshAdmin, login, logout } = useAuth()
@@ -37,20 +38,13 @@ function AppContent() {
         success: boolean
         message: string
         user_id?: string
-        access_token?: string
-        refresh_token?: string
+        signed_in: boolean
       }>('login_user', {
         email: import.meta.env.VITE_DEV_LOGIN,
         password: import.meta.env.VITE_DEV_PASS,
       })
 
       if (response.success) {
-        if (response.access_token) {
-          localStorage.setItem('access_token', response.access_token)
-        }
-        if (response.refresh_token) {
-          localStorage.setItem('refresh_token', response.refresh_token)
-        }
         if (response.user_id) {
           localStorage.setItem('user_id', response.user_id)
         }
@@ -64,7 +58,7 @@ function AppContent() {
       }
     } catch (error) {
       setToast({
-        message: 'Dev login error: ' + error,
+        message: 'Dev login error: ' + describeError(error),
         type: 'error',
       })
     }
@@ -141,12 +135,12 @@ function AppContent() {
                 <div className="fixed top-4 right-4 z-50">
                   <div className="flex items-center gap-4">
                     <ThemeToggle />
-                    <UserProfileDropdown />
+                    <UserProfileDropdown onLogoutEverywhere={() => logout(true)} />
                   </div>
                 </div>
 
                 <div className="fixed bottom-4 left-4 z-50 flex flex-col gap-2">
-                  <Button onClick={logout} variant="danger" size="sm">
+                  <Button onClick={() => logout()} variant="danger" size="sm">
                     Wyloguj
                   </Button>
                   <Button onClick={() => navigate('/playground')} variant="blue" size="sm">
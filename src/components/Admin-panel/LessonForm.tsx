@@ -1,7 +1,8 @@
 import { useState } from 'react'
 import { Course, Module, QuizOption } from '../../types/lesson'
+import { formatService } from '../../services/FormatService'
 
-type LessonLanguage = 'python' | 'javascript' | 'html' | 'css' | 'typescript'
+type LessonLanguage = 'python' | 'javascript' | 'html' | 'css' | 'typescript' | 'rust' | 'c' | 'go'
 type LessonType = 'exercise' | 'theory' | 'quiz' | 'project'
 
 interface NewLessonData {
@@ -55,6 +56,34 @@ export default function LessonForm({
   onChangeModule,
 }: LessonFormProps) {
   const [newLesson, setNewLesson] = useState<NewLessonData>(initialLessonState)
+  const [formatError, setFormatError] = useState<string | null>(null)
+
+  // Keeps starter code and solutions formatted the same way across lessons.
+  const handleFormat = async (field: 'starterCode' | 'solution') => {
+    setFormatError(null)
+    try {
+      const result = await formatService.formatCode(newLesson.language, newLesson[field])
+      const formatted = result.formatted
+      if (formatted === null) {
+        setFormatError(formatService.describeErrors(result))
+      } else {
+        setNewLesson((lesson) => ({ ...lesson, [field]: formatted }))
+      }
+    } catch (error) {
+      setFormatError(String(error))
+    }
+  }
+
+  const formatButton = (field: 'starterCode' | 'solution') =>
+    formatService.canFormat(newLesson.language) && (
+      <button
+        type="button"
+        onClick={() => handleFormat(field)}
+        className="ml-2 text-xs text-purple-600 hover:text-purple-800 underline"
+      >
+        Formatuj
+      </button>
+    )
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault()
@@ -152,6 +181,9 @@ export default function LessonForm({
               <option value="html">HTML</option>
               <option value="css">CSS</option>
               <option value="typescript">TypeScript</option>
+              <option value="rust">Rust</option>
+              <option value="c">C</option>
+              <option value="go">Go</option>
             </select>
           </div>
         </div>
@@ -235,6 +267,7 @@ export default function LessonForm({
             <div>
               <label className="block text-sm font-medium text-slate-700 mb-2">
                 Kod startowy
+                {formatButton('starterCode')}
               </label>
               <textarea
                 value={newLesson.starterCode}
@@ -250,6 +283,7 @@ export default function LessonForm({
             <div>
               <label className="block text-sm font-medium text-slate-700 mb-2">
                 Rozwiązanie *
+                {formatButton('solution')}
               </label>
               <textarea
                 value={newLesson.solution}
@@ -261,6 +295,11 @@ export default function LessonForm({
                 className="w-full px-4 py-2 rounded-lg border border-slate-300 focus:ring-2 focus:ring-purple-500 outline-none font-mono text-sm resize-none"
                 placeholder="print('Hello World')"
               />
+              {formatError && (
+                <p className="mt-2 text-sm text-red-600 font-mono whitespace-pre-wrap">
+                  {formatError}
+                </p>
+              )}
             </div>
 
             <div>
@@ -9,6 +9,7 @@ import LessonList from './Admin-panel/LessonList'
 import CourseForm, { NewCourseData } from './Admin-panel/CourseForm'
 import ModuleForm, { NewModuleData } from './Admin-panel/ModuleForm'
 import LessonForm, { NewLessonData } from './Admin-panel/LessonForm'
+import { describeError } from '../services/errors'
 
 interface AdminPanelProps {
   onBack: () => void;
@@ -54,7 +55,7 @@ export default function AdminPanel({ onBack }: AdminPanelProps) {
       setSelectedCourse(created)
     } catch (error) {
       console.error('Error creating course:', error)
-      alert('Error: ' + error)
+      alert('Error: ' + describeError(error))
     }
   }
 
@@ -74,7 +75,7 @@ export default function AdminPanel({ onBack }: AdminPanelProps) {
       setSelectedModule(created)
     } catch (error) {
       console.error('Error creating module:', error)
-      alert('Error: ' + error)
+      alert('Error: ' + describeError(error))
     }
   }
 
@@ -112,7 +113,7 @@ export default function AdminPanel({ onBack }: AdminPanelProps) {
       loadCourses()
     } catch (error) {
       console.error('Error creating lesson:', error)
-      alert('Error: ' + error)
+      alert('Error: ' + describeError(error))
     }
   }
 
@@ -127,7 +128,28 @@ export default function AdminPanel({ onBack }: AdminPanelProps) {
       setSelectedModule(null)
     } catch (error) {
       console.error('Error deleting course:', error)
-      alert('Błąd podczas usuwania kursu: ' + error)
+      alert('Błąd podczas usuwania kursu: ' + describeError(error))
+    }
+  }
+
+  const handleVerifySolutions = async (course: Course) => {
+    try {
+      const report = await lessonService.verifyCourseSolutions(course.id)
+      const problems = report.lessons
+        .filter((lesson) => lesson.status !== 'passed')
+        .map(
+          (lesson) =>
+            `${lesson.status === 'failed' ? '✗' : '?'} ${lesson.module} / ${lesson.title}\n  ${lesson.problems.join('\n  ')}`
+        )
+      alert(
+        [
+          `Rozwiązania kursu "${course.title}": ${report.passed} poprawnych, ${report.failed} błędnych, ${report.unverified} niesprawdzonych`,
+          ...problems,
+        ].join('\n\n')
+      )
+    } catch (error) {
+      console.error('Error verifying solutions:', error)
+      alert('Błąd: ' + describeError(error))
     }
   }
 
@@ -140,7 +162,7 @@ export default function AdminPanel({ onBack }: AdminPanelProps) {
       loadCourses()
     } catch (error) {
       console.error('Error deleting lesson:', error)
-      alert('Błąd: ' + error)
+      alert('Błąd: ' + describeError(error))
     }
   }
 
@@ -176,6 +198,17 @@ export default function AdminPanel({ onBack }: AdminPanelProps) {
               '0 12px 48px rgba(0, 0, 0, 0.1), 0 4px 12px rgba(0, 0, 0, 0.05)',
           }}
         >
+          {activeTab === 'courses' && selectedCourse && (
+            <div className="flex justify-end mb-4">
+              <button
+                onClick={() => handleVerifySolutions(selectedCourse)}
+                className="px-4 py-2 bg-purple-500 hover:bg-purple-600 text-white text-sm font-semibold rounded-lg transition shadow-md"
+              >
+                Sprawdź rozwiązania kursu
+              </button>
+            </div>
+          )}
+
           {activeTab === 'courses' && (
             <CourseList
               courses={courses}
@@ -3,13 +3,14 @@ import { invoke } from '@tauri-apps/api/core'
 import LoginForm from './LoginForm'
 import RegisterForm from './RegisterForm'
 import Toast, { ToastType } from './Toast'
+import { describeError } from '../services/errors'
+import { saveAuthTokens } from '../utils/auth'
 
 interface AuthResponse {
   success: boolean
   message: string
   user_id?: string
-  access_token?: string
-  refresh_token?: string
+  signed_in: boolean
 }
 
 interface AuthPanelProps {
@@ -40,7 +41,7 @@ export default function AuthPanel({ onLoginSuccess }: AuthPanelProps) {
       })
     } catch (error) {
       setToast({
-        message: 'Błąd podczas logowania przez Google: ' + error,
+        message: 'Błąd podczas logowania przez Google: ' + describeError(error),
         type: 'error',
       })
     } finally {
@@ -63,14 +64,8 @@ export default function AuthPanel({ onLoginSuccess }: AuthPanelProps) {
         })
         console.log('User ID:', response.user_id)
 
-        if (response.access_token) {
-          localStorage.setItem('access_token', response.access_token)
-        }
-        if (response.refresh_token) {
-          localStorage.setItem('refresh_token', response.refresh_token)
-        }
-        if (response.user_id) {
-          localStorage.setItem('user_id', response.user_id)
+        if (response.signed_in && response.user_id) {
+          saveAuthTokens({ user_id: response.user_id })
         }
 
         setTimeout(() => {
@@ -84,7 +79,7 @@ export default function AuthPanel({ onLoginSuccess }: AuthPanelProps) {
       }
     } catch (error) {
       setToast({
-        message: 'Błąd podczas logowania: ' + error,
+        message: 'Błąd podczas logowania: ' + describeError(error),
         type: 'error',
       })
     } finally {
@@ -112,18 +107,12 @@ export default function AuthPanel({ onLoginSuccess }: AuthPanelProps) {
         })
         console.log('User ID:', response.user_id)
 
-        if (response.access_token) {
-          localStorage.setItem('access_token', response.access_token)
-        }
-        if (response.refresh_token) {
-          localStorage.setItem('refresh_token', response.refresh_token)
-        }
-        if (response.user_id) {
-          localStorage.setItem('user_id', response.user_id)
+        if (response.signed_in && response.user_id) {
+          saveAuthTokens({ user_id: response.user_id })
         }
 
         setTimeout(() => {
-          if (response.access_token) {
+          if (response.signed_in) {
             onLoginSuccess?.()
           } else {
             setIsLogin(true)
@@ -137,7 +126,7 @@ export default function AuthPanel({ onLoginSuccess }: AuthPanelProps) {
       }
     } catch (error) {
       setToast({
-        message: 'Błąd podczas rejestracjid: ' + error,
+        message: 'Błąd podczas rejestracjid: ' + describeError(error),
         type: 'error',
       })
     } finally {
@@ -1,6 +1,7 @@
 import Editor from '@monaco-editor/react'
 import { useState } from 'react'
 import { getFileNameForLanguage } from '../utils/courseUtils'
+import { formatService } from '../services/FormatService'
 
 interface CodeEditorProps {
   initialCode?: string
@@ -23,6 +24,8 @@ export default function CodeEditor({
 }: CodeEditorProps) {
   const [code, setCode] = useState(initialCode)
   const [isRunning, setIsRunning] = useState(false)
+  const [isFormatting, setIsFormatting] = useState(false)
+  const [formatError, setFormatError] = useState<string | null>(null)
 
 
   
@@ -43,6 +46,24 @@ export default function CodeEditor({
     }
   }
 
+  const handleFormat = async () => {
+    setIsFormatting(true)
+    setFormatError(null)
+    try {
+      const result = await formatService.formatCode(language, code)
+      if (result.formatted === null) {
+        setFormatError(formatService.describeErrors(result))
+      } else if (result.changed) {
+        setCode(result.formatted)
+        onChange?.(result.formatted)
+      }
+    } catch (error) {
+      setFormatError(String(error))
+    } finally {
+      setIsFormatting(false)
+    }
+  }
+
   const handleReset = () => {
     setCode(initialCode)
     onChange?.(initialCode)
@@ -63,6 +84,16 @@ export default function CodeEditor({
         </div>
 
         <div className="flex gap-2">
+          {!readOnly && formatService.canFormat(language) && (
+            <button
+              onClick={handleFormat}
+              disabled={isFormatting}
+              className="px-3 py-1.5 bg-slate-600 text-sm text-white hover:bg-slate-700 rounded transition disabled:opacity-50"
+              title="Formatuj kod"
+            >
+              {isFormatting ? 'Formatowanie...' : 'Formatuj'}
+            </button>
+          )}
           {!readOnly && (
             <button
               onClick={handleReset}
@@ -90,6 +121,12 @@ export default function CodeEditor({
         </div>
       </div>
 
+      {formatError && (
+        <div className="bg-red-900/60 border-x border-gray-700 px-4 py-2 text-sm text-red-200 font-mono whitespace-pre-wrap">
+          {formatError}
+        </div>
+      )}
+
       <div className="border border-gray-700 border-t-0 rounded-b-lg overflow-hidden shadow-lg">
         <Editor
           height={height}
@@ -1,6 +1,7 @@
-import { useState, JSX, useEffect } from 'react'
+import { useState, JSX, useEffect, useRef } from 'react'
 import { Editor } from '@monaco-editor/react'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import {
   SiPython,
   SiJavascript,
@@ -10,6 +11,30 @@ import {
 } from 'react-icons/si'
 import { DiJava } from 'react-icons/di'
 
+interface RunOutputEvent {
+  run_id: string
+  stream: 'stdout' | 'stderr'
+  data: string
+}
+
+interface RunQueuedEvent {
+  run_id: string | null
+  position: number
+}
+
+interface ReplOutputEvent {
+  session_id: string
+  stream: 'stdout' | 'stderr'
+  data: string
+}
+
+interface ReplExitedEvent {
+  session_id: string
+  reason: 'exited' | 'idle' | 'closed'
+  message: string
+}
+
+const REPL_LANGUAGES = ['python', 'javascript']
 
 export default function CodePlayground({ onBack }: {onBack?: () => void}) {
   const [code, setCode] = useState<string>('')
@@ -17,6 +42,9 @@ export default function CodePlayground({ onBack }: {onBack?: () => void}) {
   const [output, setOutput] = useState<string>('')
   const [isRunning, setIsRunning] = useState<boolean>(false)
   const [terminalHeight, setTerminalHeight] = useState<number>(200)
+  const runIdRef = useRef<string | null>(null)
+  const [replSession, setReplSession] = useState<string | null>(null)
+  const [replInput, setReplInput] = useState<string>('')
 
   const getCourseIcon = (language: string): JSX.Element | null => {
     const courseIcons: Record<string, JSX.Element> = {
@@ -31,32 +59,125 @@ export default function CodePlayground({ onBack }: {onBack?: () => void}) {
   }
 
   const handleRun = async () => {
+    if (runIdRef.current) return
+
+    const runId = crypto.randomUUID()
+    runIdRef.current = runId
     setIsRunning(true)
     setOutput('> Running...\n')
 
+    // Output arrives as the program prints it; listen before starting.
+    const unlisten = await listen<RunOutputEvent>('run-output', (event) => {
+      if (event.payload.run_id === runId) {
+        setOutput((current) => current + event.payload.data)
+      }
+    })
+    // Other runs may be using every slot; nothing is printed until ours starts.
+    const unlistenQueue = await listen<RunQueuedEvent>('run-queued', (event) => {
+      if (event.payload.run_id === runId) {
+        setOutput(
+          event.payload.position > 0
+            ? `> W kolejce (pozycja ${event.payload.position})...\n`
+            : '> Running...\n'
+        )
+      }
+    })
+
     try {
       const result = await invoke<{
         success: boolean
-        output: string
+        outcome: string
         error?: string
-      }>('validate_code', {
+        compile_error?: string
+      }>('run_code_streaming', {
+        runId,
         code,
         language,
-        expectedOutput: '',
       })
 
-      if (result.success) {
-        setOutput(`>>> ${result.output}`)
-      } else {
-        setOutput(`>>> ${result.error || 'Error'}`)
+      if (!result.success) {
+        setOutput(
+          (current) =>
+            `${current}\n>>> ${result.compile_error ?? result.error ?? 'Error'}`
+        )
       }
     } catch (error) {
-      setOutput(`> Error\n${error}`)
+      setOutput((current) => `${current}\n> Error\n${error}`)
     } finally {
+      unlisten()
+      unlistenQueue()
+      runIdRef.current = null
       setIsRunning(false)
     }
   }
 
+  const handleStop = () => {
+    if (runIdRef.current) {
+      invoke('cancel_run', { runId: runIdRef.current }).catch((error) =>
+        console.error('Error cancelling run:', error)
+      )
+    }
+  }
+
+  useEffect(() => {
+    if (!replSession) return
+
+    const output = listen<ReplOutputEvent>('repl-output', (event) => {
+      if (event.payload.session_id === replSession) {
+        setOutput((current) => current + event.payload.data)
+      }
+    })
+    const exited = listen<ReplExitedEvent>('repl-exited', (event) => {
+      if (event.payload.session_id === replSession) {
+        setOutput((current) => `${current}\n> ${event.payload.message}\n`)
+        setReplSession(null)
+      }
+    })
+
+    return () => {
+      output.then((unlisten) => unlisten())
+      exited.then((unlisten) => unlisten())
+    }
+  }, [replSession])
+
+  // Changing language or leaving the playground ends the session.
+  useEffect(() => {
+    return () => {
+      if (replSession) {
+        invoke('stop_repl', { sessionId: replSession }).catch(() => {})
+      }
+    }
+  }, [replSession, language])
+
+  const handleToggleRepl = async () => {
+    if (replSession) {
+      await invoke('stop_repl', { sessionId: replSession })
+      return
+    }
+
+    const sessionId = crypto.randomUUID()
+    setOutput('')
+    setReplSession(sessionId)
+    try {
+      await invoke('start_repl', { sessionId, language })
+    } catch (error) {
+      setOutput(`> Error\n${error}`)
+      setReplSession(null)
+    }
+  }
+
+  const handleReplSubmit = async () => {
+    if (!replSession) return
+
+    setOutput((current) => `${current}${replInput}\n`)
+    setReplInput('')
+    try {
+      await invoke('send_repl_input', { sessionId: replSession, input: replInput })
+    } catch (error) {
+      setOutput((current) => `${current}> Error\n${error}\n`)
+    }
+  }
+
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
       if (e.key === 'F5' || (e.altKey && e.key === 'Enter')){
@@ -119,6 +240,22 @@ export default function CodePlayground({ onBack }: {onBack?: () => void}) {
           >
             <span>Run</span>
           </button>
+          {REPL_LANGUAGES.includes(language) && (
+            <button
+              onClick={handleToggleRepl}
+              className="bg-[#3c3c3c] hover:bg-[#505050] text-white px-4 py-1 rounded text-sm transition"
+            >
+              {replSession ? 'Close REPL' : 'REPL'}
+            </button>
+          )}
+          {isRunning && (
+            <button
+              onClick={handleStop}
+              className="bg-[#a1260d] hover:bg-[#c72e0f] text-white px-4 py-1 rounded text-sm transition"
+            >
+              Stop
+            </button>
+          )}
         </div>
       </div>
 
@@ -191,6 +328,21 @@ export default function CodePlayground({ onBack }: {onBack?: () => void}) {
             {output || "Press 'Run' to execute your code..."}
           </pre>
         </div>
+        {replSession && (
+          <input
+            value={replInput}
+            onChange={(e) => setReplInput(e.target.value)}
+            onKeyDown={(e) => {
+              if (e.key === 'Enter') {
+                e.preventDefault()
+                handleReplSubmit()
+              }
+            }}
+            autoFocus
+            className="bg-[#252526] text-[#cccccc] font-mono text-sm px-3 py-1 border-t border-[#2d2d30] outline-none"
+            placeholder="Wpisz polecenie i naciśnij Enter"
+          />
+        )}
       </div>
     </div>
   )
@@ -8,11 +8,100 @@ import { progressService } from '../services/ProgressService'
 import { Lesson, Course } from '../types/lesson'
 import { getNextLessonId, findCourseByLessonId } from '../utils/courseUtils'
 
+interface DiffLine {
+  kind: 'same' | 'missing' | 'extra'
+  expected_line: number | null
+  actual_line: number | null
+  text: string
+}
+
+type Outcome =
+  | 'passed'
+  | 'wrong_answer'
+  | 'runtime_error'
+  | 'compile_error'
+  | 'timeout'
+  | 'limit_exceeded'
+  | 'toolchain_missing'
+  | 'sandbox_error'
+  | 'cancelled'
+
 interface CodeValidationResponse {
   success: boolean
+  outcome: Outcome
   output: string
+  stdout: string
+  stderr: string
+  exit_code: number | null
+  duration_ms: number
   error?: string
   is_correct: boolean
+  timed_out: boolean
+  limit_exceeded: 'memory' | 'cpu' | 'processes' | 'output' | 'file_size' | null
+  compile_error?: string
+  diagnostics: { line: number | null; column: number | null; message: string }[]
+  hints: Hint[]
+  diff: DiffLine[]
+  tests: { name: string; passed: boolean; message: string | null }[]
+  confinement: {
+    network_isolated: boolean
+    filesystem_read_only: boolean
+    resource_limits: boolean
+    process_limit: boolean
+    warnings: string[]
+  }
+  // Answered from the backend's cache of earlier runs
+  cached: boolean
+}
+
+interface Hint {
+  line: number | null
+  code: string | null
+  title: string
+  explanation: string
+}
+
+interface TestCaseResult {
+  description?: string
+  input?: string
+  expected_output: string
+  actual_output: string
+  passed: boolean
+  outcome: Outcome
+  stderr: string
+  exit_code: number | null
+  duration_ms: number
+  error?: string
+  timed_out: boolean
+  limit_exceeded: CodeValidationResponse['limit_exceeded']
+  diff: DiffLine[]
+  hints: Hint[]
+}
+
+interface TestCasesResponse {
+  all_passed: boolean
+  passed_count: number
+  total: number
+  cases: TestCaseResult[]
+  confinement: CodeValidationResponse['confinement']
+}
+
+interface MarkupValidationResponse {
+  all_passed: boolean
+  passed_count: number
+  total: number
+  assertions: { description: string; passed: boolean; message: string | null }[]
+  diagnostics: CodeValidationResponse['diagnostics']
+}
+
+interface ToolchainStatus {
+  language: string
+  name: string
+  available: boolean
+  path: string | null
+  version: string | null
+  error: string | null
+  embedded: string | null
 }
 
 interface LessonDemoProps {
@@ -33,15 +122,28 @@ export default function LessonDemo({
   const [course, setCourse] = useState<Course | null>(null)
   const [loading, setLoading] = useState<boolean>(true)
   const [htmlPreview, setHtmlPreview] = useState<string>('');
+  const [missingToolchain, setMissingToolchain] = useState<ToolchainStatus | null>(null)
+  const [revealedHint, setRevealedHint] = useState<string | null>(null)
   
 
   useEffect(() => {
+    setRevealedHint(null)
     loadLesson()
   }, [lessonId])
 
+  useEffect(() => {
+    if (!lesson) return
+    invoke<ToolchainStatus[]>('check_toolchains')
+      .then((statuses) => {
+        const status = statuses.find((s) => s.language === lesson.language)
+        setMissingToolchain(status && !status.available && !status.embedded ? status : null)
+      })
+      .catch((error) => console.error('Error checking toolchains:', error))
+  }, [lesson?.language])
+
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
-      if (e.altKey && e.key === 'Enter') {
+      if (e.altKey && e.key === 'Enter' && !missingToolchain) {
         e.preventDefault()
         handleRunCode(currentCode)
       }
@@ -50,7 +152,7 @@ export default function LessonDemo({
     return () => {
       window.removeEventListener('keydown', handleKeyDown)
     }
-  }, [currentCode])
+  }, [currentCode, missingToolchain])
 
   const loadLesson = async () => {
     try {
@@ -96,42 +198,192 @@ export default function LessonDemo({
       ? lesson.content.testCases[0].expectedOutput
       : ''
 
-  const handleRunCode = async (code: string) => {
-    try {
-      if (lesson.language === 'html') {
-        setHtmlPreview(code)
+  const exerciseLimits =
+    lesson.content.type === 'exercise' ? lesson.content.limits : undefined
+  const comparison =
+    lesson.content.type === 'exercise' ? lesson.content.comparison : undefined
+  const hiddenTests =
+    lesson.content.type === 'exercise' ? lesson.content.tests : undefined
+  const engine =
+    lesson.content.type === 'exercise' ? lesson.content.engine : undefined
+  const assertions =
+    lesson.content.type === 'exercise' ? lesson.content.assertions ?? [] : []
+
+  // "-" lines were expected but not printed, "+" lines were printed instead.
+  const formatDiff = (diff: DiffLine[]) =>
+    diff
+      .map((line) => {
+        const marker = line.kind === 'missing' ? '-' : line.kind === 'extra' ? '+' : ' '
+        return `${marker} ${line.text}`
+      })
+      .join('\n')
+  const testCases =
+    lesson.content.type === 'exercise' ? lesson.content.testCases ?? [] : []
+
+  const describeFailedCase = (failed: TestCaseResult, report: TestCasesResponse) => {
+    const lines = [
+      `Zaliczone testy: ${report.passed_count}/${report.total}`,
+      `Niezaliczony test${failed.description ? `: ${failed.description}` : ''}`,
+    ]
+    if (failed.input) lines.push(`Wejście:\n${failed.input}`)
+    lines.push(`Oczekiwano:\n${failed.expected_output}`)
+    lines.push(failed.error ? `Błąd:\n${failed.error}` : `Otrzymano:\n${failed.actual_output}`)
+    if (failed.diff.length > 0) lines.push(`Różnice:\n${formatDiff(failed.diff)}`)
+    if (failed.hints.length > 0) lines.push(formatHints(failed.hints))
+    return lines.join('\n\n')
+  }
+
+  const formatHints = (hints: Hint[]) =>
+    hints
+      .map((hint) => {
+        const location = hint.line
+          ? `Linia ${hint.line}${hint.code ? `: ${hint.code.trim()}` : ''}\n`
+          : ''
+        return `💡 ${hint.title}\n${location}${hint.explanation}`
+      })
+      .join('\n\n')
+
+  const formatUnitTests = (result: CodeValidationResponse) =>
+    result.tests
+      .map((test) =>
+        test.passed ? `✓ ${test.name}` : `✗ ${test.name}${test.message ? `: ${test.message}` : ''}`
+      )
+      .join('\n')
+
+  const runCode = async (code: string) => {
+    if (lesson.language === 'html' || lesson.language === 'css') {
+      if (assertions.length === 0) {
+        return { output: 'Podgląd zaktualizowany', isCorrect: null }
       }
+      const report = await invoke<MarkupValidationResponse>('validate_markup', {
+        code,
+        language: lesson.language,
+        assertions,
+        html: lesson.content.type === 'exercise' ? lesson.content.html : undefined,
+      })
+      const checklist = report.assertions.map((assertion) =>
+        assertion.passed
+          ? `✓ ${assertion.description}`
+          : `✗ ${assertion.description}${assertion.message ? `: ${assertion.message}` : ''}`
+      )
+      const problems = report.diagnostics.map(
+        (diagnostic) => `Linia ${diagnostic.line ?? '?'}: ${diagnostic.message}`
+      )
 
+      return {
+        output: [checklist.join('\n'), problems.join('\n')].filter(Boolean).join('\n\n'),
+        isCorrect: report.all_passed,
+      }
+    }
+
+    if (hiddenTests) {
       const result = await invoke<CodeValidationResponse>('validate_code', {
         code,
         language: lesson.language,
-        expectedOutput: expectedOutput,
+        expectedOutput: '',
+        limits: exerciseLimits,
+        tests: hiddenTests,
+        engine,
       })
+      const sections = [result.output, formatUnitTests(result)]
+      if (!result.is_correct) sections.push(result.compile_error ?? result.error ?? '')
+      sections.push(formatHints(result.hints))
 
-      if (result.error) {
-        setOutput(result.error)
-        setIsCorrect(false)
-      } else {
-        setOutput(result.output)
-        setIsCorrect(result.is_correct)
-
-        if (result.is_correct) {
-          // Mark lesson as completed
-          const userId = localStorage.getItem('user_id')
-          if (userId) {
-            try {
-              await progressService.markLessonCompleted(userId, lessonId)
-              console.log('Lesson marked as completed')
-            } catch (error) {
-              console.error('Error saving progress:', error)
-            }
-          }
+      return {
+        output: sections.filter(Boolean).join('\n\n'),
+        isCorrect: result.is_correct,
+      }
+    }
+
+    if (testCases.length > 1) {
+      const report = await invoke<TestCasesResponse>('validate_test_cases', {
+        code,
+        language: lesson.language,
+        testCases,
+        limits: exerciseLimits,
+        comparison,
+        engine,
+      })
+      const failed = report.cases.find((testCase) => !testCase.passed)
+
+      return {
+        output: failed
+          ? describeFailedCase(failed, report)
+          : `Wszystkie testy zaliczone (${report.passed_count}/${report.total})`,
+        isCorrect: report.all_passed,
+      }
+    }
+
+    const result = await invoke<CodeValidationResponse>('validate_code', {
+      code,
+      language: lesson.language,
+      expectedOutput: expectedOutput,
+      limits: exerciseLimits,
+      comparison,
+      engine,
+    })
+
+    const hints = formatHints(result.hints)
+
+    if (result.outcome === 'wrong_answer' && result.diff.length > 0) {
+      return {
+        output: [
+          `${result.output}\n\nRóżnice względem oczekiwanego wyniku:\n${formatDiff(result.diff)}`,
+          hints,
+        ]
+          .filter(Boolean)
+          .join('\n\n'),
+        isCorrect: false,
+      }
+    }
+
+    return {
+      output: [result.compile_error ?? result.error ?? result.output, hints]
+        .filter(Boolean)
+        .join('\n\n'),
+      isCorrect: !result.error && result.is_correct,
+    }
+  }
+
+  const handleRunCode = async (code: string) => {
+    try {
+      if (lesson.language === 'html') {
+        setHtmlPreview(code)
+      }
+
+      const result = await runCode(code)
+      setOutput(result.output)
+      setIsCorrect(result.isCorrect)
 
-          setTimeout(() => {
-            setShowSuccessModal(true)
-          }, 500)
+      // Every graded run counts as an attempt; the lesson's hint is only
+      // revealed after enough of them.
+      const userId = localStorage.getItem('user_id')
+      let attempts: number | undefined
+      if (userId && result.isCorrect !== null) {
+        try {
+          const record = await progressService.recordAttempt(lessonId)
+          attempts = record.attempts
+          if (!result.isCorrect && record.hint) setRevealedHint(record.hint)
+        } catch (error) {
+          console.error('Error recording attempt:', error)
         }
       }
+
+      if (result.isCorrect) {
+        // Mark lesson as completed
+        if (userId) {
+          try {
+            await progressService.markLessonCompleted(userId, lessonId, attempts)
+            console.log('Lesson marked as completed')
+          } catch (error) {
+            console.error('Error saving progress:', error)
+          }
+        }
+
+        setTimeout(() => {
+          setShowSuccessModal(true)
+        }, 500)
+      }
     } catch (error) {
       setOutput(`Error: ${error}`)
       setIsCorrect(false)
@@ -240,11 +492,19 @@ export default function LessonDemo({
                   </p>
                 </div>
               )}
-              {lesson.content.type === 'exercise' && lesson.content.hint && (
+              {missingToolchain && (
+                <div className="bg-red-50 dark:bg-red-900/30 rounded-2xl p-5 border border-red-100 dark:border-red-800">
+                  <p className="text-sm text-red-800 dark:text-red-200 leading-relaxed">
+                    <strong className="font-semibold">Nie można uruchomić kodu:</strong>{' '}
+                    {missingToolchain.name} nie jest zainstalowany na tym komputerze.
+                  </p>
+                </div>
+              )}
+              {revealedHint && (
                 <div className="bg-amber-50 dark:bg-amber-900/30 rounded-2xl p-5 border border-amber-100 dark:border-amber-800">
                   <p className="text-sm text-amber-800 dark:text-amber-200 leading-relaxed">
                     <strong className="font-semibold">💡 Wskazówka:</strong>{' '}
-                    {lesson.content.hint}
+                    {revealedHint}
                   </p>
                 </div>
               )}
@@ -269,7 +529,7 @@ export default function LessonDemo({
                   initialCode={starterCode}
                   language={lesson.language}
                   onChange={setCurrentCode}
-                  onRun={handleRunCode}
+                  onRun={missingToolchain ? undefined : handleRunCode}
                   height="300px"
                   theme="vs-dark"
                 />
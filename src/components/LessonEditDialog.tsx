@@ -1,6 +1,8 @@
 import { useState, useEffect } from 'react'
 import { Lesson, QuizOption } from '../types/lesson'
 import { lessonService } from '../services/LessonService'
+import { formatService } from '../services/FormatService'
+import { describeError } from '../services/errors'
 
 interface LessonEditDialogProps {
   isOpen: boolean
@@ -26,7 +28,10 @@ export default function LessonEditDialog({
       | 'javascript'
       | 'html'
       | 'css'
-      | 'typescript',
+      | 'typescript'
+      | 'rust'
+      | 'c'
+      | 'go',
     lessonType: 'exercise' as 'exercise' | 'theory' | 'quiz' | 'project',
     xpReward: 10,
     instruction: '',
@@ -40,6 +45,7 @@ export default function LessonEditDialog({
     quizOptions: [] as QuizOption[],
     quizExplanation: '',
   })
+  const [formatError, setFormatError] = useState<string | null>(null)
 
   useEffect(() => {
     if (isOpen && lessonId) {
@@ -82,12 +88,38 @@ export default function LessonEditDialog({
       })
     } catch (error) {
       console.error('Error loading lesson:', error)
-      alert('Błąd ładowania lekcji: ' + error)
+      alert('Błąd ładowania lekcji: ' + describeError(error))
     } finally {
       setLoading(false)
     }
   }
 
+  const handleFormat = async (field: 'starterCode' | 'solution') => {
+    setFormatError(null)
+    try {
+      const result = await formatService.formatCode(formData.language, formData[field])
+      const formatted = result.formatted
+      if (formatted === null) {
+        setFormatError(formatService.describeErrors(result))
+      } else {
+        setFormData((data) => ({ ...data, [field]: formatted }))
+      }
+    } catch (error) {
+      setFormatError(String(error))
+    }
+  }
+
+  const formatButton = (field: 'starterCode' | 'solution') =>
+    formatService.canFormat(formData.language) && (
+      <button
+        type="button"
+        onClick={() => handleFormat(field)}
+        className="ml-2 text-xs text-purple-600 hover:text-purple-800 underline"
+      >
+        Formatuj
+      </button>
+    )
+
   const handleSave = async () => {
     try {
       setSaving(true)
@@ -130,7 +162,7 @@ export default function LessonEditDialog({
       onClose()
     } catch (error) {
       console.error('Error updating lesson:', error)
-      alert('Błąd aktualizacji: ' + error)
+      alert('Błąd aktualizacji: ' + describeError(error))
     } finally {
       setSaving(false)
     }
@@ -221,6 +253,9 @@ export default function LessonEditDialog({
                     <option value="html">HTML</option>
                     <option value="css">CSS</option>
                     <option value="typescript">TypeScript</option>
+                    <option value="rust">Rust</option>
+                    <option value="c">C</option>
+                    <option value="go">Go</option>
                   </select>
                 </div>
               </div>
@@ -348,6 +383,7 @@ export default function LessonEditDialog({
                       <div>
                         <label className="block text-sm font-medium text-slate-700 mb-2">
                           Kod startowy
+                          {formatButton('starterCode')}
                         </label>
                         <textarea
                           value={formData.starterCode}
@@ -366,6 +402,7 @@ export default function LessonEditDialog({
                       <div>
                         <label className="block text-sm font-medium text-slate-700 mb-2">
                           Rozwiązanie *
+                          {formatButton('solution')}
                         </label>
                         <textarea
                           value={formData.solution}
@@ -379,6 +416,11 @@ export default function LessonEditDialog({
                           className="w-full px-4 py-2 rounded-lg border border-slate-300 focus:ring-2 focus:ring-purple-500 outline-none font-mono text-sm resize-none"
                           placeholder="print('Hello World')"
                         />
+                        {formatError && (
+                          <p className="mt-2 text-sm text-red-600 font-mono whitespace-pre-wrap">
+                            {formatError}
+                          </p>
+                        )}
                       </div>
 
                       <div className="grid grid-cols-2 gap-4">
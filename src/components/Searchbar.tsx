@@ -66,16 +66,8 @@ export default function SearchBar({
 
     setIsLoading(true)
     try {
-      const token = localStorage.getItem('access_token')
-      if (!token) {
-        console.warn('No access token for search')
-        setIsLoading(false)
-        return
-      }
-
       const searchResults = await invoke<SearchResult[]>('search_lessons', {
         query: query.trim(),
-        accessToken: token,
       })
 
       setResults(searchResults)
@@ -16,7 +16,11 @@ const getInitials = (email?: string, username?: string) => {
   return '?'
 }
 
-export const UserProfileDropdown = () => {
+interface UserProfileDropdownProps {
+  onLogoutEverywhere: () => void
+}
+
+export const UserProfileDropdown = ({ onLogoutEverywhere }: UserProfileDropdownProps) => {
   const { profile, loading } = useUserProfile()
   const [isOpen, setIsOpen] = useState(false)
   const [showModal, setShowModal] = useState(false)
@@ -135,6 +139,15 @@ export const UserProfileDropdown = () => {
               >
                 Zobacz pełny profil
               </button>
+              <button
+                onClick={() => {
+                  setIsOpen(false)
+                  onLogoutEverywhere()
+                }}
+                className="w-full text-left px-4 py-2 text-sm text-red-600 dark:text-red-400 hover:bg-slate-100 dark:hover:bg-slate-700 rounded-md transition-colors"
+              >
+                Wyloguj ze wszystkich urządzeń
+              </button>
             </div>
           </div>
         )}
@@ -1,5 +1,5 @@
 import { describe, it, expect, vi, beforeEach } from 'vitest'
-import { renderHook, waitFor } from '@testing-library/react'
+import { renderHook, waitFor, act } from '@testing-library/react'
 import { useAuth } from './useAuth'
 import '@testing-library/jest-dom/vitest'
 
@@ -7,13 +7,29 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: vi.fn(),
 }))
 
+vi.mock('@tauri-apps/api/event', () => ({
+  listen: vi.fn(() => Promise.resolve(() => {})),
+}))
+
 vi.mock('../utils/auth', () => ({
   isAuthenticated: vi.fn(),
   clearAuthTokens: vi.fn(),
+  saveAuthTokens: vi.fn(),
 }))
 
 import { invoke } from '@tauri-apps/api/core'
-import { isAuthenticated, clearAuthTokens } from '../utils/auth'
+import { isAuthenticated, clearAuthTokens, saveAuthTokens } from '../utils/auth'
+
+// Answers `restore_session` with `userId` and `check_is_admin` with `isAdmin`.
+function mockBackend(isAdmin: boolean | Error, userId: string | null = 'user-1') {
+  vi.mocked(invoke).mockImplementation(async (command) => {
+    if (command === 'restore_session') {
+      return { user_id: userId, expires_at: userId ? 1700000000 : null }
+    }
+    if (isAdmin instanceof Error) throw isAdmin
+    return isAdmin
+  })
+}
 
 describe('useAuth', () => {
   beforeEach(() => {
@@ -31,9 +47,9 @@ describe('useAuth', () => {
   })
 
   it('sets isAdmin to true when user is admin', async () => {
-    localStorage.setItem('access_token', 'test_token')
+    localStorage.setItem('user_id', 'user-1')
     vi.mocked(isAuthenticated).mockReturnValue(true)
-    vi.mocked(invoke).mockResolvedValue(true)
+    mockBackend(true)
 
     const { result } = renderHook(() => useAuth())
 
@@ -43,9 +59,9 @@ describe('useAuth', () => {
   })
 
   it('sets isAdmin to false when user is not admin', async () => {
-    localStorage.setItem('access_token', 'test_token')
+    localStorage.setItem('user_id', 'user-1')
     vi.mocked(isAuthenticated).mockReturnValue(true)
-    vi.mocked(invoke).mockResolvedValue(false)
+    mockBackend(false)
 
     const { result } = renderHook(() => useAuth())
 
@@ -54,7 +70,7 @@ describe('useAuth', () => {
     })
   })
 
-  it('sets isAdmin to false when no token exists', async () => {
+  it('sets isAdmin to false when nobody is signed in', async () => {
     const { result } = renderHook(() => useAuth())
 
     await result.current.refreshAdmin()
@@ -63,12 +79,14 @@ describe('useAuth', () => {
   })
 
   it('login sets authenticated to true and refreshes admin status', async () => {
-    localStorage.setItem('access_token', 'test_token')
+    localStorage.setItem('user_id', 'user-1')
     vi.mocked(isAuthenticated).mockReturnValue(false)
-    vi.mocked(invoke).mockResolvedValue(true)
+    mockBackend(true)
 
     const { result } = renderHook(() => useAuth())
 
+    // The login form stores the user before calling login().
+    vi.mocked(isAuthenticated).mockReturnValue(true)
     result.current.login()
 
     await waitFor(() => {
@@ -76,31 +94,45 @@ describe('useAuth', () => {
     })
 
     await waitFor(() => {
-      expect(invoke).toHaveBeenCalledWith('check_is_admin', {
-        accessToken: 'test_token',
-      })
+      expect(invoke).toHaveBeenCalledWith('check_is_admin')
     })
   })
 
-  it('logout clears tokens and resets state', () => {
+  it('logout revokes the session, clears tokens and resets state', async () => {
     // reload window.location properly
     delete (window as any).location
     ;(window as any).location = { reload: vi.fn() }
+    mockBackend(false)
 
     const { result } = renderHook(() => useAuth())
 
-    result.current.logout()
+    await act(() => result.current.logout())
 
+    expect(invoke).toHaveBeenCalledWith('logout_user', { everywhere: false })
     expect(clearAuthTokens).toHaveBeenCalled()
     expect(result.current.isAuthenticated).toBe(false)
     expect(result.current.isAdmin).toBe(false)
     expect(window.location.reload).toHaveBeenCalled()
   })
 
+  it('logout signs out locally when the session cannot be revoked', async () => {
+    delete (window as any).location
+    ;(window as any).location = { reload: vi.fn() }
+    mockBackend(new Error('Network error'))
+
+    const { result } = renderHook(() => useAuth())
+
+    await act(() => result.current.logout(true))
+
+    expect(invoke).toHaveBeenCalledWith('logout_user', { everywhere: true })
+    expect(clearAuthTokens).toHaveBeenCalled()
+    expect(result.current.isAuthenticated).toBe(false)
+  })
+
   it('handles invoke error gracefully', async () => {
-    localStorage.setItem('access_token', 'test_token')
+    localStorage.setItem('user_id', 'user-1')
     vi.mocked(isAuthenticated).mockReturnValue(true)
-    vi.mocked(invoke).mockRejectedValue(new Error('API Error'))
+    mockBackend(new Error('API Error'))
 
     const { result } = renderHook(() => useAuth())
 
@@ -108,4 +140,27 @@ describe('useAuth', () => {
       expect(result.current.isAdmin).toBe(false)
     })
   })
+
+  it('restores the session saved by the previous run', async () => {
+    mockBackend(false, 'user-2')
+
+    const { result } = renderHook(() => useAuth())
+
+    await waitFor(() => {
+      expect(result.current.isAuthenticated).toBe(true)
+    })
+    expect(saveAuthTokens).toHaveBeenCalledWith({ user_id: 'user-2' })
+  })
+
+  it('signs out when no saved session could be restored', async () => {
+    vi.mocked(isAuthenticated).mockReturnValue(true)
+    mockBackend(false, null)
+
+    const { result } = renderHook(() => useAuth())
+
+    await waitFor(() => {
+      expect(result.current.isAuthenticated).toBe(false)
+    })
+    expect(clearAuthTokens).toHaveBeenCalled()
+  })
 })
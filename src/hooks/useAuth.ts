@@ -1,20 +1,27 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { useCallback, useState, useEffect } from 'react'
-import { isAuthenticated as isAuth, clearAuthTokens } from '../utils/auth'
+import {
+  isAuthenticated as isAuth,
+  clearAuthTokens,
+  saveAuthTokens,
+} from '../utils/auth'
+
+interface SessionChanged {
+  user_id: string | null
+  expires_at: number | null
+}
 
 export function useAuth() {
   const [isAuthenticated, setIsAuthenticated] = useState<boolean>(isAuth())
   const [isAdmin, setIsAdmin] = useState<boolean>(false)
   const refreshAdmin = useCallback(async () => {
-    const token = localStorage.getItem('access_token')
-    if (!token) {
+    if (!isAuth()) {
       setIsAdmin(false)
       return
     }
     try {
-      const res = await invoke<boolean>('check_is_admin', {
-        accessToken: token,
-      })
+      const res = await invoke<boolean>('check_is_admin')
       setIsAdmin(res)
     } catch (error) {
       setIsAdmin(false)
@@ -26,19 +33,54 @@ export function useAuth() {
     refreshAdmin()
   }, [refreshAdmin])
 
-  const logout = useCallback(() => {
+  // Signed out locally even when the backend couldn't revoke the session.
+  const logout = useCallback(async (everywhere = false) => {
+    try {
+      await invoke('logout_user', { everywhere })
+    } catch (error) {
+      console.error('Error signing out:', error)
+    }
     clearAuthTokens()
     setIsAuthenticated(false)
     setIsAdmin(false)
     window.location.reload()
   }, [])
 
+  // The backend keeps the session between runs; until it has checked the
+  // saved one, trust what the previous run left in localStorage.
   useEffect(() => {
     setIsAuthenticated(isAuth())
     if (isAuth()) {
       refreshAdmin()
     }
+    invoke<SessionChanged | undefined>('restore_session')
+      .then((session) => {
+        if (session?.user_id) {
+          saveAuthTokens({ user_id: session.user_id })
+          setIsAuthenticated(true)
+        } else {
+          clearAuthTokens()
+          setIsAuthenticated(false)
+          setIsAdmin(false)
+        }
+      })
+      .catch((error) => console.error('Error restoring session:', error))
   }, [refreshAdmin])
 
+  // The backend refreshes the session on its own; it only ends when the
+  // refresh token is refused.
+  useEffect(() => {
+    const unlisten = listen<SessionChanged>('session-changed', (event) => {
+      if (!event.payload.user_id) {
+        clearAuthTokens()
+        setIsAuthenticated(false)
+        setIsAdmin(false)
+      }
+    })
+    return () => {
+      unlisten.then((stop) => stop())
+    }
+  }, [])
+
   return { isAuthenticated, isAdmin, refreshAdmin, login, logout }
 }
@@ -1,6 +1,7 @@
 import { useState, useEffect } from 'react'
 import { UserProfile, userProfileService } from '../services/UserProfileService'
 import { useAuth } from './useAuth'
+import { describeError } from '../services/errors'
 
 export function useUserProfile() {
   const { isAuthenticated } = useAuth()
@@ -28,9 +29,7 @@ export function useUserProfile() {
           setError(null);
 
         } catch (err) {
-          setError(
-            err instanceof Error ? err.message : 'An unknown error occurred'
-          )
+          setError(describeError(err))
           console.error('Failed to fetch user profile:', err)
         } finally {
           setLoading(false)
@@ -14,33 +14,20 @@ export interface Achievement {
 
 export class AchievementService {
   async getAvailableAchievements(): Promise<Achievement[]> {
-    const token = localStorage.getItem('access_token')
-    if (!token) throw new Error('No access token')
-
-    return await invoke<Achievement[]>('get_available_achievements', {
-      accessToken: token,
-    })
+    return await invoke<Achievement[]>('get_available_achievements')
   }
 
   async getUserAchievements(userId: string): Promise<Achievement[]> {
-    const token = localStorage.getItem('access_token')
-    if (!token) throw new Error('No access token')
-
     const response = await invoke<any[]>('get_user_achievements', {
       userId,
-      accessToken: token,
     })
 
     return response.map((item) => item.achievements).filter(Boolean)
   }
 
   async checkAndUnlockAchievements(userId: string): Promise<Achievement[]> {
-    const token = localStorage.getItem('access_token')
-    if (!token) throw new Error('No access token')
-
     return await invoke<Achievement[]>('check_and_unlock_achievements', {
       userId,
-      accessToken: token,
     })
   }
 }
@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/core'
+
+interface FormatDiagnostic {
+  line: number | null
+  column: number | null
+  message: string
+}
+
+interface FormatResult {
+  // null when the formatter rejected the code; `diagnostics` say why
+  formatted: string | null
+  changed: boolean
+  formatter: string
+  diagnostics: FormatDiagnostic[]
+}
+
+// Languages with a formatter in the backend (black/ruff, prettier, rustfmt)
+const FORMATTABLE = ['python', 'javascript', 'typescript', 'rust']
+
+export class FormatService {
+  canFormat(language: string): boolean {
+    return FORMATTABLE.includes(language)
+  }
+
+  async formatCode(language: string, code: string): Promise<FormatResult> {
+    return await invoke<FormatResult>('format_code', { language, code })
+  }
+
+  describeErrors(result: FormatResult): string {
+    return result.diagnostics
+      .map((d) => (d.line ? `Linia ${d.line}: ${d.message}` : d.message))
+      .join('\n')
+  }
+}
+
+export const formatService = new FormatService()
+
+export type { FormatResult, FormatDiagnostic }
@@ -49,14 +49,12 @@ describe('LessonService - cache', () => {
     await expect(service.getCourses()).rejects.toThrow('API Error')
   })
 
-  it('should pass access token from localstorage when true', async () => {
+  it('should leave the access token to the backend session', async () => {
     localStorage.setItem('access_token', 'test-token-123')
     vi.mocked(invoke).mockResolvedValue([])
 
     await service.getCourses()
 
-    expect(invoke).toHaveBeenCalledWith('get_all_courses', {
-      accessToken: 'test-token-123',
-    })
+    expect(invoke).toHaveBeenCalledWith('get_all_courses')
   })
 })
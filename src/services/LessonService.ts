@@ -11,6 +11,7 @@ import {
   CreateLessonDTO,
   Language,
 } from '../types/lesson'
+import { isAuthenticated } from '../utils/auth'
 
 export class LessonService {
   private cache = new Map<string, Course>()
@@ -20,10 +21,7 @@ export class LessonService {
       return Array.from(this.cache.values())
     }
 
-    const token = localStorage.getItem('access_token')
-    const rawCourses = token
-      ? await invoke<any[]>('get_all_courses', { accessToken: token })
-      : await invoke<any[]>('get_all_courses')
+    const rawCourses = await invoke<any[]>('get_all_courses')
 
     const courses: Course[] = (rawCourses || []).map((c: any) => {
       const modules = (c.modules || []).map((m: any) => ({
@@ -61,11 +59,8 @@ export class LessonService {
   }
 
   async getLessonById(lessonId: string): Promise<Lesson | null> {
-    const token = this.getTokenOrThrow()
-
     const lesson = await invoke<Lesson>('get_lesson_by_id', {
       lessonId,
-      accessToken: token,
     })
 
     return lesson
@@ -182,27 +177,40 @@ export class LessonService {
     await this.invokeWithAuth<void>('delete_course', { courseId })
   }
 
-  isAuthenticated(): boolean {
-    return !!localStorage.getItem('access_token')
+  // Runs every exercise's solution against its own test cases
+  async verifyCourseSolutions(courseId: string): Promise<CourseSolutionsReport> {
+    return await invoke<CourseSolutionsReport>('verify_course_solutions', {
+      courseId,
+    })
   }
 
-  private getTokenOrThrow(): string {
-    const token = localStorage.getItem('access_token')
-    if (!token) {
-      throw new Error('Not authenticated - access token missing')
-    }
-    return token
+  isAuthenticated(): boolean {
+    return isAuthenticated()
   }
 
+  // The backend attaches the session's token; only the cache is ours to
+  // invalidate.
   private async invokeWithAuth<T>(command: string, params: any): Promise<T> {
-    const token = this.getTokenOrThrow()
-    const result = await invoke<T>(command, {
-      ...params,
-      accessToken: token,
-    })
+    const result = await invoke<T>(command, params)
     this.cache.clear()
     return result
   }
 }
 
 export const lessonService = new LessonService()
+
+export interface LessonSolutionReport {
+  lesson_id: string
+  title: string
+  module: string
+  status: 'passed' | 'failed' | 'skipped' | 'unverified'
+  problems: string[]
+}
+
+export interface CourseSolutionsReport {
+  course_id: string
+  passed: number
+  failed: number
+  unverified: number
+  lessons: LessonSolutionReport[]
+}
@@ -30,26 +30,23 @@ describe('ProgressService', () => {
 
   describe('getUserProgress', () => {
     it('should call invoke with correct parameters', async () => {
-      localStorage.setItem('access_token', 'test-token-123')
       vi.mocked(invoke).mockResolvedValue([mockProgress])
       const result = await service.getUserProgress('user-1')
       expect(invoke).toHaveBeenCalledWith('get_user_progress', {
         userId: 'user-1',
-        accessToken: 'test-token-123',
       })
       expect(result).toEqual([mockProgress])
     })
 
-    it('should throw an error if no access token is found', async () => {
-      await expect(service.getUserProgress('user-1')).rejects.toThrow(
-        'No access token'
-      )
+    it('should pass on the error when the session has expired', async () => {
+      const expired = { kind: 'auth_expired', message: 'JWT expired' }
+      vi.mocked(invoke).mockRejectedValue(expired)
+      await expect(service.getUserProgress('user-1')).rejects.toEqual(expired)
     })
   })
 
   describe('updateLessonProgress', () => {
     it("should call invoke with correct parameters for 'in_progress' status", async () => {
-      localStorage.setItem('access_token', 'test-token-456')
       vi.mocked(invoke).mockResolvedValue(mockProgress)
 
       await service.updateLessonProgress('user-1', 'lesson-1', 'in_progress', {
@@ -66,12 +63,10 @@ describe('ProgressService', () => {
           attempts: 2,
           completed_at: undefined,
         },
-        accessToken: 'test-token-456',
       })
     })
 
     it("should call invoke with completed_at for 'completed' status", async () => {
-      localStorage.setItem('access_token', 'test-token-789')
       const completedProgress = {
         ...mockProgress,
         status: 'completed' as const,
@@ -92,12 +87,10 @@ describe('ProgressService', () => {
           attempts: 3,
           completed_at: mockDate.toISOString(),
         },
-        accessToken: 'test-token-789',
       })
     })
 
     it('should use 1 attempt if not provided', async () => {
-      localStorage.setItem('access_token', 'test-token-456')
       vi.mocked(invoke).mockResolvedValue(mockProgress)
 
       await service.updateLessonProgress('user-1', 'lesson-1', 'in_progress', {
@@ -113,14 +106,15 @@ describe('ProgressService', () => {
           attempts: 1,
           completed_at: undefined,
         },
-        accessToken: 'test-token-456',
       })
     })
 
-    it('should throw an error if no access token is found', async () => {
+    it('should pass on the error when the session has expired', async () => {
+      const expired = { kind: 'auth_expired', message: 'JWT expired' }
+      vi.mocked(invoke).mockRejectedValue(expired)
       await expect(
         service.updateLessonProgress('user-1', 'lesson-1', 'in_progress')
-      ).rejects.toThrow('No access token')
+      ).rejects.toEqual(expired)
     })
   })
 })
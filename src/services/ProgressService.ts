@@ -11,6 +11,12 @@ interface UserProgress {
   time_spent_seconds?: number
 }
 
+interface AttemptRecord {
+  attempts: number
+  // The lesson's hint, once the learner has tried often enough to see it.
+  hint: string | null
+}
+
 interface CreateProgressInput {
   user_id: string
   lesson_id: string
@@ -23,14 +29,8 @@ interface CreateProgressInput {
 
 export class ProgressService {
   async getUserProgress(userId: string): Promise<UserProgress[]> {
-    const token = localStorage.getItem('access_token')
-    if (!token) {
-      throw new Error('No access token')
-    }
-
     return await invoke<UserProgress[]>('get_user_progress', {
       userId,
-      accessToken: token,
     })
   }
 
@@ -43,11 +43,6 @@ export class ProgressService {
       attempts?: number
     }
   ): Promise<UserProgress> {
-    const token = localStorage.getItem('access_token')
-    if (!token) {
-      throw new Error('No access token')
-    }
-
     const progressData: CreateProgressInput = {
       user_id: userId,
       lesson_id: lessonId,
@@ -60,15 +55,20 @@ export class ProgressService {
 
     return await invoke<UserProgress>('update_lesson_progress', {
       progress: progressData,
-      accessToken: token,
     })
   }
 
+  // Counted for whoever the backend's session belongs to.
+  async recordAttempt(lessonId: string): Promise<AttemptRecord> {
+    return await invoke<AttemptRecord>('record_attempt', { lessonId })
+  }
+
   async markLessonCompleted(
     userId: string,
-    lessonId: string
+    lessonId: string,
+    attempts?: number
   ): Promise<UserProgress> {
-    return this.updateLessonProgress(userId, lessonId, 'completed')
+    return this.updateLessonProgress(userId, lessonId, 'completed', { attempts })
   }
 
   async markLessonInProgress(
@@ -102,4 +102,4 @@ export class ProgressService {
 
 export const progressService = new ProgressService()
 
-export type { UserProgress, CreateProgressInput }
+export type { UserProgress, CreateProgressInput, AttemptRecord }
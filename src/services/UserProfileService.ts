@@ -22,12 +22,8 @@ interface UserStatistics {
 
 export class UserProfileService {
   async getUserProfile(userId: string): Promise<UserProfile> {
-    const token = localStorage.getItem('access_token')
-    if (!token) throw new Error('No access token')
-
     const profile = await invoke<UserProfile>('get_user_profile', {
       userId,
-      accessToken: token,
     })
 
     return {
@@ -40,31 +36,22 @@ export class UserProfileService {
   }
 
   async getUserStatistics(userId: string): Promise<UserStatistics> {
-    const token = localStorage.getItem('access_token')
-    if (!token) throw new Error('No access token')
     return await invoke<UserStatistics>('get_user_statistics', {
       userId,
-      accessToken: token,
     })
   }
 
   async updateAvatar(userId: string, avatarUrl: string): Promise<void> {
-    const token = localStorage.getItem('access_token')
-    if (!token) throw new Error('No access token')
     await invoke<void>('update_user_avatar', {
       userId,
       avatarUrl,
-      accessToken: token,
     })
   }
 
   async updateUsername(userId: string, username: string): Promise<void> {
-    const token = localStorage.getItem('access_token')
-    if (!token) throw new Error('No access token')
     await invoke<void>('update_user_username', {
       userId,
       username,
-      accessToken: token,
     })
   }
 }
@@ -0,0 +1,46 @@
+/** Mirrors `SupabaseError` in `src-tauri/src/supabase/error.rs`. */
+export type SupabaseError =
+  | { kind: 'network'; message: string }
+  | { kind: 'parse'; message: string }
+  | ({ kind: 'http'; status: number } & ErrorDetails)
+  | ({
+      kind: 'auth_expired' | 'forbidden' | 'not_found' | 'conflict' | 'validation'
+    } & ErrorDetails)
+
+export interface ErrorDetails {
+  message: string
+  /** Postgres SQLSTATE, PostgREST `PGRST…` code or GoTrue `error_code`. */
+  code: string | null
+  hint: string | null
+  details: string | null
+}
+
+export function isSupabaseError(error: unknown): error is SupabaseError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    'kind' in error &&
+    'message' in error
+  )
+}
+
+const KIND_LABELS: Record<SupabaseError['kind'], string> = {
+  network: 'Brak połączenia z serwerem',
+  parse: 'Nieoczekiwana odpowiedź serwera',
+  http: 'Błąd serwera',
+  auth_expired: 'Sesja wygasła, zaloguj się ponownie',
+  forbidden: 'Brak uprawnień',
+  not_found: 'Nie znaleziono',
+  conflict: 'Taki wpis już istnieje',
+  validation: 'Nieprawidłowe dane',
+}
+
+/** A message for the user, whatever a command rejected with. */
+export function describeError(error: unknown): string {
+  if (isSupabaseError(error)) {
+    const hint = 'hint' in error && error.hint ? ` (${error.hint})` : ''
+    return `${KIND_LABELS[error.kind]}: ${error.message}${hint}`
+  }
+  if (error instanceof Error) return error.message
+  return String(error)
+}
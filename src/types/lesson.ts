@@ -10,12 +10,46 @@ interface TheoryLesson {
   blocks: CodeBlock[]
 }
 
+interface ResourceLimits {
+  timeoutMs?: number
+  memoryMb?: number
+  cpuSeconds?: number
+  maxProcesses?: number
+  maxOutputBytes?: number
+}
+
+type ComparisonMode =
+  | 'exact'
+  | 'whitespace'
+  | 'trim_lines'
+  | 'case_insensitive'
+  | 'numeric'
+  | 'unordered_lines'
+  | 'regex'
+
+// Checks of an HTML or CSS exercise; nothing is run, the page is parsed.
+type MarkupAssertion = {
+  description?: string
+} & (
+  | { type: 'element_exists'; selector: string; count?: number; text?: string }
+  | { type: 'attribute'; selector: string; name: string; value?: string }
+  | { type: 'css_property'; selector: string; property: string; value?: string }
+  | { type: 'valid_markup' }
+)
+
+interface OutputComparison {
+  mode: ComparisonMode
+  tolerance?: number
+}
+
 interface ExerciseLesson {
   type: 'exercise'
   instruction: string
   starterCode: string
   solution: string
   hint?: string
+  // Failed attempts before `hint` is shown; 3 when not set.
+  hintAfterAttempts?: number
   exampleCode?: string
   exampleDescription?: string
   testCases?: {
@@ -23,6 +57,18 @@ interface ExerciseLesson {
     expectedOutput: string
     description?: string
   }[]
+  limits?: ResourceLimits
+  comparison?: OutputComparison
+  // Hidden unit tests run after the learner's code: `test_*` functions in
+  // Python, `test(name, fn)` calls in JavaScript and TypeScript.
+  tests?: string
+  // 'embedded' runs JavaScript on the engine built into the app even when
+  // Node.js is installed; without Node.js it is used anyway.
+  engine?: 'toolchain' | 'embedded'
+  // HTML and CSS exercises are graded by these instead of the output.
+  assertions?: MarkupAssertion[]
+  // Page a CSS exercise's stylesheet is applied to.
+  html?: string
 }
 
 interface QuizOption {
@@ -61,13 +107,28 @@ interface CreateLessonDTO {
   description?: string
   lessonType: LessonType
   content: LessonContent
-  language: 'python' | 'javascript' | 'html' | 'css' | 'typescript'
+  language: 'python' | 'javascript' | 'html' | 'css' | 'typescript' | 'rust' | 'c' | 'go'
   xpReward: number
   orderIndex: number
   isLocked?: boolean
   estimatedMinutes?: number
 }
 
+// Automatic check of one requirement, by its index in `requirements`.
+// Run and output checks share one run per distinct `stdin`; file checks
+// look at the project after the runs.
+type ProjectCheck = {
+  requirement: number
+  description?: string
+} & (
+  | { type: 'runs'; stdin?: string }
+  | { type: 'output'; stdin?: string; expectedOutput: string; comparison?: OutputComparison }
+  | { type: 'output_pattern'; stdin?: string; pattern: string }
+  | { type: 'tests'; code: string }
+  | { type: 'file_exists'; path: string }
+  | { type: 'file_contains'; path: string; pattern: string }
+)
+
 interface ProjectLesson {
   type: 'project'
   title: string
@@ -75,6 +136,11 @@ interface ProjectLesson {
   requirements: string[]
   starterCode?: string
   hints?: string[]
+  // File the program starts from; the first file of the project when unset.
+  entrypoint?: string
+  checks?: ProjectCheck[]
+  limits?: ResourceLimits
+  engine?: 'toolchain' | 'embedded'
 }
 
 type LessonContent = TheoryLesson | ExerciseLesson | QuizLesson | ProjectLesson
@@ -124,7 +190,7 @@ interface UserProgress {
 }
 type Difficulty = 'beginner' | 'intermediate' | 'advanced'
 type LessonType = 'theory' | 'exercise' | 'quiz' | 'project'
-type Language = 'python' | 'javascript' | 'html' | 'css' | 'typescript'
+type Language = 'python' | 'javascript' | 'html' | 'css' | 'typescript' | 'rust' | 'c' | 'go'
 interface UserCourseProgress {
   courseId: string
   progress: UserProgress[]
@@ -138,6 +204,7 @@ export type {
   QuizLesson,
   QuizOption,
   ProjectLesson,
+  ProjectCheck,
   LessonContent,
   Lesson,
   Module,
@@ -151,4 +218,8 @@ export type {
   Language,
   UserProgressData,
   UserCourseProgress,
+  ResourceLimits,
+  ComparisonMode,
+  OutputComparison,
+  MarkupAssertion,
 }
@@ -1,16 +1,10 @@
+// The backend holds the session tokens; the frontend only remembers who is
+// signed in.
 type Tokens = {
-  access_token?: string
-  refresh_token?: string
   user_id?: string
 }
 
 function saveAuthTokens(tokens: Tokens) {
-  if (tokens.access_token) {
-    localStorage.setItem('access_token', tokens.access_token)
-  }
-  if (tokens.refresh_token) {
-    localStorage.setItem('refresh_token', tokens.refresh_token)
-  }
   if (tokens.user_id) {
     localStorage.setItem('user_id', tokens.user_id)
   }
@@ -22,7 +16,7 @@ function clearAuthTokens() {
   localStorage.removeItem('user_id')
 }
 function isAuthenticated(): boolean {
-  return !!localStorage.getItem('access_token')
+  return !!localStorage.getItem('user_id')
 }
 
 export { saveAuthTokens, clearAuthTokens, isAuthenticated }
@@ -12,6 +12,9 @@ export const getFileNameForLanguage = (language: string): string => {
     html: 'index.html',
     css: 'styles.css',
     typescript: 'script.ts',
+    rust: 'main.rs',
+    c: 'main.c',
+    go: 'main.go',
   }
   return mapping[language] || `code.${language}`
 }